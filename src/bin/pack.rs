@@ -0,0 +1,42 @@
+//pack.rs
+
+//packs an asset directory into a single asset pack file
+//usage : pack <asset directory> <output file>
+//        pack --list <pack file>
+use rs_cli_renderer::pack::Pack;
+use std::env;
+use std::process::ExitCode;
+
+fn run(args: &[String]) -> Result<(), String> {
+    match args {
+        [flag, pack] if flag == "--list" => {
+            let pack = Pack::load(pack)?;
+            for entry in pack.entries() {
+                let status = match pack.bytes(&entry.name) {
+                    Ok(bytes) => format!("{} bytes", bytes.len()),
+                    Err(why) => why,
+                };
+                println!("{:?}\t{}\t{}", entry.kind, entry.name, status);
+            }
+            Ok(())
+        }
+        [directory, output] => {
+            let pack = Pack::from_directory(directory)?;
+            pack.save(output)
+        }
+        _ => Err(
+            "usage : pack <asset directory> <output file> | pack --list <pack file>".to_string(),
+        ),
+    }
+}
+
+fn main() -> ExitCode {
+    let args: Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(()) => ExitCode::SUCCESS,
+        Err(why) => {
+            eprintln!("{}", why);
+            ExitCode::FAILURE
+        }
+    }
+}
@@ -0,0 +1,158 @@
+//collision.rs
+use crate::sprite::{x_pos, y_pos, Sprite};
+use std::collections::HashSet;
+
+///axis aligned rectangle in worldspace, `x` and `y` are the top left corner
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: x_pos,
+    pub y: y_pos,
+    pub width: u16,
+    pub height: u16,
+}
+
+///a sprite placed in worldspace with its top left corner at (`x`,`y`), the same position
+///`Layerstack::write_sprite` takes
+#[derive(Clone, Copy)]
+pub struct Placed<'a> {
+    pub x: x_pos,
+    pub y: y_pos,
+    pub sprite: &'a Sprite,
+}
+
+///result of a pixel perfect collision
+#[derive(Clone, Debug, PartialEq)]
+pub struct Contact {
+    ///smallest rectangle holding every contact point
+    pub bounds: Rect,
+    ///worldspace positions where both sprites have a rendered pixel, in row major order
+    pub points: Vec<(x_pos, y_pos)>,
+}
+
+impl Rect {
+    pub fn new(x: x_pos, y: y_pos, width: u16, height: u16) -> Self {
+        Rect {
+            x,
+            y,
+            width,
+            height,
+        }
+    }
+    ///returns the bounding box of a sprite placed at (`x`,`y`), from `Sprite::width` and `height`
+    pub fn of_sprite(x: x_pos, y: y_pos, sprite: &Sprite) -> Self {
+        Rect::new(x, y, sprite.width, sprite.height)
+    }
+    ///returns the first column right of the rectangle
+    pub fn right(&self) -> i32 {
+        self.x as i32 + self.width as i32
+    }
+    ///returns the first row below the rectangle
+    pub fn bottom(&self) -> i32 {
+        self.y as i32 + self.height as i32
+    }
+    ///returns true for empty rectangles, which never collide
+    pub fn is_empty(&self) -> bool {
+        self.width == 0 || self.height == 0
+    }
+    ///returns true if the point lies inside the rectangle
+    pub fn contains(&self, x: x_pos, y: y_pos) -> bool {
+        x >= self.x && y >= self.y && (x as i32) < self.right() && (y as i32) < self.bottom()
+    }
+    ///returns the overlap of two rectangles, `None` when they do not overlap
+    ///rectangles that only touch along an edge do not overlap
+    ///# Example
+    ///```
+    ///let overlap = Rect::new(0,0,4,4).intersection(&Rect::new(2,3,4,4));
+    ///```
+    ///`overlap` is `Some(Rect::new(2,3,2,1))`
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let left = self.x.max(other.x);
+        let top = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if self.is_empty() || other.is_empty() || right <= left as i32 || bottom <= top as i32 {
+            return None;
+        }
+        Some(Rect::new(
+            left,
+            top,
+            (right - left as i32) as u16,
+            (bottom - top as i32) as u16,
+        ))
+    }
+}
+
+impl<'a> Placed<'a> {
+    pub fn new(x: x_pos, y: y_pos, sprite: &'a Sprite) -> Self {
+        Placed { x, y, sprite }
+    }
+    ///returns the worldspace bounding box of the sprite
+    pub fn bounds(&self) -> Rect {
+        Rect::of_sprite(self.x, self.y, self.sprite)
+    }
+    ///returns the worldspace positions of the rendered pixels of the sprite inside `area`
+    fn rendered_in(&self, area: &Rect) -> impl Iterator<Item = (x_pos, y_pos)> + '_ {
+        let area = *area;
+        self.sprite
+            .pixels
+            .iter()
+            .filter(|pixel| pixel.isrendered)
+            .map(|pixel| (pixel.x + self.x, pixel.y + self.y))
+            .filter(move |(x, y)| area.contains(*x, *y))
+    }
+}
+
+///returns the overlap of the bounding boxes of two placed sprites, `None` when they do not overlap
+///# Example
+///```
+///if let Some(overlap) = bounding_box_overlap(Placed::new(x,y,&player),Placed::new(10,4,&wall)) {
+///    x -= overlap.width as i16;
+///}
+///```
+pub fn bounding_box_overlap(a: Placed, b: Placed) -> Option<Rect> {
+    a.bounds().intersection(&b.bounds())
+}
+
+///returns where two placed sprites overlap with rendered pixels, transparent pixels
+///(`Pixel::isrendered` false) never collide
+///only the overlap of the bounding boxes is checked, so sprites far apart are cheap to test
+///# Example
+///```
+///if let Some(contact) = pixel_overlap(Placed::new(x,y,&bullet),Placed::new(ex,ey,&enemy)) {
+///    explode(contact.points[0]);
+///}
+///```
+pub fn pixel_overlap(a: Placed, b: Placed) -> Option<Contact> {
+    let area = bounding_box_overlap(a, b)?;
+    let mask: HashSet<(x_pos, y_pos)> = a.rendered_in(&area).collect();
+    let mut points: Vec<(x_pos, y_pos)> =
+        b.rendered_in(&area).filter(|point| mask.contains(point)).collect();
+    if points.is_empty() {
+        return None;
+    }
+    points.sort_by_key(|(x, y)| (*y, *x));
+    points.dedup();
+    let left = points.iter().map(|(x, _)| *x).min().unwrap();
+    let right = points.iter().map(|(x, _)| *x).max().unwrap();
+    let top = points[0].1;
+    let bottom = points[points.len() - 1].1;
+    Some(Contact {
+        bounds: Rect::new(
+            left,
+            top,
+            (right - left) as u16 + 1,
+            (bottom - top) as u16 + 1,
+        ),
+        points,
+    })
+}
+
+///returns true if two placed sprites overlap with rendered pixels, see `pixel_overlap`
+///stops at the first shared pixel
+pub fn pixels_collide(a: Placed, b: Placed) -> bool {
+    let Some(area) = bounding_box_overlap(a, b) else {
+        return false;
+    };
+    let mask: HashSet<(x_pos, y_pos)> = a.rendered_in(&area).collect();
+    b.rendered_in(&area).any(|point| mask.contains(&point))
+}
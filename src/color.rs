@@ -0,0 +1,500 @@
+//color.rs
+use crossterm::style::Color;
+
+///RGB triplet used for color math
+pub type Rgb = (u8, u8, u8);
+
+///defines how a layer's pixels are combined with the pixels already in the framebuffer
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BlendMode {
+    ///source replaces destination (weighted by alpha)
+    Normal,
+    ///source is added to destination, clamped to white
+    Additive,
+    ///source and destination are multiplied, always darkens
+    Multiply,
+    ///inverse of multiply, always lightens
+    Screen,
+}
+
+///color levels of the 6x6x6 cube in the 256 color ANSI palette
+const CUBE_LEVELS: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+///RGB values of the 16 named terminal colors in ANSI order
+pub const ANSI_16: [Rgb; 16] = [
+    (0, 0, 0),
+    (128, 0, 0),
+    (0, 128, 0),
+    (128, 128, 0),
+    (0, 0, 128),
+    (128, 0, 128),
+    (0, 128, 128),
+    (192, 192, 192),
+    (128, 128, 128),
+    (255, 0, 0),
+    (0, 255, 0),
+    (255, 255, 0),
+    (0, 0, 255),
+    (255, 0, 255),
+    (0, 255, 255),
+    (255, 255, 255),
+];
+
+///converts any `Color` into an RGB triplet
+///named colors use the standard xterm values, `Color::Reset` is treated as black
+///# Example
+///```
+///to_rgb(Color::DarkGrey);
+///```
+///returns (128,128,128)
+pub fn to_rgb(color: Color) -> Rgb {
+    match color {
+        Color::Rgb { r, g, b } => (r, g, b),
+        Color::AnsiValue(value) => ansi_to_rgb(value),
+        Color::Reset | Color::Black => ANSI_16[0],
+        Color::DarkRed => ANSI_16[1],
+        Color::DarkGreen => ANSI_16[2],
+        Color::DarkYellow => ANSI_16[3],
+        Color::DarkBlue => ANSI_16[4],
+        Color::DarkMagenta => ANSI_16[5],
+        Color::DarkCyan => ANSI_16[6],
+        Color::Grey => ANSI_16[7],
+        Color::DarkGrey => ANSI_16[8],
+        Color::Red => ANSI_16[9],
+        Color::Green => ANSI_16[10],
+        Color::Yellow => ANSI_16[11],
+        Color::Blue => ANSI_16[12],
+        Color::Magenta => ANSI_16[13],
+        Color::Cyan => ANSI_16[14],
+        Color::White => ANSI_16[15],
+    }
+}
+
+///converts an index of the 256 color ANSI palette into an RGB triplet
+fn ansi_to_rgb(value: u8) -> Rgb {
+    match value {
+        0..=15 => ANSI_16[value as usize],
+        16..=231 => {
+            let index = value - 16;
+            (
+                CUBE_LEVELS[(index / 36) as usize],
+                CUBE_LEVELS[((index / 6) % 6) as usize],
+                CUBE_LEVELS[(index % 6) as usize],
+            )
+        }
+        _ => {
+            let level = 8 + (value - 232) * 10;
+            (level, level, level)
+        }
+    }
+}
+
+///blends `src` on top of `dst`
+///# Parameters
+///- `dst` : color already in the framebuffer
+///- `src` : color being written
+///- `alpha` : strength of `src` from 0.0 (invisible) to 1.0 (opaque)
+///- `mode` : blend mode used to combine the colors
+///# Example
+///```
+///blend(Color::Black, Color::White, 0.5, BlendMode::Normal);
+///```
+///returns a grey halfway between black and white
+///
+///opaque normal blending returns `src` untouched so named colors survive compositing
+pub fn blend(dst: Color, src: Color, alpha: f32, mode: BlendMode) -> Color {
+    let alpha = alpha.clamp(0.0, 1.0);
+    if alpha == 0.0 {
+        return dst;
+    }
+    if alpha == 1.0 && mode == BlendMode::Normal {
+        return src;
+    }
+    let (dr, dg, db) = to_rgb(dst);
+    let (sr, sg, sb) = to_rgb(src);
+    let mix = |d: u8, s: u8| -> u8 {
+        let (d, s) = (d as f32, s as f32);
+        let blended = match mode {
+            BlendMode::Normal => s,
+            BlendMode::Additive => (d + s).min(255.0),
+            BlendMode::Multiply => d * s / 255.0,
+            BlendMode::Screen => 255.0 - (255.0 - d) * (255.0 - s) / 255.0,
+        };
+        (d + (blended - d) * alpha).round() as u8
+    };
+    Color::Rgb {
+        r: mix(dr, sr),
+        g: mix(dg, sg),
+        b: mix(db, sb),
+    }
+}
+
+///color capability of the terminal the renderer presents to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorDepth {
+    ///24 bit color, every color is presented as is
+    TrueColor,
+    ///256 color ANSI palette
+    Ansi256,
+    ///16 named terminal colors
+    Ansi16,
+    ///black and white only
+    Monochrome,
+}
+
+///4x4 bayer threshold matrix used for ordered dithering
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
+///named colors in ANSI order, paired with `ANSI_16`
+pub(crate) const NAMED_16: [Color; 16] = [
+    Color::Black,
+    Color::DarkRed,
+    Color::DarkGreen,
+    Color::DarkYellow,
+    Color::DarkBlue,
+    Color::DarkMagenta,
+    Color::DarkCyan,
+    Color::Grey,
+    Color::DarkGrey,
+    Color::Red,
+    Color::Green,
+    Color::Yellow,
+    Color::Blue,
+    Color::Magenta,
+    Color::Cyan,
+    Color::White,
+];
+
+impl ColorDepth {
+    ///detects the color capability of the current terminal from the environment
+    ///`NO_COLOR` and `TERM=dumb` force monochrome, otherwise `COLORTERM` and `TERM` are inspected
+    ///# Example
+    ///```
+    ///let depth = ColorDepth::detect();
+    ///```
+    ///depth is `ColorDepth::TrueColor` when `COLORTERM=truecolor`
+    pub fn detect() -> Self {
+        if std::env::var_os("NO_COLOR").is_some() {
+            return ColorDepth::Monochrome;
+        }
+        let term = std::env::var("TERM").unwrap_or_default();
+        if term == "dumb" {
+            return ColorDepth::Monochrome;
+        }
+        let colorterm = std::env::var("COLORTERM").unwrap_or_default();
+        if colorterm.contains("truecolor") || colorterm.contains("24bit") {
+            ColorDepth::TrueColor
+        } else if term.contains("256") {
+            ColorDepth::Ansi256
+        } else {
+            match crossterm::style::available_color_count() {
+                u16::MAX => ColorDepth::TrueColor,
+                256 => ColorDepth::Ansi256,
+                _ => ColorDepth::Ansi16,
+            }
+        }
+    }
+    ///distance between neighbouring colors of this depth, used to scale dithering noise
+    fn step(&self) -> f32 {
+        match self {
+            ColorDepth::TrueColor => 0.0,
+            ColorDepth::Ansi256 => 40.0,
+            ColorDepth::Ansi16 => 128.0,
+            ColorDepth::Monochrome => 255.0,
+        }
+    }
+}
+
+///squared distance between two RGB triplets
+fn distance(a: Rgb, b: Rgb) -> u32 {
+    let dr = a.0 as i32 - b.0 as i32;
+    let dg = a.1 as i32 - b.1 as i32;
+    let db = a.2 as i32 - b.2 as i32;
+    (dr * dr + dg * dg + db * db) as u32
+}
+
+///index of the closest level in the 256 color cube
+fn nearest_cube_level(value: u8) -> u8 {
+    match value {
+        0..=47 => 0,
+        48..=114 => 1,
+        _ => (value - 35) / 40,
+    }
+}
+
+///returns the closest color of the 256 color palette (cube or grayscale ramp)
+fn nearest_256(rgb: Rgb) -> Color {
+    let (r, g, b) = (
+        nearest_cube_level(rgb.0),
+        nearest_cube_level(rgb.1),
+        nearest_cube_level(rgb.2),
+    );
+    let cube_index = 16 + 36 * r + 6 * g + b;
+    let average = (rgb.0 as u16 + rgb.1 as u16 + rgb.2 as u16) / 3;
+    let gray_index = if average < 8 {
+        232
+    } else {
+        (232 + (average - 8 + 5) / 10).min(255) as u8
+    };
+    if distance(ansi_to_rgb(gray_index), rgb) < distance(ansi_to_rgb(cube_index), rgb) {
+        Color::AnsiValue(gray_index)
+    } else {
+        Color::AnsiValue(cube_index)
+    }
+}
+
+///returns the closest of the 16 named colors
+fn nearest_16(rgb: Rgb) -> Color {
+    let mut closest = 0;
+    for (index, candidate) in ANSI_16.iter().enumerate() {
+        if distance(*candidate, rgb) < distance(ANSI_16[closest], rgb) {
+            closest = index;
+        }
+    }
+    NAMED_16[closest]
+}
+
+///quantizes a color to the nearest color supported by `depth`
+///# Parameters
+///- `color` : color to quantize
+///- `depth` : color capability of the target terminal
+///# Example
+///```
+///quantize(Color::Rgb{r:250,g:10,b:10},ColorDepth::Ansi16);
+///```
+///returns `Color::Red`
+pub fn quantize(color: Color, depth: ColorDepth) -> Color {
+    match (depth, color) {
+        (ColorDepth::TrueColor, _) => color,
+        //named colors are valid in every palette but monochrome
+        (ColorDepth::Ansi256, Color::Rgb { .. }) => nearest_256(to_rgb(color)),
+        (ColorDepth::Ansi256, _) => color,
+        (ColorDepth::Ansi16, _) => nearest_16(to_rgb(color)),
+        (ColorDepth::Monochrome, _) => {
+            let (r, g, b) = to_rgb(color);
+            let luma = 0.299 * r as f32 + 0.587 * g as f32 + 0.114 * b as f32;
+            if luma >= 128.0 {
+                Color::White
+            } else {
+                Color::Black
+            }
+        }
+    }
+}
+
+///quantizes a color using ordered (bayer) dithering
+///the pixel position selects a threshold from the bayer matrix which nudges the color
+///before quantization, so flat areas between two palette colors become a fine checker pattern
+///# Parameters
+///- `color` : color to quantize
+///- `depth` : color capability of the target terminal
+///- `x` : x position of the pixel
+///- `y` : y position of the pixel
+pub fn quantize_ordered(color: Color, depth: ColorDepth, x: u16, y: u16) -> Color {
+    if depth == ColorDepth::TrueColor {
+        return color;
+    }
+    let threshold = BAYER_4X4[(y % 4) as usize][(x % 4) as usize] as f32 / 16.0 - 0.5;
+    let offset = threshold * depth.step();
+    let (r, g, b) = to_rgb(color);
+    let nudge = |value: u8| (value as f32 + offset).round().clamp(0.0, 255.0) as u8;
+    quantize(
+        Color::Rgb {
+            r: nudge(r),
+            g: nudge(g),
+            b: nudge(b),
+        },
+        depth,
+    )
+}
+
+///dithering algorithm used when quantizing to a limited color depth
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Dither {
+    ///plain nearest color quantization
+    None,
+    ///4x4 bayer matrix, stable between frames and cheap
+    Ordered,
+    ///floyd-steinberg error diffusion, smoother gradients but noisier in motion
+    FloydSteinberg,
+}
+
+///quantizes a flattened grid of colors in place using the chosen dithering
+///# Parameters
+///- `colors` : row major grid of colors
+///- `width` : length of a row of the grid
+///- `depth` : color depth to quantize to
+///- `dither` : dithering algorithm
+///- `mask` : only colors whose entry is `true` are quantized, errors never diffuse outside the mask
+///# Example
+///```
+///dither(&mut buffer,50,ColorDepth::Ansi16,Dither::FloydSteinberg,&mask);
+///```
+///quantizes every masked color of the 50 wide buffer to the 16 named colors
+pub fn dither(
+    colors: &mut [Color],
+    width: usize,
+    depth: ColorDepth,
+    dither: Dither,
+    mask: &[bool],
+) {
+    if depth == ColorDepth::TrueColor {
+        return;
+    }
+    match dither {
+        Dither::None => {
+            for (index, color) in colors.iter_mut().enumerate() {
+                if mask[index] {
+                    *color = quantize(*color, depth);
+                }
+            }
+        }
+        Dither::Ordered => {
+            for (index, color) in colors.iter_mut().enumerate() {
+                if mask[index] {
+                    let (x, y) = ((index % width) as u16, (index / width) as u16);
+                    *color = quantize_ordered(*color, depth, x, y);
+                }
+            }
+        }
+        Dither::FloydSteinberg => floyd_steinberg(colors, width, depth, mask),
+    }
+}
+
+///floyd-steinberg error diffusion, see `dither`
+fn floyd_steinberg(colors: &mut [Color], width: usize, depth: ColorDepth, mask: &[bool]) {
+    let mut error: Vec<[f32; 3]> = vec![[0.0; 3]; colors.len()];
+    for index in 0..colors.len() {
+        if !mask[index] {
+            continue;
+        }
+        let (r, g, b) = to_rgb(colors[index]);
+        let wanted = [
+            r as f32 + error[index][0],
+            g as f32 + error[index][1],
+            b as f32 + error[index][2],
+        ];
+        let clamped = |value: f32| value.round().clamp(0.0, 255.0) as u8;
+        let quantized = quantize(
+            Color::Rgb {
+                r: clamped(wanted[0]),
+                g: clamped(wanted[1]),
+                b: clamped(wanted[2]),
+            },
+            depth,
+        );
+        let (qr, qg, qb) = to_rgb(quantized);
+        let residual = [
+            wanted[0] - qr as f32,
+            wanted[1] - qg as f32,
+            wanted[2] - qb as f32,
+        ];
+        colors[index] = quantized;
+
+        let x = index % width;
+        //(neighbour offset, weight) of the classic 7/3/5/1 kernel
+        let mut neighbours: Vec<(usize, f32)> = vec![];
+        if x + 1 < width {
+            neighbours.push((index + 1, 7.0 / 16.0));
+        }
+        if x > 0 {
+            neighbours.push((index + width - 1, 3.0 / 16.0));
+        }
+        neighbours.push((index + width, 5.0 / 16.0));
+        if x + 1 < width {
+            neighbours.push((index + width + 1, 1.0 / 16.0));
+        }
+        for (neighbour, weight) in neighbours {
+            if neighbour < colors.len() && mask[neighbour] {
+                for channel in 0..3 {
+                    error[neighbour][channel] += residual[channel] * weight;
+                }
+            }
+        }
+    }
+}
+
+///parses a color from text
+///accepts named colors (`Red`, `darkgrey`, ...), hex triplets (`#ff8000`) and 256 color palette indices (`208`)
+///# Example
+///```
+///parse_color("#ff8000");
+///```
+///returns `Color::Rgb{r:255,g:128,b:0}`
+pub fn parse_color(text: &str) -> Result<Color, String> {
+    if let Some(hex) = text.strip_prefix('#') {
+        let channel = |index: usize| {
+            hex.get(index..index + 2)
+                .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+                .ok_or(format!("invalid hex color : {}", text))
+        };
+        if hex.len() != 6 {
+            return Err(format!("invalid hex color : {}", text));
+        }
+        return Ok(Color::Rgb {
+            r: channel(0)?,
+            g: channel(2)?,
+            b: channel(4)?,
+        });
+    }
+    if let Ok(value) = text.parse::<u8>() {
+        return Ok(Color::AnsiValue(value));
+    }
+    let name = text.to_lowercase().replace(['_', '-'], "");
+    if name == "reset" {
+        return Ok(Color::Reset);
+    }
+    NAMED_16
+        .iter()
+        .find(|named| format!("{:?}", named).to_lowercase() == name)
+        .copied()
+        .ok_or(format!("unknown color : {}", text))
+}
+
+///named colors shared between sprites, loaded from a palette file
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Palette {
+    colors: Vec<(String, Color)>,
+}
+
+impl Palette {
+    ///parses a palette file, one `name color` pair per line (see `parse_color`),
+    ///lines starting with `//` are comments
+    ///# Example
+    ///```
+    ///// night
+    ///sky #101030
+    ///grass DarkGreen
+    ///```
+    pub fn parse(text: &str) -> Result<Self, String> {
+        let mut palette = Palette::default();
+        for (number, line) in text.lines().enumerate() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with("//") {
+                continue;
+            }
+            let (name, color) = line
+                .split_once(char::is_whitespace)
+                .ok_or(format!("line {} : expected a name and a color", number + 1))?;
+            let color = parse_color(color.trim())
+                .map_err(|why| format!("line {} : {}", number + 1, why))?;
+            palette.set(name, color);
+        }
+        Ok(palette)
+    }
+    ///returns the color called `name`
+    pub fn get(&self, name: &str) -> Option<Color> {
+        self.colors.iter().find(|(existing, _)| existing == name).map(|(_, color)| *color)
+    }
+    ///sets the color called `name`, adding it if the palette does not have it yet
+    pub fn set(&mut self, name: &str, color: Color) {
+        match self.colors.iter_mut().find(|(existing, _)| existing == name) {
+            Some((_, existing)) => *existing = color,
+            None => self.colors.push((name.to_string(), color)),
+        }
+    }
+    ///returns every (name, color) pair in file order
+    pub fn colors(&self) -> &[(String, Color)] {
+        &self.colors
+    }
+}
@@ -0,0 +1,459 @@
+//draw.rs
+use crate::sprite::{x_pos, y_pos, Pixel};
+use crate::stack::{LayerID, Layerstack, SpriteVector};
+use crossterm::style::Color;
+use std::collections::HashSet;
+
+///inclusive bounds shapes are clipped to before their pixels are created
+#[derive(Clone, Copy)]
+struct Clip {
+    left: i32,
+    top: i32,
+    right: i32,
+    bottom: i32,
+}
+
+impl Clip {
+    ///every position a pixel can hold, used by the free shape functions
+    const WORLD: Clip = Clip {
+        left: x_pos::MIN as i32,
+        top: y_pos::MIN as i32,
+        right: x_pos::MAX as i32,
+        bottom: y_pos::MAX as i32,
+    };
+    ///returns the bounds of a framebuffer, used by the Layerstack wrappers
+    fn screen((width, height): (u16, u16)) -> Clip {
+        Clip {
+            left: 0,
+            top: 0,
+            right: width as i32 - 1,
+            bottom: height as i32 - 1,
+        }
+    }
+    fn contains(&self, x: i32, y: i32) -> bool {
+        (self.left..=self.right).contains(&x) && (self.top..=self.bottom).contains(&y)
+    }
+}
+
+///pushes an opaque pixel, positions outside the clip are dropped instead of wrapping around
+fn push(pixels: &mut SpriteVector, clip: Clip, x: i32, y: i32, color: Color) {
+    if clip.contains(x, y) {
+        pixels.push(Pixel {
+            x: x as x_pos,
+            y: y as y_pos,
+            color,
+            isrendered: true,
+            alpha: 255,
+        });
+    }
+}
+
+///removes pixels sharing a position with an earlier pixel
+///shapes whose parts overlap would otherwise blend the shared pixels twice
+fn dedup(pixels: SpriteVector) -> SpriteVector {
+    let mut seen: HashSet<(x_pos, y_pos)> = HashSet::new();
+    pixels.into_iter().filter(|pixel| seen.insert((pixel.x, pixel.y))).collect()
+}
+
+///returns the pixels of a line between two points, both ends included (Bresenham)
+///# Example
+///```
+///let diagonal : SpriteVector = line(0,0,4,4,Color::White);
+///```
+pub fn line(x0: x_pos, y0: y_pos, x1: x_pos, y1: y_pos, color: Color) -> SpriteVector {
+    clipped_line(
+        x0 as i32,
+        y0 as i32,
+        x1 as i32,
+        y1 as i32,
+        color,
+        Clip::WORLD,
+    )
+}
+
+fn clipped_line(x0: i32, y0: i32, x1: i32, y1: i32, color: Color, clip: Clip) -> SpriteVector {
+    let (mut x, mut y) = (x0, y0);
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let step_x = if x < x1 { 1 } else { -1 };
+    let step_y = if y < y1 { 1 } else { -1 };
+    let mut error = dx + dy;
+    let mut pixels: SpriteVector = vec![];
+    loop {
+        push(&mut pixels, clip, x, y, color);
+        if x == x1 && y == y1 {
+            return pixels;
+        }
+        let doubled = 2 * error;
+        if doubled >= dy {
+            error += dy;
+            x += step_x;
+        }
+        if doubled <= dx {
+            error += dx;
+            y += step_y;
+        }
+    }
+}
+
+///returns the pixels of a rectangle
+///# Parameters
+///- `x` : worldspace x position of the left edge
+///- `y` : worldspace y position of the top edge
+///- `width` : width in pixels, nothing is drawn for 0
+///- `height` : height in pixels, nothing is drawn for 0
+///- `color` : color of the rectangle
+///- `filled` : fills the inside when true, only draws the outline otherwise
+pub fn rect(
+    x: x_pos,
+    y: y_pos,
+    width: u16,
+    height: u16,
+    color: Color,
+    filled: bool,
+) -> SpriteVector {
+    clipped_rect(x, y, width, height, color, filled, Clip::WORLD)
+}
+
+fn clipped_rect(
+    x: x_pos,
+    y: y_pos,
+    width: u16,
+    height: u16,
+    color: Color,
+    filled: bool,
+    clip: Clip,
+) -> SpriteVector {
+    let (left, top) = (x as i32, y as i32);
+    let (right, bottom) = (left + width as i32 - 1, top + height as i32 - 1);
+    let mut pixels: SpriteVector = vec![];
+    for row in top.max(clip.top)..=bottom.min(clip.bottom) {
+        let edge = row == top || row == bottom;
+        for column in left.max(clip.left)..=right.min(clip.right) {
+            if filled || edge || column == left || column == right {
+                push(&mut pixels, clip, column, row, color);
+            }
+        }
+    }
+    pixels
+}
+
+///returns the positions of the quarter of an ellipse with x and y >= 0 (midpoint algorithm)
+fn ellipse_quadrant(radius_x: i32, radius_y: i32) -> Vec<(i32, i32)> {
+    let (rx2, ry2) = ((radius_x as f64).powi(2), (radius_y as f64).powi(2));
+    let (mut x, mut y) = (0, radius_y);
+    let mut points = vec![(x, y)];
+    //region 1, slope shallower than -1
+    let mut decision = ry2 - rx2 * radius_y as f64 + rx2 / 4.0;
+    while ry2 * (x as f64) < rx2 * (y as f64) {
+        x += 1;
+        if decision < 0.0 {
+            decision += ry2 * (2 * x + 1) as f64;
+        } else {
+            y -= 1;
+            decision += ry2 * (2 * x + 1) as f64 - 2.0 * rx2 * y as f64;
+        }
+        points.push((x, y));
+    }
+    //region 2, slope steeper than -1
+    let mut decision = ry2 * (x as f64 + 0.5).powi(2) + rx2 * ((y - 1) as f64).powi(2) - rx2 * ry2;
+    while y > 0 {
+        y -= 1;
+        if decision > 0.0 {
+            decision += rx2 * (1 - 2 * y) as f64;
+        } else {
+            x += 1;
+            decision += ry2 * (2 * x) as f64 + rx2 * (1 - 2 * y) as f64;
+        }
+        points.push((x, y));
+    }
+    points
+}
+
+///returns the pixels of an ellipse
+///# Parameters
+///- `center_x` : worldspace x position of the center
+///- `center_y` : worldspace y position of the center
+///- `radius_x` : horizontal radius in pixels
+///- `radius_y` : vertical radius in pixels
+///- `color` : color of the ellipse
+///- `filled` : fills the inside when true, only draws the outline otherwise
+pub fn ellipse(
+    center_x: x_pos,
+    center_y: y_pos,
+    radius_x: u16,
+    radius_y: u16,
+    color: Color,
+    filled: bool,
+) -> SpriteVector {
+    clipped_ellipse(
+        center_x,
+        center_y,
+        radius_x,
+        radius_y,
+        color,
+        filled,
+        Clip::WORLD,
+    )
+}
+
+fn clipped_ellipse(
+    center_x: x_pos,
+    center_y: y_pos,
+    radius_x: u16,
+    radius_y: u16,
+    color: Color,
+    filled: bool,
+    clip: Clip,
+) -> SpriteVector {
+    let (center_x, center_y) = (center_x as i32, center_y as i32);
+    let (radius_x, radius_y) = (radius_x as i32, radius_y as i32);
+    //flat ellipses are lines
+    if radius_x == 0 || radius_y == 0 {
+        return clipped_line(
+            center_x - radius_x,
+            center_y - radius_y,
+            center_x + radius_x,
+            center_y + radius_y,
+            color,
+            clip,
+        );
+    }
+    let quadrant = ellipse_quadrant(radius_x, radius_y);
+    let mut pixels: SpriteVector = vec![];
+    if filled {
+        //widest extent of every row, mirrored into a horizontal span
+        let mut spans = vec![0; radius_y as usize + 1];
+        for (x, y) in quadrant {
+            spans[y as usize] = spans[y as usize].max(x);
+        }
+        for (y, span) in spans.iter().enumerate() {
+            let (start, end) = (
+                (center_x - span).max(clip.left),
+                (center_x + span).min(clip.right),
+            );
+            for row in [center_y + y as i32, center_y - y as i32] {
+                if (clip.top..=clip.bottom).contains(&row) {
+                    for column in start..=end {
+                        push(&mut pixels, clip, column, row, color);
+                    }
+                }
+            }
+        }
+    } else {
+        for (x, y) in quadrant {
+            for (sign_x, sign_y) in [(1, 1), (-1, 1), (1, -1), (-1, -1)] {
+                push(
+                    &mut pixels,
+                    clip,
+                    center_x + sign_x * x,
+                    center_y + sign_y * y,
+                    color,
+                );
+            }
+        }
+    }
+    dedup(pixels)
+}
+
+///returns the pixels of a circle, see `ellipse`
+pub fn circle(
+    center_x: x_pos,
+    center_y: y_pos,
+    radius: u16,
+    color: Color,
+    filled: bool,
+) -> SpriteVector {
+    ellipse(center_x, center_y, radius, radius, color, filled)
+}
+
+///returns the pixels of a closed polygon
+///# Parameters
+///- `points` : worldspace corners in drawing order, the last corner connects back to the first
+///- `color` : color of the polygon
+///- `filled` : fills the inside (even odd rule) when true, only draws the outline otherwise
+///# Example
+///```
+///let triangle : SpriteVector = polygon(&[(0,0),(8,0),(4,6)],Color::Red,true);
+///```
+pub fn polygon(points: &[(x_pos, y_pos)], color: Color, filled: bool) -> SpriteVector {
+    clipped_polygon(points, color, filled, Clip::WORLD)
+}
+
+fn clipped_polygon(
+    points: &[(x_pos, y_pos)],
+    color: Color,
+    filled: bool,
+    clip: Clip,
+) -> SpriteVector {
+    let mut pixels: SpriteVector = vec![];
+    let edges: Vec<((x_pos, y_pos), (x_pos, y_pos))> = points
+        .iter()
+        .zip(points.iter().cycle().skip(1))
+        .map(|(start, end)| (*start, *end))
+        .collect();
+    for (start, end) in edges.iter() {
+        pixels.extend(clipped_line(
+            start.0 as i32,
+            start.1 as i32,
+            end.0 as i32,
+            end.1 as i32,
+            color,
+            clip,
+        ));
+    }
+    if filled && points.len() > 2 {
+        let top = points.iter().map(|point| point.1).min().unwrap_or(0) as i32;
+        let bottom = points.iter().map(|point| point.1).max().unwrap_or(0) as i32;
+        for row in top.max(clip.top)..=bottom.min(clip.bottom) {
+            //edges are sampled through the pixel centers
+            let sample = row as f64 + 0.5;
+            let mut crossings: Vec<f64> = vec![];
+            for ((x0, y0), (x1, y1)) in edges.iter() {
+                let (x0, y0, x1, y1) = (*x0 as f64, *y0 as f64, *x1 as f64, *y1 as f64);
+                if (y0 <= sample) != (y1 <= sample) {
+                    crossings.push(x0 + (sample - y0) * (x1 - x0) / (y1 - y0));
+                }
+            }
+            crossings.sort_by(|a, b| a.total_cmp(b));
+            for span in crossings.chunks_exact(2) {
+                let (start, end) = (
+                    ((span[0] - 0.5).ceil() as i32).max(clip.left),
+                    ((span[1] - 0.5).floor() as i32).min(clip.right),
+                );
+                for column in start..=end {
+                    push(&mut pixels, clip, column, row, color);
+                }
+            }
+        }
+    }
+    dedup(pixels)
+}
+
+impl Layerstack {
+    ///draws a line onto the target layer, see `draw::line`
+    ///# Example
+    ///```
+    ///layer_draw_line(0,0,10,5,Color::White,1);
+    ///```
+    ///draws a white line from (0,0) to (10,5) on layer 1
+    pub fn draw_line(
+        &mut self,
+        x0: x_pos,
+        y0: y_pos,
+        x1: x_pos,
+        y1: y_pos,
+        color: Color,
+        layer_id: LayerID,
+    ) {
+        let clip = Clip::screen(self.size());
+        let pixels = clipped_line(x0 as i32, y0 as i32, x1 as i32, y1 as i32, color, clip);
+        self.write_pixels(pixels, layer_id);
+    }
+    ///draws a rectangle onto the target layer, see `draw::rect`
+    ///# Example
+    ///```
+    ///layer_draw_rect(2,2,10,4,Color::Blue,false,1);
+    ///```
+    ///draws the outline of a 10x4 rectangle with its top left corner at (2,2) on layer 1
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_rect(
+        &mut self,
+        x: x_pos,
+        y: y_pos,
+        width: u16,
+        height: u16,
+        color: Color,
+        filled: bool,
+        layer_id: LayerID,
+    ) {
+        let clip = Clip::screen(self.size());
+        let pixels = clipped_rect(x, y, width, height, color, filled, clip);
+        self.write_pixels(pixels, layer_id);
+    }
+    ///draws a circle onto the target layer, see `draw::ellipse`
+    ///# Example
+    ///```
+    ///layer_draw_circle(20,10,4,Color::Yellow,true,1);
+    ///```
+    ///draws a filled circle with a radius of 4 centered on (20,10) on layer 1
+    pub fn draw_circle(
+        &mut self,
+        center_x: x_pos,
+        center_y: y_pos,
+        radius: u16,
+        color: Color,
+        filled: bool,
+        layer_id: LayerID,
+    ) {
+        self.draw_ellipse(center_x, center_y, radius, radius, color, filled, layer_id);
+    }
+    ///draws an ellipse onto the target layer, see `draw::ellipse`
+    #[allow(clippy::too_many_arguments)]
+    pub fn draw_ellipse(
+        &mut self,
+        center_x: x_pos,
+        center_y: y_pos,
+        radius_x: u16,
+        radius_y: u16,
+        color: Color,
+        filled: bool,
+        layer_id: LayerID,
+    ) {
+        let clip = Clip::screen(self.size());
+        let pixels = clipped_ellipse(center_x, center_y, radius_x, radius_y, color, filled, clip);
+        self.write_pixels(pixels, layer_id);
+    }
+    ///draws a closed polygon onto the target layer, see `draw::polygon`
+    pub fn draw_polygon(
+        &mut self,
+        points: &[(x_pos, y_pos)],
+        color: Color,
+        filled: bool,
+        layer_id: LayerID,
+    ) {
+        let clip = Clip::screen(self.size());
+        self.write_pixels(clipped_polygon(points, color, filled, clip), layer_id);
+    }
+    ///fills the area around (`x`,`y`) on the target layer
+    ///the area spreads to neighbouring pixels (no diagonals) of the same color as (`x`,`y`),
+    ///pixels not yet drawn this frame count as one color, it is bounded by the framebuffer edges
+    ///only the target layer is considered, so shapes have to be drawn on it before filling
+    ///# Parameters
+    ///- `x` : worldspace x position to start filling from
+    ///- `y` : worldspace y position to start filling from
+    ///- `color` : color to fill with
+    ///- `layer_id` : target layer
+    ///# Example
+    ///```
+    ///layer_draw_rect(0,0,8,8,Color::White,false,1);
+    ///layer_flood_fill(4,4,Color::Blue,1);
+    ///```
+    ///fills the inside of the white outline with blue
+    pub fn flood_fill(&mut self, x: x_pos, y: y_pos, color: Color, layer_id: LayerID) {
+        println!("flood filling layer (id : {})", layer_id);
+        let (width, height) = self.size();
+        if !(0..width as x_pos).contains(&x) || !(0..height as y_pos).contains(&y) {
+            return;
+        }
+        let colors = self.layer_colors(layer_id);
+        let index = |x: i32, y: i32| y as usize * width as usize + x as usize;
+        let target = colors[index(x as i32, y as i32)];
+        let mut visited = vec![false; colors.len()];
+        let mut pixels: SpriteVector = vec![];
+        let mut pending: Vec<(i32, i32)> = vec![(x as i32, y as i32)];
+        while let Some((x, y)) = pending.pop() {
+            if x < 0 || y < 0 || x >= width as i32 || y >= height as i32 {
+                continue;
+            }
+            let position = index(x, y);
+            if visited[position] || colors[position] != target {
+                continue;
+            }
+            visited[position] = true;
+            push(&mut pixels, Clip::WORLD, x, y, color);
+            pending.extend([(x + 1, y), (x - 1, y), (x, y + 1), (x, y - 1)]);
+        }
+        self.write_pixels(pixels, layer_id);
+    }
+}
@@ -0,0 +1,206 @@
+//export.rs
+use crate::color;
+use crate::stack::{FrameBuffer, TextCell};
+use crossterm::style::{Color, Stylize};
+use std::collections::HashMap;
+
+///text formats a frame can be exported to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ScreenshotFormat {
+    ///text file with ANSI escape codes, `cat` it in a terminal to view
+    Ansi,
+    ///standalone HTML page with colored spans
+    Html,
+    ///SVG image of colored rectangles
+    Svg,
+}
+
+///size of one framebuffer pixel in SVG user units
+const SVG_PIXEL_SIZE: usize = 10;
+
+///returns the color as a `#rrggbb` hex string
+fn hex(pixel_color: Color) -> String {
+    let (r, g, b) = color::to_rgb(pixel_color);
+    format!("#{:02x}{:02x}{:02x}", r, g, b)
+}
+
+///a run of terminal columns drawn in the same colors
+struct Run {
+    text: String,
+    ///color of the characters, `None` for blank columns of non rendered pixels
+    fg: Option<Color>,
+    ///color behind the characters, `None` for plain pixels
+    bg: Option<Color>,
+}
+
+///groups each row of the framebuffer into runs of terminal columns drawn in the same colors
+///a pixel takes one column per character of `pixel_element`,
+///with `with_text` the text cells of the frame replace the columns they sit on
+fn runs(framebuffer: &FrameBuffer, pixel_element: &str, with_text: bool) -> Vec<Vec<Run>> {
+    let cells: HashMap<(u16, u16), &TextCell> = match with_text {
+        true => {
+            framebuffer.text().into_iter().map(|cell| ((cell.column, cell.row), cell)).collect()
+        }
+        false => HashMap::new(),
+    };
+    let element_width = pixel_element.chars().count() as u16;
+    let mut rows: Vec<Vec<Run>> = vec![];
+    for y in 0..framebuffer.height() {
+        let mut row_runs: Vec<Run> = vec![];
+        for x in 0..framebuffer.width() {
+            let pixel = match framebuffer.get(x, y) {
+                Some(pixel) => pixel,
+                None => continue,
+            };
+            let pixel_color = if pixel.isrendered {
+                Some(pixel.color)
+            } else {
+                None
+            };
+            for (offset, element) in pixel_element.chars().enumerate() {
+                let column = x * element_width + offset as u16;
+                let (character, fg, bg) = match cells.get(&(column, y)) {
+                    Some(cell) => (
+                        cell.character,
+                        Some(cell.fg),
+                        Some(cell.bg.or(pixel_color).unwrap_or(Color::Reset)),
+                    ),
+                    None if pixel_color.is_some() => (element, pixel_color, None),
+                    None => (' ', None, None),
+                };
+                match row_runs.last_mut() {
+                    Some(run) if run.fg == fg && run.bg == bg => run.text.push(character),
+                    _ => row_runs.push(Run {
+                        text: character.to_string(),
+                        fg,
+                        bg,
+                    }),
+                }
+            }
+        }
+        rows.push(row_runs);
+    }
+    rows
+}
+
+///serializes a frame to text with ANSI escape codes, one line per row
+///# Parameters
+///- `framebuffer` : frame to serialize
+///- `pixel_element` : string each pixel is drawn as
+///# Example
+///```
+///let text = frame_to_ansi(renderer.last_frame(),"██");
+///```
+pub fn frame_to_ansi(framebuffer: &FrameBuffer, pixel_element: &str) -> String {
+    let mut text = String::new();
+    for row in runs(framebuffer, pixel_element, true) {
+        for run in row {
+            text += &match (run.fg, run.bg) {
+                (Some(fg), Some(bg)) => format!("{}", run.text.with(fg).on(bg)),
+                (Some(fg), None) => format!("{}", run.text.with(fg)),
+                (None, _) => run.text,
+            };
+        }
+        text.push('\n');
+    }
+    text
+}
+
+///escapes the characters HTML treats specially
+fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;")
+}
+
+///serializes a frame to a standalone HTML page
+///# Parameters
+///- `framebuffer` : frame to serialize
+///- `pixel_element` : string each pixel is drawn as
+///# Example
+///```
+///let page = frame_to_html(renderer.last_frame(),"██");
+///```
+pub fn frame_to_html(framebuffer: &FrameBuffer, pixel_element: &str) -> String {
+    let mut html = String::from(
+        "<!DOCTYPE html>\n<html>\n<head>\n<meta charset=\"utf-8\">\n<title>frame</title>\n</head>\n<body style=\"background:#000000\">\n<pre style=\"font-family:monospace;line-height:1\">\n",
+    );
+    for row in runs(framebuffer, pixel_element, true) {
+        for run in row {
+            let text = escape_html(&run.text);
+            html += &match (run.fg, run.bg) {
+                (Some(fg), Some(bg)) => format!(
+                    "<span style=\"color:{};background:{}\">{}</span>",
+                    hex(fg),
+                    hex(bg),
+                    text
+                ),
+                (Some(fg), None) => format!("<span style=\"color:{}\">{}</span>", hex(fg), text),
+                (None, _) => text,
+            };
+        }
+        html.push('\n');
+    }
+    html += "</pre>\n</body>\n</html>\n";
+    html
+}
+
+///serializes a frame to an SVG image, every pixel is a square
+///text cells are drawn as monospace characters over the pixels, each taking the width of
+///one terminal column
+///# Parameters
+///- `framebuffer` : frame to serialize
+///- `pixel_element` : string each pixel is drawn as, sets how many columns a pixel spans
+///# Example
+///```
+///let image = frame_to_svg(renderer.last_frame(),"██");
+///```
+pub fn frame_to_svg(framebuffer: &FrameBuffer, pixel_element: &str) -> String {
+    let width = framebuffer.width() as usize * SVG_PIXEL_SIZE;
+    let height = framebuffer.height() as usize * SVG_PIXEL_SIZE;
+    let mut svg = format!(
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{}\" height=\"{}\" viewBox=\"0 0 {} {}\" shape-rendering=\"crispEdges\">\n",
+        width, height, width, height
+    );
+    //pixels first, one rect per run of equal color
+    for (y, row) in runs(framebuffer, "█", false).into_iter().enumerate() {
+        let mut x = 0;
+        for run in row {
+            let length = run.text.chars().count();
+            if let Some(run_color) = run.fg {
+                svg += &format!(
+                    "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                    x * SVG_PIXEL_SIZE,
+                    y * SVG_PIXEL_SIZE,
+                    length * SVG_PIXEL_SIZE,
+                    SVG_PIXEL_SIZE,
+                    hex(run_color)
+                );
+            }
+            x += length;
+        }
+    }
+    let column_width = SVG_PIXEL_SIZE as f32 / pixel_element.chars().count().max(1) as f32;
+    for cell in framebuffer.text() {
+        let x = cell.column as f32 * column_width;
+        let y = cell.row as usize * SVG_PIXEL_SIZE;
+        if let Some(bg) = cell.bg {
+            svg += &format!(
+                "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" fill=\"{}\"/>\n",
+                x,
+                y,
+                column_width,
+                SVG_PIXEL_SIZE,
+                hex(bg)
+            );
+        }
+        svg += &format!(
+            "<text x=\"{}\" y=\"{}\" fill=\"{}\" font-family=\"monospace\" font-size=\"{}\">{}</text>\n",
+            x,
+            y + SVG_PIXEL_SIZE - 2,
+            hex(cell.fg),
+            SVG_PIXEL_SIZE,
+            escape_html(&cell.character.to_string())
+        );
+    }
+    svg += "</svg>\n";
+    svg
+}
@@ -0,0 +1,220 @@
+//font.rs
+use crate::sprite::{self, x_pos, y_pos, Pixel, Sprite};
+use crate::stack::{LayerID, Layerstack, SpriteVector};
+use crossterm::style::Color;
+use std::collections::HashMap;
+use std::sync::OnceLock;
+
+///builtin 3x5 pixel font in the sprite file format
+const BUILTIN_FONT: &str = include_str!("fonts/small.sprites");
+
+///bitmap font, maps characters to glyph Sprites
+#[derive(Clone)]
+pub struct Font {
+    glyphs: HashMap<char, Sprite>,
+    ///height of a line of text (tallest glyph)
+    pub height: u16,
+    ///empty columns between two glyphs
+    pub letter_spacing: u16,
+    ///width of characters without a glyph, used for spaces when the font has no space glyph
+    pub space_width: u16,
+}
+
+///horizontal alignment of text relative to the x position it is drawn at
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TextAlign {
+    ///x is the left edge of each line
+    Left,
+    ///x is the center of each line
+    Center,
+    ///x is the right edge of each line
+    Right,
+}
+
+///how text is laid out and colored by `Layerstack::draw_text`
+#[derive(Clone, Debug)]
+pub struct TextStyle {
+    ///color of the glyph pixels
+    pub color: Color,
+    ///alignment of every line
+    pub align: TextAlign,
+    ///lines longer than this many pixels are wrapped at word boundaries, `None` never wraps
+    pub wrap_width: Option<u16>,
+    ///empty rows between two lines
+    pub line_spacing: u16,
+}
+
+impl Default for TextStyle {
+    fn default() -> Self {
+        TextStyle {
+            color: Color::White,
+            align: TextAlign::Left,
+            wrap_width: None,
+            line_spacing: 1,
+        }
+    }
+}
+
+impl Font {
+    ///returns the builtin 3x5 pixel font (uppercase letters, digits and common punctuation)
+    ///the font is compiled on first use and shared afterwards, so it is cheap to call every frame,
+    ///clone it to change its spacing
+    ///# Example
+    ///```
+    ///let font : &Font = Font::builtin();
+    ///```
+    pub fn builtin() -> &'static Font {
+        static BUILTIN: OnceLock<Font> = OnceLock::new();
+        BUILTIN.get_or_init(|| Font::from_sprite_file(BUILTIN_FONT).expect("builtin font is valid"))
+    }
+    ///builds a font from named sprites, each sprite named after the character it draws
+    ///the name `space` is used for the space character
+    ///# Parameters
+    ///- `sprites` : (name, glyph) pairs, usually from `sprite::load_sprite_file`
+    pub fn from_sprites(sprites: Vec<(String, Sprite)>) -> Result<Self, String> {
+        let mut glyphs: HashMap<char, Sprite> = HashMap::new();
+        for (name, glyph) in sprites {
+            let mut characters = name.chars();
+            let character = match (name.as_str(), characters.next(), characters.next()) {
+                ("space", _, _) => ' ',
+                (_, Some(character), None) => character,
+                _ => return Err(format!("glyph name {} is not a single character", name)),
+            };
+            glyphs.insert(character, glyph);
+        }
+        let height = glyphs.values().map(|glyph| glyph.height).max().unwrap_or(0);
+        let space_width = glyphs.get(&' ').map(|glyph| glyph.width).unwrap_or(height / 2 + 1);
+        Ok(Font {
+            glyphs,
+            height,
+            letter_spacing: 1,
+            space_width,
+        })
+    }
+    ///parses a font from text in the sprite file format, see `sprite::parse_sprite_file`
+    pub fn from_sprite_file(text: &str) -> Result<Self, String> {
+        Font::from_sprites(sprite::parse_sprite_file(text)?)
+    }
+    ///loads a font from a sprite file
+    ///# Example
+    ///```
+    ///let font = Font::load("assets/big.sprites")?;
+    ///```
+    pub fn load(path: &str) -> Result<Self, String> {
+        Font::from_sprites(sprite::load_sprite_file(path)?)
+    }
+    ///returns the glyph of a character, lowercase characters fall back to their uppercase glyph
+    pub fn glyph(&self, character: char) -> Option<&Sprite> {
+        self.glyphs.get(&character).or_else(|| self.glyphs.get(&character.to_ascii_uppercase()))
+    }
+    ///returns the horizontal space a character takes, excluding letter spacing
+    fn advance(&self, character: char) -> u16 {
+        self.glyph(character).map(|glyph| glyph.width).unwrap_or(self.space_width)
+    }
+    ///returns the width in pixels of a single line of text
+    ///# Example
+    ///```
+    ///font.measure("SCORE");
+    ///```
+    ///returns 19 for the builtin font (5 glyphs of 3 pixels and 4 spacing columns)
+    pub fn measure(&self, text: &str) -> u16 {
+        let count = text.chars().count() as u16;
+        let glyphs: u16 = text.chars().map(|character| self.advance(character)).sum();
+        glyphs + count.saturating_sub(1) * self.letter_spacing
+    }
+    ///splits text into lines on `\n` and, when `wrap_width` is set, at word boundaries
+    ///words wider than `wrap_width` are broken between characters
+    pub fn layout(&self, text: &str, wrap_width: Option<u16>) -> Vec<String> {
+        let mut lines: Vec<String> = vec![];
+        for paragraph in text.split('\n') {
+            let wrap_width = match wrap_width {
+                Some(wrap_width) => wrap_width,
+                None => {
+                    lines.push(paragraph.to_string());
+                    continue;
+                }
+            };
+            let mut line = String::new();
+            for word in paragraph.split(' ') {
+                let candidate = if line.is_empty() {
+                    word.to_string()
+                } else {
+                    format!("{} {}", line, word)
+                };
+                if self.measure(&candidate) <= wrap_width {
+                    line = candidate;
+                    continue;
+                }
+                if !line.is_empty() {
+                    lines.push(line);
+                }
+                //break words that do not fit on a line of their own
+                line = String::new();
+                for character in word.chars() {
+                    let mut broken = line.clone();
+                    broken.push(character);
+                    if self.measure(&broken) > wrap_width && !line.is_empty() {
+                        lines.push(line);
+                        line = character.to_string();
+                    } else {
+                        line = broken;
+                    }
+                }
+            }
+            lines.push(line);
+        }
+        lines
+    }
+}
+
+impl Layerstack {
+    ///draws text onto the target layer
+    ///# Parameters
+    ///- `x` : worldspace x position the lines are aligned to
+    ///- `y` : worldspace y position of the top of the first line
+    ///- `text` : text to draw, `\n` starts a new line
+    ///- `font` : font to draw the text in
+    ///- `style` : color, alignment, wrapping and line spacing
+    ///- `layer_id` : target layer
+    ///# Example
+    ///```
+    ///layer_draw_text(25,2,"GAME OVER",Font::builtin(),&TextStyle{align:TextAlign::Center,..Default::default()},1);
+    ///```
+    ///draws "GAME OVER" centered on x 25 of layer 1
+    pub fn draw_text(
+        &mut self,
+        x: x_pos,
+        y: y_pos,
+        text: &str,
+        font: &Font,
+        style: &TextStyle,
+        layer_id: LayerID,
+    ) {
+        println!("drawing text to layer (id : {})", layer_id);
+        let mut pixels: SpriteVector = vec![];
+        for (line_number, line) in font.layout(text, style.wrap_width).iter().enumerate() {
+            let line_width = font.measure(line) as x_pos;
+            let mut pen_x = match style.align {
+                TextAlign::Left => x,
+                TextAlign::Center => x - line_width / 2,
+                TextAlign::Right => x - line_width,
+            };
+            let pen_y = y + line_number as y_pos * (font.height + style.line_spacing) as y_pos;
+            for character in line.chars() {
+                if let Some(glyph) = font.glyph(character) {
+                    for pixel in glyph.pixels.iter().filter(|pixel| pixel.isrendered) {
+                        pixels.push(Pixel {
+                            x: pen_x + pixel.x,
+                            y: pen_y + pixel.y,
+                            color: style.color,
+                            isrendered: true,
+                            alpha: pixel.alpha,
+                        });
+                    }
+                }
+                pen_x += (font.advance(character) + font.letter_spacing) as x_pos;
+            }
+        }
+        self.write_pixels(pixels, layer_id);
+    }
+}
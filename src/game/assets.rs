@@ -1 +1,499 @@
-///sprite assets
+//assets.rs
+use crate::color::{self, Palette};
+use crate::font::Font;
+use crate::image::{self, IMAGE_EXTENSIONS};
+use crate::pack::Pack;
+use crate::sprite::{self, Animation, Sprite, DEFAULT_FRAME_DURATION};
+use crate::stack::Renderer;
+use crate::tilemap::{Tilemap, Tileset};
+use crossterm::style::Color;
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::time::{Duration, Instant, SystemTime};
+
+///named registry of game assets loaded from a directory or an asset pack
+///every asset is loaded and compiled once, later requests return a shared handle to the same copy
+///names are paths relative to the asset directory without the extension, e.g. `enemies/bat`
+pub struct Assets {
+    storage: Storage,
+    ///color treated as transparent in images loaded as sprites
+    pub image_key: Option<Color>,
+    ///minimum time between two checks for changed files in `hot_reload`
+    pub reload_interval: Duration,
+    sprites: HashMap<String, Rc<Sprite>>,
+    animations: HashMap<String, Rc<Animation>>,
+    fonts: HashMap<String, Rc<Font>>,
+    tilesets: HashMap<String, Rc<Tileset>>,
+    tilemaps: HashMap<String, Rc<Tilemap>>,
+    palettes: HashMap<String, Rc<Palette>>,
+    ///assets loaded from disk in load order, checked for changes by `reload_changed`
+    watched: Vec<Watched>,
+    ///when `hot_reload` last checked for changed files
+    last_poll: Instant,
+}
+
+///where asset files are read from, files are named relative to the asset directory
+enum Storage {
+    Directory(PathBuf),
+    Pack(Pack),
+}
+
+///how an asset loaded from disk is loaded again
+#[derive(Clone, Debug, PartialEq)]
+enum Source {
+    Image(String),
+    SpriteFile(String),
+    Animation(String),
+    Font(String),
+    Tileset(String),
+    ///(map name, tileset name)
+    Tilemap(String, String),
+    Palette(String),
+}
+
+///files an asset was loaded from and their modification times at load
+struct Watched {
+    source: Source,
+    ///first file of the asset relative to the asset directory, used to report reloads
+    file: String,
+    paths: Vec<PathBuf>,
+    modified: Vec<Option<SystemTime>>,
+}
+
+impl Storage {
+    ///returns the file `<name>.<extension>` for the first extension that exists
+    fn find(&self, name: &str, extensions: &[&str]) -> Option<String> {
+        extensions.iter().map(|extension| format!("{}.{}", name, extension)).find(|file| match self
+        {
+            Storage::Directory(root) => root.join(file).is_file(),
+            Storage::Pack(pack) => pack.entry(file).is_some(),
+        })
+    }
+    ///returns the path of a file on disk, `None` for packed files which never change
+    fn path(&self, file: &str) -> Option<PathBuf> {
+        match self {
+            Storage::Directory(root) => Some(root.join(file)),
+            Storage::Pack(_) => None,
+        }
+    }
+    ///describes the storage in error messages
+    fn describe(&self) -> String {
+        match self {
+            Storage::Directory(root) => root.display().to_string(),
+            Storage::Pack(_) => "asset pack".to_string(),
+        }
+    }
+    fn sprites(&self, file: &str) -> Result<Vec<(String, Sprite)>, String> {
+        match self {
+            Storage::Directory(root) => {
+                sprite::load_sprite_file(&root.join(file).to_string_lossy())
+            }
+            Storage::Pack(pack) => pack.sprites(file),
+        }
+    }
+    fn image(&self, file: &str, key: Option<Color>) -> Result<Sprite, String> {
+        match self {
+            Storage::Directory(root) => image::load_image(&root.join(file).to_string_lossy(), key),
+            Storage::Pack(pack) => {
+                //packed images are stored without a key, apply it the way `load_image` does
+                let mut image = pack.image(file)?;
+                if let Some(key) = key.map(color::to_rgb) {
+                    for pixel in image.pixels.iter_mut() {
+                        if color::to_rgb(pixel.color) == key {
+                            pixel.isrendered = false;
+                        }
+                    }
+                }
+                Ok(image)
+            }
+        }
+    }
+    fn text(&self, file: &str) -> Result<String, String> {
+        match self {
+            Storage::Directory(root) => {
+                let path = root.join(file);
+                fs::read_to_string(&path)
+                    .map_err(|why| format!("could not read {} : {}", path.display(), why))
+            }
+            Storage::Pack(pack) => pack.text(file),
+        }
+    }
+    ///finds `<name>.<extension>` or returns an error naming the missing asset
+    fn require(&self, kind: &str, name: &str, extensions: &[&str]) -> Result<String, String> {
+        self.find(name, extensions).ok_or(format!(
+            "{} {} not found in {}",
+            kind,
+            name,
+            self.describe()
+        ))
+    }
+}
+
+///returns the modification time of every file, `None` for files that cannot be read
+fn modified(paths: &[PathBuf]) -> Vec<Option<SystemTime>> {
+    paths
+        .iter()
+        .map(|path| fs::metadata(path).and_then(|metadata| metadata.modified()).ok())
+        .collect()
+}
+
+///prefixes errors about the contents of a file with the file name
+fn in_file<T>(file: &str, result: Result<T, String>) -> Result<T, String> {
+    result.map_err(|why| format!("{} : {}", file, why))
+}
+
+fn load_animation(storage: &Storage, name: &str) -> Result<(Animation, String), String> {
+    let file = storage.require("animation", name, &["sprites"])?;
+    let frames = storage.sprites(&file)?;
+    let animation = in_file(
+        &file,
+        Animation::from_sprites(frames, DEFAULT_FRAME_DURATION),
+    )?;
+    Ok((animation, file))
+}
+
+fn load_font(storage: &Storage, name: &str) -> Result<(Font, Option<String>), String> {
+    match storage.find(name, &["sprites"]) {
+        Some(file) => {
+            let font = in_file(&file, Font::from_sprites(storage.sprites(&file)?))?;
+            Ok((font, Some(file)))
+        }
+        None if name == "builtin" => Ok((Font::builtin().clone(), None)),
+        None => Err(format!("font {} not found in {}", name, storage.describe())),
+    }
+}
+
+fn load_tileset(storage: &Storage, name: &str) -> Result<(Tileset, String), String> {
+    let file = storage.require("tileset", name, &["sprites"])?;
+    let tileset = in_file(&file, Tileset::from_sprites(storage.sprites(&file)?))?;
+    Ok((tileset, file))
+}
+
+fn load_tilemap(
+    storage: &Storage,
+    name: &str,
+    tileset: &Tileset,
+) -> Result<(Tilemap, String), String> {
+    let file = storage.require("tilemap", name, &["map"])?;
+    let map = in_file(
+        &file,
+        Tilemap::parse(&storage.text(&file)?, tileset.clone()),
+    )?;
+    Ok((map, file))
+}
+
+fn load_palette(storage: &Storage, name: &str) -> Result<(Palette, String), String> {
+    let file = storage.require("palette", name, &["palette"])?;
+    let palette = in_file(&file, Palette::parse(&storage.text(&file)?))?;
+    Ok((palette, file))
+}
+
+impl Assets {
+    ///returns an empty registry loading assets from the directory `root`
+    ///# Example
+    ///```
+    ///let mut assets = Assets::new("assets");
+    ///let dino : Rc<Sprite> = assets.sprite("dino")?;
+    ///renderer.layerstack.write_sprite(10,10,&dino,1);
+    ///```
+    pub fn new(root: &str) -> Self {
+        println!("initializing Assets from {}", root);
+        Assets::with_storage(Storage::Directory(PathBuf::from(root)))
+    }
+    ///returns an empty registry loading assets from an asset pack instead of a directory
+    ///assets are named exactly as in the packed directory, packed assets are never hot reloaded
+    ///# Example
+    ///```
+    ///let mut assets = Assets::from_pack(Pack::load("game.pack")?);
+    ///let dino = assets.sprite("dino")?;
+    ///```
+    pub fn from_pack(pack: Pack) -> Self {
+        println!(
+            "initializing Assets from a pack of {} files",
+            pack.entries().len()
+        );
+        Assets::with_storage(Storage::Pack(pack))
+    }
+    fn with_storage(storage: Storage) -> Self {
+        Assets {
+            storage,
+            image_key: None,
+            reload_interval: Duration::from_millis(500),
+            sprites: HashMap::new(),
+            animations: HashMap::new(),
+            fonts: HashMap::new(),
+            tilesets: HashMap::new(),
+            tilemaps: HashMap::new(),
+            palettes: HashMap::new(),
+            watched: vec![],
+            last_poll: Instant::now(),
+        }
+    }
+    ///returns the directory assets are loaded from, `None` when loading from a pack
+    pub fn root(&self) -> Option<&Path> {
+        match &self.storage {
+            Storage::Directory(root) => Some(root),
+            Storage::Pack(_) => None,
+        }
+    }
+    ///records the files an asset was loaded from, replacing an earlier record of the same asset
+    ///assets loaded from a pack are not recorded
+    fn watch(&mut self, source: Source, files: Vec<String>) {
+        self.watched.retain(|watched| watched.source != source);
+        let paths: Option<Vec<PathBuf>> =
+            files.iter().map(|file| self.storage.path(file)).collect();
+        if let Some(paths) = paths {
+            self.watched.push(Watched {
+                modified: modified(&paths),
+                file: files[0].clone(),
+                source,
+                paths,
+            });
+        }
+    }
+    ///returns the sprite called `name`
+    ///looks for an image `<name>.png|bmp|ppm|pam`, then a sprite file `<name>.sprites` holding a
+    ///single sprite, then the sprite `<sprite>` inside the sprite file `<directory>.sprites` for
+    ///names of the form `<directory>/<sprite>`, loading a sprite file registers all of its sprites
+    ///# Example
+    ///```
+    ///let bat = assets.sprite("enemies/bat")?;
+    ///```
+    ///returns the image `enemies/bat.png` or the sprite `bat` of `enemies.sprites`
+    pub fn sprite(&mut self, name: &str) -> Result<Rc<Sprite>, String> {
+        if let Some(sprite) = self.sprites.get(name) {
+            return Ok(Rc::clone(sprite));
+        }
+        if let Some(file) = self.storage.find(name, &IMAGE_EXTENSIONS) {
+            let image = self.storage.image(&file, self.image_key)?;
+            self.watch(Source::Image(name.to_string()), vec![file]);
+            return Ok(self.insert_sprite(name, image));
+        }
+        if self.storage.find(name, &["sprites"]).is_some() {
+            self.load_sprite_file(name)?;
+        } else if let Some((file, _)) = name.rsplit_once('/') {
+            if self.storage.find(file, &["sprites"]).is_some() {
+                self.load_sprite_file(file)?;
+            }
+        }
+        self.sprites.get(name).map(Rc::clone).ok_or(format!(
+            "sprite {} not found in {}",
+            name,
+            self.storage.describe()
+        ))
+    }
+    ///loads every sprite of the sprite file `<name>.sprites` as `<name>/<sprite>`,
+    ///a file holding a single sprite is registered as `<name>` too
+    pub fn load_sprite_file(&mut self, name: &str) -> Result<(), String> {
+        let file = format!("{}.sprites", name);
+        let sprites = self.storage.sprites(&file)?;
+        if let [(_, single)] = sprites.as_slice() {
+            self.insert_sprite(name, single.clone());
+        }
+        for (sprite_name, sprite) in sprites {
+            self.insert_sprite(&format!("{}/{}", name, sprite_name), sprite);
+        }
+        self.watch(Source::SpriteFile(name.to_string()), vec![file]);
+        Ok(())
+    }
+    ///returns the animation called `name`, the sprites of `<name>.sprites` in file order
+    ///each frame is shown for `DEFAULT_FRAME_DURATION` ticks, register animations with other
+    ///timings through `insert_animation`
+    pub fn animation(&mut self, name: &str) -> Result<Rc<Animation>, String> {
+        if let Some(animation) = self.animations.get(name) {
+            return Ok(Rc::clone(animation));
+        }
+        let (animation, file) = load_animation(&self.storage, name)?;
+        self.watch(Source::Animation(name.to_string()), vec![file]);
+        Ok(self.insert_animation(name, animation))
+    }
+    ///returns the font called `name`, loaded from `<name>.sprites` (see `Font::load`)
+    ///the name `builtin` returns the builtin font unless the directory has a `builtin.sprites`
+    pub fn font(&mut self, name: &str) -> Result<Rc<Font>, String> {
+        if let Some(font) = self.fonts.get(name) {
+            return Ok(Rc::clone(font));
+        }
+        let (font, file) = load_font(&self.storage, name)?;
+        if let Some(file) = file {
+            self.watch(Source::Font(name.to_string()), vec![file]);
+        }
+        Ok(self.insert_font(name, font))
+    }
+    ///returns the tileset called `name`, loaded from `<name>.sprites` (see `Tileset::load`)
+    pub fn tileset(&mut self, name: &str) -> Result<Rc<Tileset>, String> {
+        if let Some(tileset) = self.tilesets.get(name) {
+            return Ok(Rc::clone(tileset));
+        }
+        let (tileset, file) = load_tileset(&self.storage, name)?;
+        self.watch(Source::Tileset(name.to_string()), vec![file]);
+        Ok(self.insert_tileset(name, tileset))
+    }
+    ///returns the tilemap called `name`, loaded from the text grid `<name>.map` (see `Tilemap::load`)
+    ///# Parameters
+    ///- `name` : name of the map
+    ///- `tileset` : name of the tileset the map refers to, used when the map is not cached yet
+    ///# Example
+    ///```
+    ///let level = assets.tilemap("levels/1","tiles")?;
+    ///```
+    pub fn tilemap(&mut self, name: &str, tileset: &str) -> Result<Rc<Tilemap>, String> {
+        if let Some(map) = self.tilemaps.get(name) {
+            return Ok(Rc::clone(map));
+        }
+        let tileset_asset = self.tileset(tileset)?;
+        let (map, file) = load_tilemap(&self.storage, name, &tileset_asset)?;
+        //the map is rebuilt when its tileset changes too
+        let mut files = vec![file];
+        files.extend(self.storage.find(tileset, &["sprites"]));
+        self.watch(
+            Source::Tilemap(name.to_string(), tileset.to_string()),
+            files,
+        );
+        Ok(self.insert_tilemap(name, map))
+    }
+    ///returns the palette called `name`, loaded from `<name>.palette` (see `Palette::parse`)
+    pub fn palette(&mut self, name: &str) -> Result<Rc<Palette>, String> {
+        if let Some(palette) = self.palettes.get(name) {
+            return Ok(Rc::clone(palette));
+        }
+        let (palette, file) = load_palette(&self.storage, name)?;
+        self.watch(Source::Palette(name.to_string()), vec![file]);
+        Ok(self.insert_palette(name, palette))
+    }
+    ///registers a sprite built at runtime under `name`, replacing any sprite of that name
+    pub fn insert_sprite(&mut self, name: &str, sprite: Sprite) -> Rc<Sprite> {
+        let sprite = Rc::new(sprite);
+        self.sprites.insert(name.to_string(), Rc::clone(&sprite));
+        sprite
+    }
+    ///registers an animation under `name`, replacing any animation of that name
+    pub fn insert_animation(&mut self, name: &str, animation: Animation) -> Rc<Animation> {
+        let animation = Rc::new(animation);
+        self.animations.insert(name.to_string(), Rc::clone(&animation));
+        animation
+    }
+    ///registers a font under `name`, replacing any font of that name
+    pub fn insert_font(&mut self, name: &str, font: Font) -> Rc<Font> {
+        let font = Rc::new(font);
+        self.fonts.insert(name.to_string(), Rc::clone(&font));
+        font
+    }
+    ///registers a tileset under `name`, replacing any tileset of that name
+    pub fn insert_tileset(&mut self, name: &str, tileset: Tileset) -> Rc<Tileset> {
+        let tileset = Rc::new(tileset);
+        self.tilesets.insert(name.to_string(), Rc::clone(&tileset));
+        tileset
+    }
+    ///registers a tilemap under `name`, replacing any tilemap of that name
+    pub fn insert_tilemap(&mut self, name: &str, tilemap: Tilemap) -> Rc<Tilemap> {
+        let tilemap = Rc::new(tilemap);
+        self.tilemaps.insert(name.to_string(), Rc::clone(&tilemap));
+        tilemap
+    }
+    ///registers a palette under `name`, replacing any palette of that name
+    pub fn insert_palette(&mut self, name: &str, palette: Palette) -> Rc<Palette> {
+        let palette = Rc::new(palette);
+        self.palettes.insert(name.to_string(), Rc::clone(&palette));
+        palette
+    }
+    ///forgets every cached asset so the next request loads it from disk again
+    ///handles handed out before stay valid
+    pub fn clear(&mut self) {
+        println!("clearing cached assets");
+        self.sprites.clear();
+        self.animations.clear();
+        self.fonts.clear();
+        self.tilesets.clear();
+        self.tilemaps.clear();
+        self.palettes.clear();
+        self.watched.clear();
+    }
+    ///loads an asset from disk again and replaces the cached copy, the old copy is kept on error
+    fn reload(&mut self, source: &Source) -> Result<(), String> {
+        match source {
+            Source::Image(name) => {
+                let file = self.storage.require("sprite", name, &IMAGE_EXTENSIONS)?;
+                let image = self.storage.image(&file, self.image_key)?;
+                self.insert_sprite(name, image);
+            }
+            Source::SpriteFile(name) => self.load_sprite_file(name)?,
+            Source::Animation(name) => {
+                let (animation, _) = load_animation(&self.storage, name)?;
+                self.insert_animation(name, animation);
+            }
+            Source::Font(name) => {
+                let (font, _) = load_font(&self.storage, name)?;
+                self.insert_font(name, font);
+            }
+            Source::Tileset(name) => {
+                let (tileset, _) = load_tileset(&self.storage, name)?;
+                self.insert_tileset(name, tileset);
+            }
+            Source::Tilemap(name, tileset) => {
+                let tileset = self.tileset(tileset)?;
+                let (map, _) = load_tilemap(&self.storage, name, &tileset)?;
+                self.insert_tilemap(name, map);
+            }
+            Source::Palette(name) => {
+                let (palette, _) = load_palette(&self.storage, name)?;
+                self.insert_palette(name, palette);
+            }
+        }
+        Ok(())
+    }
+    ///reloads every asset whose files changed since it was loaded
+    ///the next request for a reloaded asset returns the new copy, handles handed out before keep
+    ///the old one, so games that fetch assets by name every frame pick up changes on the next frame
+    ///returns the first file (relative to the asset directory) of every reloaded asset with the
+    ///result of reloading it, an asset that failed to reload keeps its old copy and is retried
+    ///once its files change again
+    ///# Example
+    ///```
+    ///for (file,result) in assets.reload_changed() {
+    ///    if let Err(why) = result { println!("{} : {}",file,why); }
+    ///}
+    ///```
+    pub fn reload_changed(&mut self) -> Vec<(String, Result<(), String>)> {
+        let mut changed: Vec<(String, Source)> = vec![];
+        for watched in self.watched.iter_mut() {
+            let now = modified(&watched.paths);
+            if now != watched.modified {
+                watched.modified = now;
+                changed.push((watched.file.clone(), watched.source.clone()));
+            }
+        }
+        changed
+            .into_iter()
+            .map(|(file, source)| {
+                println!("reloading changed asset {}", file);
+                let result = self.reload(&source);
+                (file, result)
+            })
+            .collect()
+    }
+    ///checks for changed asset files at most once every `reload_interval` and reloads them,
+    ///reload errors are shown on the debug overlay of `renderer` until the file is fixed
+    ///meant to be called once per frame during development
+    ///# Example
+    ///```
+    ///loop {
+    ///    assets.hot_reload(&mut renderer);
+    ///    renderer.layerstack.write_sprite(10,10,&assets.sprite("dino")?,1);
+    ///    renderer.render_update();
+    ///}
+    ///```
+    pub fn hot_reload(&mut self, renderer: &mut Renderer) {
+        if self.last_poll.elapsed() < self.reload_interval {
+            return;
+        }
+        self.last_poll = Instant::now();
+        for (file, result) in self.reload_changed() {
+            match result {
+                Ok(()) => renderer.clear_overlay_message(&file),
+                Err(why) => renderer.overlay_message(&file, &why),
+            }
+        }
+    }
+}
@@ -0,0 +1,640 @@
+//core.rs
+use crate::sprite::{x_pos, y_pos, Animation, Sprite};
+use crate::stack::{LayerID, Layerstack};
+use crossterm::style::Color;
+use std::any::{Any, TypeId};
+use std::collections::{HashMap, VecDeque};
+use std::rc::Rc;
+use std::time::Duration;
+
+///handle of a game object in a World
+///the generation tells a despawned entity apart from a later one reusing its slot, so stale
+///handles never reach the components of another entity
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: u32,
+    generation: u32,
+}
+
+///position of an entity in worldspace pixels, the top left corner of its sprite
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Transform {
+    pub x: f32,
+    pub y: f32,
+}
+
+///movement of an entity in pixels per second, applied by `movement_system`
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Velocity {
+    pub x: f32,
+    pub y: f32,
+}
+
+///what `render_system` draws for an entity, sprites are shared with the asset manager
+#[derive(Clone)]
+pub enum SpriteRef {
+    Sprite(Rc<Sprite>),
+    ///an animation started at tick `start`, see `World::tick`
+    Animation {
+        animation: Rc<Animation>,
+        start: u64,
+    },
+}
+
+///layer of the Layerstack `render_system` draws an entity to
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct RenderLayer(pub LayerID);
+
+///what a system gets to work with besides the World
+pub struct Context<'a> {
+    ///index of the current tick, starting at 0
+    pub tick: u64,
+    ///time the current tick covers
+    pub delta: Duration,
+    pub layerstack: &'a mut Layerstack,
+}
+
+///a system run once per tick, any `FnMut(&mut World, &mut Context)` is a system
+pub trait System {
+    fn run(&mut self, world: &mut World, context: &mut Context);
+}
+
+impl<F: FnMut(&mut World, &mut Context)> System for F {
+    fn run(&mut self, world: &mut World, context: &mut Context) {
+        self(world, context)
+    }
+}
+
+///components of one type indexed by entity slot
+struct Components<T> {
+    values: Vec<Option<T>>,
+}
+
+///type erased component storage so the World can drop the components of despawned entities
+trait Storage {
+    fn remove(&mut self, index: usize);
+    fn as_any(&self) -> &dyn Any;
+    fn as_any_mut(&mut self) -> &mut dyn Any;
+}
+
+impl<T: 'static> Storage for Components<T> {
+    fn remove(&mut self, index: usize) {
+        if let Some(value) = self.values.get_mut(index) {
+            *value = None;
+        }
+    }
+    fn as_any(&self) -> &dyn Any {
+        self
+    }
+    fn as_any_mut(&mut self) -> &mut dyn Any {
+        self
+    }
+}
+
+///entities, their components and the systems that update them
+///components are plain structs stored per type, any `'static` type can be a component
+///# Example
+///```
+///let mut world = World::new();
+///world.add_system("movement",movement_system);
+///world.add_system("render",render_system);
+///let dino = world.spawn();
+///world.insert(dino,Transform{x:4.0,y:8.0});
+///world.insert(dino,SpriteRef::Sprite(assets.sprite("dino")?));
+///world.insert(dino,RenderLayer(1));
+///loop {
+///    world.run(Duration::from_secs(1)/60,&mut renderer.layerstack);
+///    renderer.render_update();
+///}
+///```
+pub struct World {
+    ///generation of every entity slot, odd while the slot holds a live entity
+    generations: Vec<u32>,
+    ///slots of despawned entities, reused by `spawn`
+    free: Vec<u32>,
+    components: HashMap<TypeId, Box<dyn Storage>>,
+    ///systems in run order
+    systems: Vec<(String, Box<dyn System>)>,
+    tick: u64,
+}
+
+impl Default for World {
+    fn default() -> Self {
+        World::new()
+    }
+}
+
+impl World {
+    ///returns a World without entities or systems
+    pub fn new() -> Self {
+        println!("initializing World");
+        World {
+            generations: vec![],
+            free: vec![],
+            components: HashMap::new(),
+            systems: vec![],
+            tick: 0,
+        }
+    }
+    ///creates an entity without components
+    pub fn spawn(&mut self) -> Entity {
+        match self.free.pop() {
+            Some(index) => {
+                self.generations[index as usize] += 1;
+                Entity {
+                    index,
+                    generation: self.generations[index as usize],
+                }
+            }
+            None => {
+                self.generations.push(1);
+                Entity {
+                    index: self.generations.len() as u32 - 1,
+                    generation: 1,
+                }
+            }
+        }
+    }
+    ///removes an entity and all of its components, returns false for stale handles
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        self.generations[entity.index as usize] += 1;
+        self.free.push(entity.index);
+        for storage in self.components.values_mut() {
+            storage.remove(entity.index as usize);
+        }
+        true
+    }
+    ///returns true if the entity was spawned and not despawned since
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.generations.get(entity.index as usize) == Some(&entity.generation)
+            && entity.generation % 2 == 1
+    }
+    ///returns every live entity in slot order
+    pub fn entities(&self) -> Vec<Entity> {
+        self.generations
+            .iter()
+            .enumerate()
+            .filter(|(_, generation)| *generation % 2 == 1)
+            .map(|(index, generation)| Entity {
+                index: index as u32,
+                generation: *generation,
+            })
+            .collect()
+    }
+    ///returns the live entity in a slot
+    fn entity_at(&self, index: usize) -> Entity {
+        Entity {
+            index: index as u32,
+            generation: self.generations[index],
+        }
+    }
+    fn storage<T: 'static>(&self) -> Option<&Components<T>> {
+        self.components
+            .get(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any().downcast_ref::<Components<T>>())
+    }
+    fn storage_mut<T: 'static>(&mut self) -> Option<&mut Components<T>> {
+        self.components
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any_mut().downcast_mut::<Components<T>>())
+    }
+    ///adds a component to an entity, replacing and returning a component of the same type
+    ///components of stale handles are dropped
+    pub fn insert<T: 'static>(&mut self, entity: Entity, component: T) -> Option<T> {
+        if !self.is_alive(entity) {
+            println!("error! cannot add a component to a despawned entity");
+            return None;
+        }
+        let storage = self
+            .components
+            .entry(TypeId::of::<T>())
+            .or_insert_with(|| Box::new(Components::<T> { values: vec![] }))
+            .as_any_mut()
+            .downcast_mut::<Components<T>>()
+            .unwrap();
+        let index = entity.index as usize;
+        if storage.values.len() <= index {
+            storage.values.resize_with(index + 1, || None);
+        }
+        storage.values[index].replace(component)
+    }
+    ///removes a component from an entity and returns it
+    pub fn remove<T: 'static>(&mut self, entity: Entity) -> Option<T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storage_mut::<T>()?.values.get_mut(entity.index as usize)?.take()
+    }
+    ///returns the component of type `T` of an entity
+    pub fn get<T: 'static>(&self, entity: Entity) -> Option<&T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storage::<T>()?.values.get(entity.index as usize)?.as_ref()
+    }
+    ///returns the component of type `T` of an entity for changing it
+    pub fn get_mut<T: 'static>(&mut self, entity: Entity) -> Option<&mut T> {
+        if !self.is_alive(entity) {
+            return None;
+        }
+        self.storage_mut::<T>()?.values.get_mut(entity.index as usize)?.as_mut()
+    }
+    ///returns true if the entity has a component of type `T`
+    pub fn has<T: 'static>(&self, entity: Entity) -> bool {
+        self.get::<T>(entity).is_some()
+    }
+    ///returns every entity with a component of type `T` and the component, in slot order
+    ///# Example
+    ///```
+    ///for (entity,health) in world.iter::<Health>() { ... }
+    ///```
+    pub fn iter<T: 'static>(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.storage::<T>()
+            .into_iter()
+            .flat_map(|storage| storage.values.iter().enumerate())
+            .filter_map(|(index, value)| value.as_ref().map(|value| (self.entity_at(index), value)))
+    }
+    ///returns every entity with a component of type `T` and the component for changing it
+    pub fn iter_mut<T: 'static>(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        let generations = &self.generations;
+        self.components
+            .get_mut(&TypeId::of::<T>())
+            .and_then(|storage| storage.as_any_mut().downcast_mut::<Components<T>>())
+            .into_iter()
+            .flat_map(|storage| storage.values.iter_mut().enumerate())
+            .filter_map(move |(index, value)| {
+                let entity = Entity {
+                    index: index as u32,
+                    generation: generations[index],
+                };
+                value.as_mut().map(|value| (entity, value))
+            })
+    }
+    ///adds a system that runs after every system added before it
+    pub fn add_system(&mut self, name: &str, system: impl System + 'static) {
+        println!("adding system {}", name);
+        self.systems.push((name.to_string(), Box::new(system)));
+    }
+    ///removes the system called `name`, returns false if there is none
+    pub fn remove_system(&mut self, name: &str) -> bool {
+        let count = self.systems.len();
+        self.systems.retain(|(existing, _)| existing != name);
+        self.systems.len() != count
+    }
+    ///returns the names of the systems in run order
+    pub fn system_names(&self) -> Vec<&str> {
+        self.systems.iter().map(|(name, _)| name.as_str()).collect()
+    }
+    ///returns the index of the next tick `run` will run
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+    ///runs every system once in order and advances the tick
+    ///# Parameters
+    ///- `delta` : time the tick covers, a fixed timestep keeps games deterministic
+    ///- `layerstack` : layerstack systems draw to
+    pub fn run(&mut self, delta: Duration, layerstack: &mut Layerstack) {
+        //systems are taken out while they run so they can borrow the world mutably
+        let mut systems = std::mem::take(&mut self.systems);
+        let mut context = Context {
+            tick: self.tick,
+            delta,
+            layerstack,
+        };
+        for (_, system) in systems.iter_mut() {
+            system.run(self, &mut context);
+        }
+        //keep systems added while running after the existing ones
+        systems.append(&mut self.systems);
+        self.systems = systems;
+        self.tick += 1;
+    }
+}
+
+///moves every entity with a Transform and a Velocity by its velocity times the tick length
+pub fn movement_system(world: &mut World, context: &mut Context) {
+    let seconds = context.delta.as_secs_f32();
+    let velocities: Vec<(Entity, Velocity)> =
+        world.iter::<Velocity>().map(|(entity, velocity)| (entity, *velocity)).collect();
+    for (entity, velocity) in velocities {
+        if let Some(transform) = world.get_mut::<Transform>(entity) {
+            transform.x += velocity.x * seconds;
+            transform.y += velocity.y * seconds;
+        }
+    }
+}
+
+///writes every entity with a Transform, a SpriteRef and a RenderLayer to its layer, entities
+///are written in slot order so later entities cover earlier ones on the same layer
+pub fn render_system(world: &mut World, context: &mut Context) {
+    for (entity, sprite) in world.iter::<SpriteRef>() {
+        let (Some(transform), Some(RenderLayer(layer_id))) = (
+            world.get::<Transform>(entity),
+            world.get::<RenderLayer>(entity),
+        ) else {
+            continue;
+        };
+        let sprite = match sprite {
+            SpriteRef::Sprite(sprite) => sprite,
+            SpriteRef::Animation { animation, start } => {
+                animation.frame(context.tick.saturating_sub(*start))
+            }
+        };
+        let (x, y) = (transform.x.round() as x_pos, transform.y.round() as y_pos);
+        context.layerstack.write_sprite(x, y, sprite, *layer_id);
+    }
+}
+
+///layer ID reserved by SceneStack for drawing transitions, scenes must not use it
+pub const TRANSITION_LAYER: LayerID = LayerID::MAX;
+
+///a screen of the game (title screen, gameplay, pause menu) managed by a SceneStack
+///a scene creates its layers in `enter` and lists them in `layers`, the stack hides them while
+///the scene is covered and removes them when the scene leaves the stack
+pub trait Scene {
+    ///called when the scene is added to the stack, creates the layers of the scene
+    fn enter(&mut self, layerstack: &mut Layerstack);
+    ///returns the layers the scene owns
+    fn layers(&self) -> Vec<LayerID>;
+    ///called once per tick while the scene is on top of the stack and no transition is running,
+    ///returns a command to change the stack
+    fn update(&mut self, context: &mut Context) -> Option<SceneCommand>;
+    ///called once per tick while the scene is visible, writes the scene to its layers
+    fn render(&mut self, context: &mut Context);
+    ///called before the scene leaves the stack, its layers are removed right after
+    fn exit(&mut self, _layerstack: &mut Layerstack) {}
+    ///called when another scene is pushed on top of this one
+    fn pause(&mut self) {}
+    ///called when this scene is on top again after the scene above it was popped
+    fn resume(&mut self) {}
+    ///overlay scenes (pause menus, dialogs) are drawn over the scene beneath them,
+    ///which keeps rendering but is not updated
+    fn is_overlay(&self) -> bool {
+        false
+    }
+}
+
+///how the screen changes between scenes
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Transition {
+    ///switch at once
+    Cut,
+    ///fade to `color` and back over `ticks` ticks, the scenes switch halfway
+    Fade { color: Color, ticks: u32 },
+    ///cover the screen with `color` from left to right and uncover it to the right over `ticks`
+    ///ticks, the scenes switch halfway
+    Wipe { color: Color, ticks: u32 },
+}
+
+///change to a SceneStack
+pub enum SceneCommand {
+    ///put a scene on top of the current one
+    Push(Box<dyn Scene>, Transition),
+    ///remove the top scene
+    Pop(Transition),
+    ///swap the top scene for another one
+    Replace(Box<dyn Scene>, Transition),
+}
+
+impl SceneCommand {
+    fn transition(&self) -> Transition {
+        match self {
+            SceneCommand::Push(_, transition)
+            | SceneCommand::Pop(transition)
+            | SceneCommand::Replace(_, transition) => *transition,
+        }
+    }
+}
+
+///a transition in progress
+struct ActiveTransition {
+    effect: Transition,
+    ///ticks run so far
+    elapsed: u32,
+    ///applied halfway, `None` once applied
+    command: Option<SceneCommand>,
+}
+
+///stack of scenes, the top scene is updated and every scene down to the first non overlay scene
+///is rendered bottom first
+///# Example
+///```
+///let mut scenes = SceneStack::new();
+///scenes.push(Box::new(TitleScreen::new()),Transition::Cut);
+///while !scenes.is_empty() {
+///    scenes.run(Duration::from_secs(1)/60,&mut renderer.layerstack);
+///    renderer.render_update();
+///}
+///```
+///where `TitleScreen::update` returns
+///`Some(SceneCommand::Replace(Box::new(Gameplay::new()),Transition::Fade{color:Color::Black,ticks:30}))`
+///once a key is pressed
+pub struct SceneStack {
+    scenes: Vec<Box<dyn Scene>>,
+    ///commands waiting for the running transition to end
+    pending: VecDeque<SceneCommand>,
+    transition: Option<ActiveTransition>,
+    tick: u64,
+}
+
+impl Default for SceneStack {
+    fn default() -> Self {
+        SceneStack::new()
+    }
+}
+
+impl SceneStack {
+    pub fn new() -> Self {
+        println!("initializing SceneStack");
+        SceneStack {
+            scenes: vec![],
+            pending: VecDeque::new(),
+            transition: None,
+            tick: 0,
+        }
+    }
+    ///queues a scene to be put on top, applied by the next `run`
+    pub fn push(&mut self, scene: Box<dyn Scene>, transition: Transition) {
+        self.pending.push_back(SceneCommand::Push(scene, transition));
+    }
+    ///queues removing the top scene, applied by the next `run`
+    pub fn pop(&mut self, transition: Transition) {
+        self.pending.push_back(SceneCommand::Pop(transition));
+    }
+    ///queues swapping the top scene, applied by the next `run`
+    pub fn replace(&mut self, scene: Box<dyn Scene>, transition: Transition) {
+        self.pending.push_back(SceneCommand::Replace(scene, transition));
+    }
+    ///returns the number of scenes on the stack
+    pub fn len(&self) -> usize {
+        self.scenes.len()
+    }
+    ///returns true once every scene was popped, usually the end of the game
+    pub fn is_empty(&self) -> bool {
+        self.scenes.is_empty() && self.pending.is_empty() && self.transition.is_none()
+    }
+    ///returns true while a transition is running
+    pub fn is_transitioning(&self) -> bool {
+        self.transition.is_some()
+    }
+    ///applies a command to the stack at once
+    fn apply(&mut self, command: SceneCommand, layerstack: &mut Layerstack) {
+        match command {
+            SceneCommand::Push(mut scene, _) => {
+                if let Some(top) = self.scenes.last_mut() {
+                    top.pause();
+                }
+                scene.enter(layerstack);
+                self.scenes.push(scene);
+            }
+            SceneCommand::Pop(_) => {
+                if let Some(scene) = self.scenes.pop() {
+                    remove_scene(scene, layerstack);
+                }
+                if let Some(top) = self.scenes.last_mut() {
+                    top.resume();
+                }
+            }
+            SceneCommand::Replace(mut scene, _) => {
+                if let Some(old) = self.scenes.pop() {
+                    remove_scene(old, layerstack);
+                }
+                scene.enter(layerstack);
+                self.scenes.push(scene);
+            }
+        }
+        self.update_visibility(layerstack);
+    }
+    ///returns the index of the lowest scene that is rendered
+    fn first_visible(&self) -> usize {
+        self.scenes.iter().rposition(|scene| !scene.is_overlay()).unwrap_or(0)
+    }
+    ///shows the layers of visible scenes and hides the layers of covered scenes
+    fn update_visibility(&self, layerstack: &mut Layerstack) {
+        let first_visible = self.first_visible();
+        for (index, scene) in self.scenes.iter().enumerate() {
+            for layer_id in scene.layers() {
+                if layerstack.contains(layer_id) {
+                    layerstack.set_visibility(layer_id, index >= first_visible);
+                }
+            }
+        }
+    }
+    ///applies queued commands until one starts a transition
+    fn start_pending(&mut self, layerstack: &mut Layerstack) {
+        while self.transition.is_none() {
+            let Some(command) = self.pending.pop_front() else {
+                break;
+            };
+            match command.transition() {
+                Transition::Fade { ticks, .. } | Transition::Wipe { ticks, .. } if ticks >= 2 => {
+                    layerstack.add(TRANSITION_LAYER, layerstack.top_pos());
+                    self.transition = Some(ActiveTransition {
+                        effect: command.transition(),
+                        elapsed: 0,
+                        command: Some(command),
+                    });
+                }
+                _ => self.apply(command, layerstack),
+            }
+        }
+    }
+    ///advances the running transition by one tick and draws it
+    fn advance_transition(&mut self, layerstack: &mut Layerstack) {
+        let Some(mut transition) = self.transition.take() else {
+            return;
+        };
+        transition.elapsed += 1;
+        let (color, ticks, wipe) = match transition.effect {
+            Transition::Fade { color, ticks } => (color, ticks, false),
+            Transition::Wipe { color, ticks } => (color, ticks, true),
+            Transition::Cut => (Color::Black, 0, false),
+        };
+        let half = ticks.div_ceil(2);
+        //fraction of the screen covered, rising to 1.0 halfway and falling back to 0.0
+        let coverage = if transition.elapsed <= half {
+            transition.elapsed as f32 / half as f32
+        } else {
+            (ticks - transition.elapsed.min(ticks)) as f32 / (ticks - half) as f32
+        };
+        if transition.elapsed >= half {
+            if let Some(command) = transition.command.take() {
+                self.apply(command, layerstack);
+                //keep covering layers the new scene added
+                layerstack.move_layer(TRANSITION_LAYER, layerstack.top_pos());
+            }
+        }
+        if transition.elapsed >= ticks {
+            layerstack.remove(TRANSITION_LAYER);
+            return;
+        }
+        let (width, height) = layerstack.size();
+        if wipe {
+            let covered = (coverage * width as f32).round() as u16;
+            //covering grows from the left, uncovering shrinks towards the right
+            let x = if transition.elapsed <= half {
+                0
+            } else {
+                width - covered
+            };
+            layerstack.draw_rect(
+                x as x_pos,
+                0,
+                covered,
+                height,
+                color,
+                true,
+                TRANSITION_LAYER,
+            );
+        } else {
+            layerstack.set_opacity(TRANSITION_LAYER, coverage);
+            layerstack.draw_rect(0, 0, width, height, color, true, TRANSITION_LAYER);
+        }
+        self.transition = Some(transition);
+    }
+    ///runs one tick : applies queued commands, advances the running transition or updates the
+    ///top scene, then renders the visible scenes bottom first
+    ///# Parameters
+    ///- `delta` : time the tick covers
+    ///- `layerstack` : layerstack the scenes draw to
+    pub fn run(&mut self, delta: Duration, layerstack: &mut Layerstack) {
+        self.start_pending(layerstack);
+        if self.transition.is_some() {
+            self.advance_transition(layerstack);
+        } else if let Some(top) = self.scenes.last_mut() {
+            let mut context = Context {
+                tick: self.tick,
+                delta,
+                layerstack,
+            };
+            if let Some(command) = top.update(&mut context) {
+                self.pending.push_back(command);
+                self.start_pending(layerstack);
+                self.advance_transition(layerstack);
+            }
+        }
+        let first_visible = self.first_visible();
+        let mut context = Context {
+            tick: self.tick,
+            delta,
+            layerstack,
+        };
+        for scene in self.scenes.iter_mut().skip(first_visible) {
+            scene.render(&mut context);
+        }
+        self.tick += 1;
+    }
+}
+
+///calls `exit` on a scene leaving the stack and removes its layers
+fn remove_scene(mut scene: Box<dyn Scene>, layerstack: &mut Layerstack) {
+    scene.exit(layerstack);
+    for layer_id in scene.layers() {
+        if layerstack.contains(layer_id) {
+            layerstack.remove(layer_id);
+        }
+    }
+}
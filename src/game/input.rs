@@ -0,0 +1,280 @@
+//input.rs
+use crossterm::event::{self, Event, KeyCode, KeyEventKind};
+use std::{fs, io, time};
+
+///keys pressed during a single frame, in the order they were pressed
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputState {
+    pub keys: Vec<KeyCode>,
+}
+
+impl InputState {
+    ///returns true if `key` was pressed during the frame
+    ///# Example
+    ///```
+    ///if input.poll().pressed(KeyCode::Char('q')) { break; }
+    ///```
+    pub fn pressed(&self, key: KeyCode) -> bool {
+        self.keys.contains(&key)
+    }
+}
+
+///per frame input of a session, only frames with at least one key are stored
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct InputRecording {
+    ///(frame index, input of that frame) in ascending frame order
+    frames: Vec<(u64, InputState)>,
+    ///number of frames the recording spans, including trailing frames without input
+    length: u64,
+}
+
+///where `Input` reads key presses from
+enum InputSource {
+    ///live keyboard events from the terminal
+    Keyboard,
+    ///a previously recorded session, `next` is the index of the next stored frame to replay
+    Replay {
+        recording: InputRecording,
+        next: usize,
+    },
+}
+
+///per frame keyboard input for the game loop
+///`poll` is called once per frame, the frame index it keeps is what makes replays deterministic
+pub struct Input {
+    source: InputSource,
+    ///index of the frame returned by the last `poll`
+    frame: u64,
+    ///input of the current frame
+    state: InputState,
+    ///active input recording and the frame it started after, `None` when not recording
+    recording: Option<(u64, InputRecording)>,
+}
+
+impl Input {
+    ///returns Input reading from the terminal keyboard
+    ///the terminal should be in raw mode (`crossterm::terminal::enable_raw_mode`)
+    ///for keys to arrive without the user pressing enter
+    pub fn keyboard() -> Self {
+        println!("initializing keyboard Input");
+        Input {
+            source: InputSource::Keyboard,
+            frame: 0,
+            state: InputState::default(),
+            recording: None,
+        }
+    }
+    ///returns Input replaying a recorded session instead of reading the keyboard
+    ///# Parameters
+    ///- `recording` : session to replay
+    ///# Example
+    ///```
+    ///let input = Input::replay(InputRecording::load("bug_1234.input")?);
+    ///```
+    pub fn replay(recording: InputRecording) -> Self {
+        println!("initializing replay Input ({} frames)", recording.length);
+        Input {
+            source: InputSource::Replay { recording, next: 0 },
+            frame: 0,
+            state: InputState::default(),
+            recording: None,
+        }
+    }
+    ///advances to the next frame and returns its input
+    ///reads every pending keyboard event without blocking, or the next frame of the replay
+    ///# Example
+    ///```
+    ///loop {
+    ///    let state = input.poll();
+    ///    //update game with state
+    ///    renderer.render_update();
+    ///}
+    ///```
+    pub fn poll(&mut self) -> &InputState {
+        self.frame += 1;
+        self.state = match &mut self.source {
+            InputSource::Keyboard => read_keyboard(),
+            InputSource::Replay { recording, next } => match recording.frames.get(*next) {
+                Some((frame, state)) if *frame == self.frame => {
+                    *next += 1;
+                    state.clone()
+                }
+                _ => InputState::default(),
+            },
+        };
+        if let Some((start, recording)) = self.recording.as_mut() {
+            recording.push(self.frame - *start, self.state.clone());
+        }
+        &self.state
+    }
+    ///returns the input of the current frame without advancing
+    pub fn state(&self) -> &InputState {
+        &self.state
+    }
+    ///returns the index of the current frame, the first polled frame is 1
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+    ///returns true once every frame of a replay has been polled, always false for the keyboard
+    pub fn replay_finished(&self) -> bool {
+        match &self.source {
+            InputSource::Keyboard => false,
+            InputSource::Replay { recording, .. } => self.frame >= recording.length,
+        }
+    }
+    ///starts recording the input of every polled frame, replacing any recording in progress
+    ///frame indices are stored relative to the frame recording started on
+    pub fn start_recording(&mut self) {
+        println!("starting input recording at frame {}", self.frame);
+        self.recording = Some((self.frame, InputRecording::default()));
+    }
+    ///stops recording and returns the recorded input, `None` if nothing was being recorded
+    pub fn stop_recording(&mut self) -> Option<InputRecording> {
+        println!("stopping input recording at frame {}", self.frame);
+        self.recording.take().map(|(_, recording)| recording)
+    }
+}
+
+///drains all pending key press events from the terminal
+fn read_keyboard() -> InputState {
+    let mut state = InputState::default();
+    while let Ok(true) = event::poll(time::Duration::ZERO) {
+        if let Ok(Event::Key(key_event)) = event::read() {
+            if key_event.kind != KeyEventKind::Release {
+                state.keys.push(key_event.code);
+            }
+        }
+    }
+    state
+}
+
+///names of the non character keys supported in recordings
+const NAMED_KEYS: [(&str, KeyCode); 14] = [
+    ("Space", KeyCode::Char(' ')),
+    ("Up", KeyCode::Up),
+    ("Down", KeyCode::Down),
+    ("Left", KeyCode::Left),
+    ("Right", KeyCode::Right),
+    ("Enter", KeyCode::Enter),
+    ("Esc", KeyCode::Esc),
+    ("Backspace", KeyCode::Backspace),
+    ("Tab", KeyCode::Tab),
+    ("Delete", KeyCode::Delete),
+    ("Home", KeyCode::Home),
+    ("End", KeyCode::End),
+    ("PageUp", KeyCode::PageUp),
+    ("PageDown", KeyCode::PageDown),
+];
+
+///returns the text form of a key, `None` for keys recordings do not support
+fn key_to_text(key: KeyCode) -> Option<String> {
+    if let Some((name, _)) = NAMED_KEYS.iter().find(|(_, code)| *code == key) {
+        return Some(name.to_string());
+    }
+    match key {
+        KeyCode::Char(character) if !character.is_whitespace() => Some(character.to_string()),
+        KeyCode::F(number) => Some(format!("F{}", number)),
+        _ => None,
+    }
+}
+
+///parses the text form of a key, see `key_to_text`
+fn text_to_key(text: &str) -> Option<KeyCode> {
+    if let Some((_, code)) = NAMED_KEYS.iter().find(|(name, _)| *name == text) {
+        return Some(*code);
+    }
+    let mut characters = text.chars();
+    match (characters.next(), characters.next()) {
+        (Some(character), None) => Some(KeyCode::Char(character)),
+        (Some('F'), Some(_)) => text[1..].parse::<u8>().ok().map(KeyCode::F),
+        _ => None,
+    }
+}
+
+impl InputRecording {
+    ///appends the input of a frame
+    fn push(&mut self, frame: u64, state: InputState) {
+        self.length = frame;
+        if !state.keys.is_empty() {
+            self.frames.push((frame, state));
+        }
+    }
+    ///returns the number of frames the recording spans
+    pub fn length(&self) -> u64 {
+        self.length
+    }
+    ///serializes the recording to text
+    ///the first line holds the length, each following line is a frame index and its keys
+    ///# Example
+    ///```
+    ///frames 120
+    ///12 Right
+    ///13 Right Space
+    ///```
+    ///keys recordings cannot represent (media keys, modifiers only) are skipped
+    pub fn to_text(&self) -> String {
+        let mut text = format!("frames {}\n", self.length);
+        for (frame, state) in self.frames.iter() {
+            let keys: Vec<String> = state.keys.iter().filter_map(|key| key_to_text(*key)).collect();
+            if !keys.is_empty() {
+                text += &format!("{} {}\n", frame, keys.join(" "));
+            }
+        }
+        text
+    }
+    ///parses a recording serialized with `to_text`
+    pub fn from_text(text: &str) -> Result<Self, String> {
+        let mut lines = text.lines().filter(|line| !line.trim().is_empty());
+        let length = match lines.next().map(|line| line.split_whitespace().collect::<Vec<&str>>()) {
+            Some(header) if header.len() == 2 && header[0] == "frames" => header[1]
+                .parse::<u64>()
+                .map_err(|_| format!("invalid frame count : {}", header[1]))?,
+            _ => return Err("input recording is missing its `frames` header".to_string()),
+        };
+        let mut recording = InputRecording {
+            frames: vec![],
+            length,
+        };
+        for line in lines {
+            let mut fields = line.split_whitespace();
+            let frame_text = fields.next().unwrap_or_default();
+            let frame = frame_text
+                .parse::<u64>()
+                .map_err(|_| format!("invalid frame index : {}", frame_text))?;
+            //polled frames start at 1, frames outside the recording would never be replayed
+            if frame == 0 || frame > length {
+                return Err(format!(
+                    "frame {} is outside the recording (1 to {})",
+                    frame, length
+                ));
+            }
+            if let Some((last_frame, _)) = recording.frames.last() {
+                if frame <= *last_frame {
+                    return Err(format!("frame {} is out of order", frame));
+                }
+            }
+            let keys = fields
+                .map(|key| {
+                    text_to_key(key).ok_or(format!("unknown key {} on frame {}", key, frame))
+                })
+                .collect::<Result<Vec<KeyCode>, String>>()?;
+            recording.frames.push((frame, InputState { keys }));
+        }
+        Ok(recording)
+    }
+    ///saves the recording to a file, see `to_text`
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        println!(
+            "saving input recording ({} frames) to {}",
+            self.length, path
+        );
+        fs::write(path, self.to_text())
+    }
+    ///loads a recording saved with `save`
+    pub fn load(path: &str) -> Result<Self, String> {
+        println!("loading input recording {}", path);
+        let text =
+            fs::read_to_string(path).map_err(|why| format!("could not read {} : {}", path, why))?;
+        InputRecording::from_text(&text)
+    }
+}
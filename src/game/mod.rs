@@ -0,0 +1,12 @@
+//game/mod.rs
+
+//assets -- named asset registry and caching
+//core -- entities, components and systems run every tick
+//input -- per frame keyboard input, recording and replay
+//physics -- bodies with gravity and tile collision in fixed steps
+//tween -- easing curves and tweens for animating values
+pub mod assets;
+pub mod core;
+pub mod input;
+pub mod physics;
+pub mod tween;
@@ -0,0 +1,370 @@
+//physics.rs
+use crate::collision::Rect;
+use crate::sprite::{x_pos, y_pos};
+use crate::tilemap::Tilemap;
+use std::time::Duration;
+
+///handle of a body in a PhysicsWorld
+pub type BodyID = usize;
+
+///most fixed steps a single `PhysicsWorld::update` runs, so a long stall does not freeze the game
+///catching up
+const MAX_STEPS_PER_UPDATE: u32 = 8;
+
+///sides of a body that touched a solid tile or body during the last step
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Contacts {
+    pub left: bool,
+    pub right: bool,
+    pub top: bool,
+    pub bottom: bool,
+}
+
+///axis aligned box moved by a PhysicsWorld, positions are in worldspace pixels and velocities
+///and accelerations in pixels per second
+#[derive(Clone, Debug, PartialEq)]
+pub struct Body {
+    ///left edge
+    pub x: f32,
+    ///top edge
+    pub y: f32,
+    pub width: u16,
+    pub height: u16,
+    pub velocity: (f32, f32),
+    ///acceleration applied on top of gravity, e.g. from input
+    pub acceleration: (f32, f32),
+    ///multiplier of the world gravity, 0 for bodies that float
+    pub gravity_scale: f32,
+    ///fixed bodies never move (platforms, walls, doors)
+    pub fixed: bool,
+    ///other bodies cannot move through a solid body
+    pub solid: bool,
+    ///the body is stopped by solid tiles of the tilemap passed to `step`
+    pub collide_tiles: bool,
+    ///sides that touched something during the last step
+    pub contacts: Contacts,
+}
+
+impl Body {
+    ///returns a moving, solid body with its top left corner at (`x`,`y`)
+    ///# Example
+    ///```
+    ///let player = world.add(Body::new(8.0,8.0,4,6));
+    ///```
+    pub fn new(x: f32, y: f32, width: u16, height: u16) -> Self {
+        Body {
+            x,
+            y,
+            width,
+            height,
+            velocity: (0.0, 0.0),
+            acceleration: (0.0, 0.0),
+            gravity_scale: 1.0,
+            fixed: false,
+            solid: true,
+            collide_tiles: true,
+            contacts: Contacts::default(),
+        }
+    }
+    ///returns a body that never moves but blocks other bodies
+    pub fn fixed(x: f32, y: f32, width: u16, height: u16) -> Self {
+        Body {
+            fixed: true,
+            gravity_scale: 0.0,
+            ..Body::new(x, y, width, height)
+        }
+    }
+    ///returns the pixel to draw the body at
+    pub fn position(&self) -> (x_pos, y_pos) {
+        (self.x.round() as x_pos, self.y.round() as y_pos)
+    }
+    ///returns the pixel bounding box of the body, e.g. for a SpatialHash
+    pub fn rect(&self) -> Rect {
+        let (x, y) = self.position();
+        Rect::new(x, y, self.width, self.height)
+    }
+    ///returns true if the body stood on something during the last step, with gravity pointing down
+    pub fn on_ground(&self) -> bool {
+        self.contacts.bottom
+    }
+}
+
+///bodies moved in fixed time steps with gravity and axis separated collision resolution
+///against solid tiles and other bodies
+///every step a moving body is moved along x and then along y, stopping flush against the first
+///solid tile or body in its way, so fast bodies do not tunnel through thin walls
+pub struct PhysicsWorld {
+    ///acceleration applied to every body, scaled by `Body::gravity_scale`
+    pub gravity: (f32, f32),
+    ///length of one fixed step
+    pub timestep: Duration,
+    bodies: Vec<Option<Body>>,
+    ///time not yet simulated by `update`
+    accumulator: Duration,
+    ///pairs of bodies that touched during the last step, (moving body, body it ran into)
+    touching: Vec<(BodyID, BodyID)>,
+}
+
+///one dimensional span [`start`,`end`)
+#[derive(Clone, Copy)]
+struct Span {
+    start: f32,
+    end: f32,
+}
+
+///tolerance below which spans are considered touching rather than overlapping
+const EPSILON: f32 = 1e-4;
+
+impl Span {
+    fn overlaps(&self, other: &Span) -> bool {
+        self.start < other.end - EPSILON && other.start < self.end - EPSILON
+    }
+}
+
+///returns the spans of a box along the moving axis and the other axis
+fn spans(x: f32, y: f32, width: u16, height: u16, horizontal: bool) -> (Span, Span) {
+    let along_x = Span {
+        start: x,
+        end: x + width as f32,
+    };
+    let along_y = Span {
+        start: y,
+        end: y + height as f32,
+    };
+    if horizontal {
+        (along_x, along_y)
+    } else {
+        (along_y, along_x)
+    }
+}
+
+///moves `span` by `delta` along its axis and stops it flush against the first obstacle in its
+///way, obstacles that already overlap the span are ignored so stuck bodies can move out
+///returns the distance actually moved and whether an obstacle was hit
+fn sweep(span: Span, delta: f32, obstacles: impl Iterator<Item = Span>) -> (f32, bool) {
+    let mut moved = delta;
+    let mut hit = false;
+    for obstacle in obstacles {
+        if span.overlaps(&obstacle) {
+            continue;
+        }
+        if delta > 0.0 && obstacle.start >= span.end - EPSILON {
+            let gap = (obstacle.start - span.end).max(0.0);
+            if gap < moved {
+                moved = gap;
+                hit = true;
+            }
+        } else if delta < 0.0 && obstacle.end <= span.start + EPSILON {
+            let gap = (obstacle.end - span.start).min(0.0);
+            if gap > moved {
+                moved = gap;
+                hit = true;
+            }
+        }
+    }
+    (moved, hit)
+}
+
+///returns the spans of every solid tile a box moving along one axis could run into
+fn tile_obstacles(
+    map: &Tilemap,
+    moving: Span,
+    other: Span,
+    delta: f32,
+    horizontal: bool,
+) -> Vec<Span> {
+    let (tile_along, tile_across) = if horizontal {
+        (
+            map.tileset.tile_width.max(1) as f32,
+            map.tileset.tile_height.max(1) as f32,
+        )
+    } else {
+        (
+            map.tileset.tile_height.max(1) as f32,
+            map.tileset.tile_width.max(1) as f32,
+        )
+    };
+    let start = moving.start.min(moving.start + delta);
+    let end = moving.end.max(moving.end + delta);
+    let first = (start / tile_along).floor() as i32;
+    let last = ((end - EPSILON) / tile_along).floor() as i32;
+    let first_across = (other.start / tile_across).floor() as i32;
+    let last_across = ((other.end - EPSILON) / tile_across).floor() as i32;
+    let mut obstacles: Vec<Span> = vec![];
+    for along in first..=last {
+        let solid = (first_across..=last_across).any(|across| {
+            if horizontal {
+                map.is_solid(along, across)
+            } else {
+                map.is_solid(across, along)
+            }
+        });
+        if solid {
+            obstacles.push(Span {
+                start: along as f32 * tile_along,
+                end: (along + 1) as f32 * tile_along,
+            });
+        }
+    }
+    obstacles
+}
+
+impl PhysicsWorld {
+    ///returns an empty world stepping at 60 steps per second
+    ///# Parameters
+    ///- `gravity` : acceleration of every body in pixels per second squared, (0.0,0.0) for
+    ///  top down games
+    ///# Example
+    ///```
+    ///let mut world = PhysicsWorld::new((0.0,120.0));
+    ///```
+    pub fn new(gravity: (f32, f32)) -> Self {
+        println!("initializing PhysicsWorld");
+        PhysicsWorld {
+            gravity,
+            timestep: Duration::from_secs(1) / 60,
+            bodies: vec![],
+            accumulator: Duration::ZERO,
+            touching: vec![],
+        }
+    }
+    ///adds a body and returns its handle
+    pub fn add(&mut self, body: Body) -> BodyID {
+        self.bodies.push(Some(body));
+        self.bodies.len() - 1
+    }
+    ///removes a body, its handle is never reused
+    pub fn remove(&mut self, id: BodyID) -> Option<Body> {
+        self.bodies.get_mut(id).and_then(Option::take)
+    }
+    pub fn get(&self, id: BodyID) -> Option<&Body> {
+        self.bodies.get(id).and_then(Option::as_ref)
+    }
+    pub fn get_mut(&mut self, id: BodyID) -> Option<&mut Body> {
+        self.bodies.get_mut(id).and_then(Option::as_mut)
+    }
+    ///returns every body with its handle
+    pub fn bodies(&self) -> impl Iterator<Item = (BodyID, &Body)> {
+        self.bodies.iter().enumerate().filter_map(|(id, body)| body.as_ref().map(|body| (id, body)))
+    }
+    ///returns the pairs of bodies that ran into each other during the last step,
+    ///as (moving body, body it ran into)
+    pub fn touching(&self) -> &[(BodyID, BodyID)] {
+        &self.touching
+    }
+    ///runs as many fixed steps as fit in the time elapsed since the last update, leftover time
+    ///is carried over to the next update, returns the number of steps run
+    ///# Parameters
+    ///- `elapsed` : time since the last update
+    ///- `map` : tilemap whose solid tiles stop bodies, if any
+    ///# Example
+    ///```
+    ///let now = Instant::now();
+    ///world.update(now - last_frame, Some(&level));
+    ///last_frame = now;
+    ///```
+    pub fn update(&mut self, elapsed: Duration, map: Option<&Tilemap>) -> u32 {
+        let timestep = self.timestep.max(Duration::from_micros(100));
+        self.accumulator += elapsed;
+        let mut steps = 0;
+        while self.accumulator >= timestep && steps < MAX_STEPS_PER_UPDATE {
+            self.accumulator -= timestep;
+            self.step(map);
+            steps += 1;
+        }
+        if steps == MAX_STEPS_PER_UPDATE {
+            //drop the time we could not catch up on instead of carrying it forever
+            self.accumulator = self.accumulator.min(timestep);
+        }
+        steps
+    }
+    ///advances the world by one fixed step of `timestep`
+    ///bodies move in the order they were added, each against the current position of the others
+    pub fn step(&mut self, map: Option<&Tilemap>) {
+        let dt = self.timestep.as_secs_f32();
+        self.touching.clear();
+        for id in 0..self.bodies.len() {
+            let Some(mut body) = self.bodies[id].take() else {
+                continue;
+            };
+            body.contacts = Contacts::default();
+            if !body.fixed {
+                body.velocity.0 += (body.acceleration.0 + self.gravity.0 * body.gravity_scale) * dt;
+                body.velocity.1 += (body.acceleration.1 + self.gravity.1 * body.gravity_scale) * dt;
+                let (dx, dy) = (body.velocity.0 * dt, body.velocity.1 * dt);
+                self.move_axis(id, &mut body, dx, true, map);
+                self.move_axis(id, &mut body, dy, false, map);
+            }
+            self.bodies[id] = Some(body);
+        }
+    }
+    ///moves a body (taken out of the world) along one axis, stopping at obstacles
+    fn move_axis(
+        &mut self,
+        id: BodyID,
+        body: &mut Body,
+        delta: f32,
+        horizontal: bool,
+        map: Option<&Tilemap>,
+    ) {
+        if delta == 0.0 {
+            return;
+        }
+        let (moving, other) = spans(body.x, body.y, body.width, body.height, horizontal);
+        let mut moved = delta;
+        let mut hit_tile = false;
+        if let Some(map) = map.filter(|_| body.collide_tiles) {
+            let obstacles = tile_obstacles(map, moving, other, delta, horizontal);
+            (moved, hit_tile) = sweep(moving, delta, obstacles.into_iter());
+        }
+        //bodies in the way, nearest first so the one actually hit is recorded
+        let mut blockers: Vec<(BodyID, Span)> = self
+            .bodies()
+            .filter(|(_, blocker)| blocker.solid && body.solid)
+            .filter_map(|(blocker_id, blocker)| {
+                let (along, across) = spans(
+                    blocker.x,
+                    blocker.y,
+                    blocker.width,
+                    blocker.height,
+                    horizontal,
+                );
+                across.overlaps(&other).then_some((blocker_id, along))
+            })
+            .collect();
+        blockers.sort_by(|(_, a), (_, b)| {
+            let distance = |span: &Span| if delta > 0.0 { span.start } else { -span.end };
+            distance(a).total_cmp(&distance(b))
+        });
+        let mut hit_body = None;
+        for (blocker_id, span) in blockers {
+            let (limited, hit) = sweep(moving, moved, std::iter::once(span));
+            if hit {
+                moved = limited;
+                hit_body = Some(blocker_id);
+                break;
+            }
+        }
+        if let Some(blocker_id) = hit_body {
+            self.touching.push((id, blocker_id));
+        }
+        if hit_tile || hit_body.is_some() {
+            match (horizontal, delta > 0.0) {
+                (true, true) => body.contacts.right = true,
+                (true, false) => body.contacts.left = true,
+                (false, true) => body.contacts.bottom = true,
+                (false, false) => body.contacts.top = true,
+            }
+            if horizontal {
+                body.velocity.0 = 0.0;
+            } else {
+                body.velocity.1 = 0.0;
+            }
+        }
+        if horizontal {
+            body.x += moved;
+        } else {
+            body.y += moved;
+        }
+    }
+}
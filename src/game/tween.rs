@@ -0,0 +1,371 @@
+//tween.rs
+use crate::color::to_rgb;
+use crate::stack::{LayerID, Layerstack};
+use crossterm::style::Color;
+use std::f32::consts::PI;
+
+///easing curves mapping linear progress (0.0 to 1.0) to eased progress
+///elastic curves overshoot below 0.0 and above 1.0
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Easing {
+    #[default]
+    Linear,
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+}
+
+fn bounce_out(t: f32) -> f32 {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1.0 / D {
+        N * t * t
+    } else if t < 2.0 / D {
+        let t = t - 1.5 / D;
+        N * t * t + 0.75
+    } else if t < 2.5 / D {
+        let t = t - 2.25 / D;
+        N * t * t + 0.9375
+    } else {
+        let t = t - 2.625 / D;
+        N * t * t + 0.984375
+    }
+}
+
+impl Easing {
+    ///returns the eased progress for linear progress `t`, clamped to 0.0..=1.0
+    pub fn apply(self, t: f32) -> f32 {
+        let t = t.clamp(0.0, 1.0);
+        match self {
+            Easing::Linear => t,
+            Easing::QuadIn => t * t,
+            Easing::QuadOut => 1.0 - (1.0 - t) * (1.0 - t),
+            Easing::QuadInOut if t < 0.5 => 2.0 * t * t,
+            Easing::QuadInOut => 1.0 - (-2.0 * t + 2.0).powi(2) / 2.0,
+            Easing::CubicIn => t * t * t,
+            Easing::CubicOut => 1.0 - (1.0 - t).powi(3),
+            Easing::CubicInOut if t < 0.5 => 4.0 * t * t * t,
+            Easing::CubicInOut => 1.0 - (-2.0 * t + 2.0).powi(3) / 2.0,
+            _ if t == 0.0 || t == 1.0 => t,
+            Easing::ElasticIn => {
+                -(2f32.powf(10.0 * t - 10.0)) * ((t * 10.0 - 10.75) * (2.0 * PI / 3.0)).sin()
+            }
+            Easing::ElasticOut => {
+                2f32.powf(-10.0 * t) * ((t * 10.0 - 0.75) * (2.0 * PI / 3.0)).sin() + 1.0
+            }
+            Easing::ElasticInOut => {
+                let wave = ((20.0 * t - 11.125) * (2.0 * PI / 4.5)).sin();
+                if t < 0.5 {
+                    -(2f32.powf(20.0 * t - 10.0) * wave) / 2.0
+                } else {
+                    2f32.powf(-20.0 * t + 10.0) * wave / 2.0 + 1.0
+                }
+            }
+            Easing::BounceIn => 1.0 - bounce_out(1.0 - t),
+            Easing::BounceOut => bounce_out(t),
+            Easing::BounceInOut if t < 0.5 => (1.0 - bounce_out(1.0 - 2.0 * t)) / 2.0,
+            Easing::BounceInOut => (1.0 + bounce_out(2.0 * t - 1.0)) / 2.0,
+        }
+    }
+}
+
+///values a Tween can interpolate
+pub trait Lerp: Copy {
+    ///returns the value `t` of the way from `self` to `to`, `t` may leave 0.0..=1.0 for
+    ///overshooting easings
+    fn lerp(self, to: Self, t: f32) -> Self;
+}
+
+impl Lerp for f32 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        self + (to - self) * t
+    }
+}
+
+///whole pixel positions, rounded to the nearest pixel
+impl Lerp for i16 {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        (self as f32).lerp(to as f32, t).round() as i16
+    }
+}
+
+///positions, camera offsets and other pairs, each part interpolated on its own
+impl<A: Lerp, B: Lerp> Lerp for (A, B) {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        (self.0.lerp(to.0, t), self.1.lerp(to.1, t))
+    }
+}
+
+///colors are interpolated in RGB, the result is always `Color::Rgb`
+impl Lerp for Color {
+    fn lerp(self, to: Self, t: f32) -> Self {
+        let (from, to) = (to_rgb(self), to_rgb(to));
+        let channel =
+            |from: u8, to: u8| (from as f32).lerp(to as f32, t).round().clamp(0.0, 255.0) as u8;
+        Color::Rgb {
+            r: channel(from.0, to.0),
+            g: channel(from.1, to.1),
+            b: channel(from.2, to.2),
+        }
+    }
+}
+
+///one step of a Tween, moving from the end of the previous step to `to`
+struct Segment<T> {
+    to: T,
+    ticks: u64,
+    easing: Easing,
+}
+
+///interpolates a value over time, ticked once per frame by the game loop
+///a tween is a sequence of steps played after an optional delay, repeated and optionally
+///played backwards every other pass
+///# Example
+///```
+///let mut slide = Tween::new((0i16,10i16),(40,10),30,Easing::CubicOut)
+///    .wait(10)
+///    .then((0,10),30,Easing::QuadIn)
+///    .repeat_forever();
+///loop {
+///    let (x,y) = slide.tick();
+///    renderer.layerstack.write_sprite(x,y,&dino,1);
+///}
+///```
+pub struct Tween<T: Lerp> {
+    from: T,
+    segments: Vec<Segment<T>>,
+    ///ticks before the first pass starts
+    delay: u64,
+    ///number of passes, `None` repeats forever
+    passes: Option<u64>,
+    ///every other pass plays backwards
+    yoyo: bool,
+    elapsed: u64,
+    value: T,
+    finished: bool,
+    on_complete: Option<Box<dyn FnMut()>>,
+}
+
+impl<T: Lerp> Tween<T> {
+    ///returns a tween from `from` to `to` over `ticks` ticks
+    pub fn new(from: T, to: T, ticks: u64, easing: Easing) -> Self {
+        Tween {
+            from,
+            segments: vec![Segment { to, ticks, easing }],
+            delay: 0,
+            passes: Some(1),
+            yoyo: false,
+            elapsed: 0,
+            value: from,
+            finished: false,
+            on_complete: None,
+        }
+    }
+    ///returns the value every step ends at
+    fn last_value(&self) -> T {
+        self.segments.last().map(|segment| segment.to).unwrap_or(self.from)
+    }
+    ///adds a step from the end of the previous step to `to`
+    pub fn then(mut self, to: T, ticks: u64, easing: Easing) -> Self {
+        self.segments.push(Segment { to, ticks, easing });
+        self
+    }
+    ///adds a step holding the current value for `ticks` ticks
+    pub fn wait(self, ticks: u64) -> Self {
+        let to = self.last_value();
+        self.then(to, ticks, Easing::Linear)
+    }
+    ///waits `ticks` ticks before the first pass, the delay is not repeated
+    pub fn delay(mut self, ticks: u64) -> Self {
+        self.delay = ticks;
+        self
+    }
+    ///plays the tween `times` more times after the first pass
+    pub fn repeat(mut self, times: u64) -> Self {
+        self.passes = Some(times + 1);
+        self
+    }
+    ///plays the tween until it is dropped, it never finishes
+    pub fn repeat_forever(mut self) -> Self {
+        self.passes = None;
+        self
+    }
+    ///plays every other pass backwards, so repeats go back and forth
+    pub fn yoyo(mut self) -> Self {
+        self.yoyo = true;
+        self
+    }
+    ///calls `callback` once when the last pass ends
+    pub fn on_complete(mut self, callback: impl FnMut() + 'static) -> Self {
+        self.on_complete = Some(Box::new(callback));
+        self
+    }
+    ///returns the value at `position` ticks into a pass
+    fn value_at(&self, position: u64) -> T {
+        let mut start = self.from;
+        let mut remaining = position;
+        for segment in self.segments.iter() {
+            if remaining < segment.ticks {
+                let progress = segment.easing.apply(remaining as f32 / segment.ticks as f32);
+                return start.lerp(segment.to, progress);
+            }
+            remaining -= segment.ticks;
+            start = segment.to;
+        }
+        start
+    }
+    ///advances the tween by one tick and returns its new value
+    pub fn tick(&mut self) -> T {
+        if self.finished {
+            return self.value;
+        }
+        self.elapsed += 1;
+        let Some(played) = self.elapsed.checked_sub(self.delay) else {
+            return self.value;
+        };
+        let length: u64 = self.segments.iter().map(|segment| segment.ticks).sum();
+        let (pass, position) = match length {
+            0 => (self.passes.unwrap_or(1), 0),
+            _ => (played / length, played % length),
+        };
+        if self.passes.is_some_and(|passes| pass >= passes) {
+            let last_pass_backwards = self.yoyo && self.passes.unwrap_or(1).is_multiple_of(2);
+            self.value = if last_pass_backwards {
+                self.from
+            } else {
+                self.last_value()
+            };
+            self.finished = true;
+            if let Some(mut callback) = self.on_complete.take() {
+                callback();
+            }
+            return self.value;
+        }
+        let backwards = self.yoyo && pass % 2 == 1;
+        self.value = self.value_at(if backwards {
+            length - position
+        } else {
+            position
+        });
+        self.value
+    }
+    ///returns the current value without advancing
+    pub fn value(&self) -> T {
+        self.value
+    }
+    ///returns true once the last pass ended, tweens repeating forever never finish
+    pub fn is_finished(&self) -> bool {
+        self.finished
+    }
+    ///restarts the tween from the beginning, including its delay
+    ///the completion callback is only called again if it has not run yet
+    pub fn restart(&mut self) {
+        self.elapsed = 0;
+        self.value = self.from;
+        self.finished = false;
+    }
+}
+
+///handle of a tween added to Tweens
+pub type TweenID = u32;
+
+///type erased tween applying its value to the layerstack every tick
+trait Running {
+    ///advances the tween, applies its value and returns true once it finished
+    fn tick(&mut self, layerstack: &mut Layerstack) -> bool;
+}
+
+struct Bound<T: Lerp, F: FnMut(T, &mut Layerstack)> {
+    tween: Tween<T>,
+    apply: F,
+}
+
+impl<T: Lerp, F: FnMut(T, &mut Layerstack)> Running for Bound<T, F> {
+    fn tick(&mut self, layerstack: &mut Layerstack) -> bool {
+        let value = self.tween.tick();
+        (self.apply)(value, layerstack);
+        self.tween.is_finished()
+    }
+}
+
+///tweens driving layerstack properties (layer opacity, tint...), ticked once per frame
+///finished tweens are dropped after applying their last value
+///# Example
+///```
+///let mut tweens = Tweens::new();
+///tweens.fade_layer(2,Tween::new(0.0,1.0,20,Easing::QuadOut));
+///tweens.tint_layer(0,Tween::new(Color::White,Color::Red,10,Easing::Linear).yoyo().repeat(3),0.5);
+///loop {
+///    tweens.tick(&mut renderer.layerstack);
+///    renderer.render_update();
+///}
+///```
+#[derive(Default)]
+pub struct Tweens {
+    running: Vec<(TweenID, Box<dyn Running>)>,
+    next_id: TweenID,
+}
+
+impl Tweens {
+    pub fn new() -> Self {
+        Tweens::default()
+    }
+    ///adds a tween whose value is passed to `apply` every tick, returns its handle
+    ///# Example
+    ///```
+    ///tweens.add(Tween::new((0i16,0i16),(64,0),120,Easing::Linear),move |camera,layerstack| {
+    ///    layerstack.draw_tilemap(&level,0,camera,tick,0);
+    ///});
+    ///```
+    pub fn add<T: Lerp + 'static>(
+        &mut self,
+        tween: Tween<T>,
+        apply: impl FnMut(T, &mut Layerstack) + 'static,
+    ) -> TweenID {
+        self.next_id += 1;
+        self.running.push((self.next_id, Box::new(Bound { tween, apply })));
+        self.next_id
+    }
+    ///tweens the opacity of a layer, see `Layerstack::set_opacity`
+    pub fn fade_layer(&mut self, layer_id: LayerID, tween: Tween<f32>) -> TweenID {
+        self.add(tween, move |opacity, layerstack| {
+            layerstack.set_opacity(layer_id, opacity)
+        })
+    }
+    ///tweens the tint color of a layer at a fixed strength, see `Layerstack::set_tint`
+    pub fn tint_layer(&mut self, layer_id: LayerID, tween: Tween<Color>, strength: f32) -> TweenID {
+        self.add(tween, move |tint, layerstack| {
+            layerstack.set_tint(layer_id, tint, strength)
+        })
+    }
+    ///stops a tween where it is, returns false if it already finished
+    pub fn remove(&mut self, id: TweenID) -> bool {
+        let count = self.running.len();
+        self.running.retain(|(existing, _)| *existing != id);
+        self.running.len() != count
+    }
+    ///returns true while the tween has not finished
+    pub fn is_running(&self, id: TweenID) -> bool {
+        self.running.iter().any(|(existing, _)| *existing == id)
+    }
+    ///returns the number of running tweens
+    pub fn len(&self) -> usize {
+        self.running.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.running.is_empty()
+    }
+    ///advances every tween by one tick in the order they were added and applies their values
+    pub fn tick(&mut self, layerstack: &mut Layerstack) {
+        self.running.retain_mut(|(_, tween)| !tween.tick(layerstack));
+    }
+}
@@ -0,0 +1,427 @@
+//image.rs
+use crate::sprite::{x_pos, y_pos, Pixel, Sprite};
+#[cfg(feature = "png")]
+use crate::stack::FrameBuffer;
+use crossterm::style::Color;
+use std::fs;
+
+///decoded image, row major RGBA
+struct RawImage {
+    width: u16,
+    height: u16,
+    pixels: Vec<[u8; 4]>,
+}
+
+///file extensions of the image formats `load_image` reads
+pub const IMAGE_EXTENSIONS: [&str; 4] = ["png", "bmp", "ppm", "pam"];
+
+///loads an image file into a Sprite
+///the format is detected from the file contents, supported formats are
+///PPM (P3/P6), PAM (P7), 24/32 bit BMP and PNG (with the `png` feature)
+///# Parameters
+///- `path` : path of the image file
+///- `key` : color treated as transparent, pixels of this color are not rendered
+///# Example
+///```
+///let dino : Sprite = load_image("assets/dino.bmp",Some(Color::Rgb{r:255,g:0,b:255}))?;
+///```
+///dino is the image with all magenta pixels transparent
+///
+///pixels with an alpha channel value of 0 are never rendered, other alpha values are kept in `Pixel::alpha`
+pub fn load_image(path: &str, key: Option<Color>) -> Result<Sprite, String> {
+    println!("loading image {}", path);
+    let bytes = fs::read(path).map_err(|why| format!("could not read {} : {}", path, why))?;
+    image_to_sprite(&bytes, key)
+}
+
+///decodes an image held in memory into a Sprite, see `load_image`
+pub fn image_to_sprite(bytes: &[u8], key: Option<Color>) -> Result<Sprite, String> {
+    let image = match bytes.get(0..2) {
+        Some(b"P3") | Some(b"P6") => decode_ppm(bytes)?,
+        Some(b"P7") => decode_pam(bytes)?,
+        Some(b"BM") => decode_bmp(bytes)?,
+        Some(b"\x89P") => decode_png(bytes)?,
+        _ => return Err("unrecognised image format, expected PPM, PAM, BMP or PNG".to_string()),
+    };
+    Ok(raw_to_sprite(image, key))
+}
+
+///turns decoded RGBA data into a Sprite
+fn raw_to_sprite(image: RawImage, key: Option<Color>) -> Sprite {
+    let key = key.map(|key| match key {
+        Color::Rgb { r, g, b } => (r, g, b),
+        named => crate::color::to_rgb(named),
+    });
+    let mut sprite = Sprite {
+        pixels: vec![],
+        height: image.height,
+        width: image.width,
+        center: (image.width / 2, image.height / 2),
+        tag: None,
+    };
+    for (index, [r, g, b, a]) in image.pixels.into_iter().enumerate() {
+        let keyed = key == Some((r, g, b));
+        sprite.pixels.push(Pixel {
+            x: (index % image.width as usize) as x_pos,
+            y: (index / image.width as usize) as y_pos,
+            color: Color::Rgb { r, g, b },
+            isrendered: !keyed && a != 0,
+            alpha: a,
+        });
+    }
+    sprite
+}
+
+///reads whitespace separated header tokens of a netpbm file, skipping `#` comments
+///returns the tokens and the offset of the byte following the last token's terminating whitespace
+fn netpbm_tokens(bytes: &[u8], count: usize) -> Result<(Vec<String>, usize), String> {
+    let mut tokens: Vec<String> = vec![];
+    let mut offset = 0;
+    while tokens.len() < count {
+        match bytes.get(offset) {
+            None => return Err("unexpected end of image header".to_string()),
+            Some(b'#') => {
+                while offset < bytes.len() && bytes[offset] != b'\n' {
+                    offset += 1;
+                }
+            }
+            Some(byte) if byte.is_ascii_whitespace() => offset += 1,
+            Some(_) => {
+                let start = offset;
+                while offset < bytes.len() && !bytes[offset].is_ascii_whitespace() {
+                    offset += 1;
+                }
+                tokens.push(String::from_utf8_lossy(&bytes[start..offset]).to_string());
+            }
+        }
+    }
+    //a single whitespace character separates the header from binary data
+    Ok((tokens, offset + 1))
+}
+
+///parses a header value into a number
+fn parse_number(token: &str, name: &str) -> Result<u32, String> {
+    token.parse::<u32>().map_err(|_| format!("invalid {} in image header : {}", name, token))
+}
+
+///checks image dimensions fit into a Sprite
+fn check_size(width: u32, height: u32) -> Result<(u16, u16), String> {
+    if width == 0 || height == 0 || width > u16::MAX as u32 || height > u16::MAX as u32 {
+        return Err(format!("unsupported image size {}x{}", width, height));
+    }
+    Ok((width as u16, height as u16))
+}
+
+///scales a sample with maximum `maxval` into 0..=255
+///works in u64 as BMP masks can make `maxval` as wide as 32 bits
+fn scale(sample: u32, maxval: u32) -> u8 {
+    let (sample, maxval) = (sample.min(maxval) as u64, maxval as u64);
+    ((sample * 255 + maxval / 2) / maxval) as u8
+}
+
+///reads `count` binary samples of `maxval` precision starting at `offset`
+fn binary_samples(
+    bytes: &[u8],
+    offset: usize,
+    count: usize,
+    maxval: u32,
+) -> Result<Vec<u8>, String> {
+    let sample_size = if maxval > 255 { 2 } else { 1 };
+    let data = bytes
+        .get(offset..offset + count * sample_size)
+        .ok_or("image data is shorter than its header describes")?;
+    Ok(data
+        .chunks(sample_size)
+        .map(|sample| {
+            let value = match sample {
+                [high, low] => (*high as u32) << 8 | *low as u32,
+                [value] => *value as u32,
+                _ => 0,
+            };
+            scale(value, maxval)
+        })
+        .collect())
+}
+
+///decodes a plain (P3) or raw (P6) PPM
+fn decode_ppm(bytes: &[u8]) -> Result<RawImage, String> {
+    let (header, offset) = netpbm_tokens(bytes, 4)?;
+    let (width, height) = check_size(
+        parse_number(&header[1], "width")?,
+        parse_number(&header[2], "height")?,
+    )?;
+    let maxval = parse_number(&header[3], "maxval")?;
+    if maxval == 0 || maxval > 65535 {
+        return Err(format!("invalid maxval {}", maxval));
+    }
+    let count = width as usize * height as usize * 3;
+    let samples = if header[0] == "P6" {
+        binary_samples(bytes, offset, count, maxval)?
+    } else {
+        let (tokens, _) = netpbm_tokens(bytes, 4 + count)?;
+        tokens[4..]
+            .iter()
+            .map(|token| Ok(scale(parse_number(token, "sample")?, maxval)))
+            .collect::<Result<Vec<u8>, String>>()?
+    };
+    Ok(RawImage {
+        width,
+        height,
+        pixels: samples.chunks(3).map(|rgb| [rgb[0], rgb[1], rgb[2], 255]).collect(),
+    })
+}
+
+///decodes a PAM (P7) with a GRAYSCALE, GRAYSCALE_ALPHA, RGB or RGB_ALPHA tuple type
+fn decode_pam(bytes: &[u8]) -> Result<RawImage, String> {
+    let mut width = 0;
+    let mut height = 0;
+    let mut depth = 0;
+    let mut maxval = 0;
+    let mut offset = 0;
+    loop {
+        let end = bytes[offset..]
+            .iter()
+            .position(|byte| *byte == b'\n')
+            .ok_or("PAM header is missing ENDHDR")?;
+        let line = String::from_utf8_lossy(&bytes[offset..offset + end]).to_string();
+        offset += end + 1;
+        let mut fields = line.split_whitespace();
+        match (fields.next(), fields.next()) {
+            (Some("ENDHDR"), _) => break,
+            (Some("WIDTH"), Some(value)) => width = parse_number(value, "width")?,
+            (Some("HEIGHT"), Some(value)) => height = parse_number(value, "height")?,
+            (Some("DEPTH"), Some(value)) => depth = parse_number(value, "depth")?,
+            (Some("MAXVAL"), Some(value)) => maxval = parse_number(value, "maxval")?,
+            _ => {}
+        }
+    }
+    let (width, height) = check_size(width, height)?;
+    if !(1..=4).contains(&depth) {
+        return Err(format!("unsupported PAM depth {}", depth));
+    }
+    if maxval == 0 || maxval > 65535 {
+        return Err(format!("invalid maxval {}", maxval));
+    }
+    let count = width as usize * height as usize * depth as usize;
+    let samples = binary_samples(bytes, offset, count, maxval)?;
+    Ok(RawImage {
+        width,
+        height,
+        pixels: samples
+            .chunks(depth as usize)
+            .map(|tuple| match tuple {
+                [gray] => [*gray, *gray, *gray, 255],
+                [gray, alpha] => [*gray, *gray, *gray, *alpha],
+                [r, g, b] => [*r, *g, *b, 255],
+                [r, g, b, alpha] => [*r, *g, *b, *alpha],
+                _ => [0, 0, 0, 0],
+            })
+            .collect(),
+    })
+}
+
+///reads a little endian u16 from `bytes` at `offset`
+fn read_u16(bytes: &[u8], offset: usize) -> Result<u16, String> {
+    bytes
+        .get(offset..offset + 2)
+        .map(|data| u16::from_le_bytes([data[0], data[1]]))
+        .ok_or("BMP header is truncated".to_string())
+}
+
+///reads a little endian u32 from `bytes` at `offset`
+fn read_u32(bytes: &[u8], offset: usize) -> Result<u32, String> {
+    bytes
+        .get(offset..offset + 4)
+        .map(|data| u32::from_le_bytes([data[0], data[1], data[2], data[3]]))
+        .ok_or("BMP header is truncated".to_string())
+}
+
+///extracts the channel selected by `mask` from a 32 bit pixel and scales it into 0..=255
+fn masked_channel(value: u32, mask: u32) -> u8 {
+    if mask == 0 {
+        return 0;
+    }
+    let shifted = (value & mask) >> mask.trailing_zeros();
+    let max = mask >> mask.trailing_zeros();
+    scale(shifted, max)
+}
+
+///decodes an uncompressed (BI_RGB or BI_BITFIELDS) 24 or 32 bit BMP
+fn decode_bmp(bytes: &[u8]) -> Result<RawImage, String> {
+    let data_offset = read_u32(bytes, 10)? as usize;
+    let header_size = read_u32(bytes, 14)?;
+    if header_size < 40 {
+        return Err("only BMP files with a BITMAPINFOHEADER or newer are supported".to_string());
+    }
+    let width = read_u32(bytes, 18)? as i32;
+    let raw_height = read_u32(bytes, 22)? as i32;
+    let bits = read_u16(bytes, 28)?;
+    let compression = read_u32(bytes, 30)?;
+    //negative height means rows are stored top to bottom, a negative width is never valid
+    let top_down = raw_height < 0;
+    if width < 0 {
+        return Err(format!("invalid BMP width {}", width));
+    }
+    let (width, height) = check_size(width as u32, raw_height.unsigned_abs())?;
+
+    let mut masks = (0x00ff0000, 0x0000ff00, 0x000000ff, 0xff000000);
+    match (bits, compression) {
+        (24, 0) | (32, 0) => {}
+        (32, 3) => {
+            //masks follow a 40 byte header, or live inside a V4/V5 header
+            masks = (
+                read_u32(bytes, 54)?,
+                read_u32(bytes, 58)?,
+                read_u32(bytes, 62)?,
+                if header_size >= 56 {
+                    read_u32(bytes, 66)?
+                } else {
+                    0
+                },
+            );
+        }
+        _ => {
+            return Err(format!(
+                "unsupported BMP : {} bits per pixel with compression {}",
+                bits, compression
+            ))
+        }
+    }
+
+    let bytes_per_pixel = bits as usize / 8;
+    let row_size = (width as usize * bytes_per_pixel).div_ceil(4) * 4;
+    let mut pixels: Vec<[u8; 4]> = Vec::with_capacity(width as usize * height as usize);
+    for row in 0..height as usize {
+        let stored_row = if top_down {
+            row
+        } else {
+            height as usize - 1 - row
+        };
+        let start = data_offset + stored_row * row_size;
+        let data = bytes
+            .get(start..start + width as usize * bytes_per_pixel)
+            .ok_or("BMP pixel data is truncated")?;
+        for pixel in data.chunks(bytes_per_pixel) {
+            if bits == 24 {
+                pixels.push([pixel[2], pixel[1], pixel[0], 255]);
+            } else {
+                let value = u32::from_le_bytes([pixel[0], pixel[1], pixel[2], pixel[3]]);
+                pixels.push([
+                    masked_channel(value, masks.0),
+                    masked_channel(value, masks.1),
+                    masked_channel(value, masks.2),
+                    masked_channel(value, masks.3),
+                ]);
+            }
+        }
+    }
+    //most 32 bit BMPs leave the alpha byte unused as 0, treat those as opaque
+    if bits == 32 && pixels.iter().all(|pixel| pixel[3] == 0) {
+        for pixel in pixels.iter_mut() {
+            pixel[3] = 255;
+        }
+    }
+    Ok(RawImage {
+        width,
+        height,
+        pixels,
+    })
+}
+
+///decodes a PNG of any color type and bit depth into 8 bit RGBA
+#[cfg(feature = "png")]
+fn decode_png(bytes: &[u8]) -> Result<RawImage, String> {
+    let mut decoder = png::Decoder::new(bytes);
+    decoder.set_transformations(png::Transformations::normalize_to_color8());
+    let mut reader = decoder.read_info().map_err(|why| format!("invalid PNG : {}", why))?;
+    let mut data = vec![0; reader.output_buffer_size()];
+    let info = reader.next_frame(&mut data).map_err(|why| format!("invalid PNG : {}", why))?;
+    let (width, height) = check_size(info.width, info.height)?;
+    let channels = info.color_type.samples();
+    Ok(RawImage {
+        width,
+        height,
+        pixels: data[..info.buffer_size()]
+            .chunks(channels)
+            .map(|tuple| match tuple {
+                [gray] => [*gray, *gray, *gray, 255],
+                [gray, alpha] => [*gray, *gray, *gray, *alpha],
+                [r, g, b] => [*r, *g, *b, 255],
+                [r, g, b, alpha] => [*r, *g, *b, *alpha],
+                _ => [0, 0, 0, 0],
+            })
+            .collect(),
+    })
+}
+
+#[cfg(not(feature = "png"))]
+fn decode_png(_bytes: &[u8]) -> Result<RawImage, String> {
+    Err("PNG support requires the `png` feature".to_string())
+}
+
+///writes 8 bit RGBA data to a PNG file
+#[cfg(feature = "png")]
+fn write_png(path: &str, width: u16, height: u16, data: &[u8]) -> Result<(), String> {
+    let file =
+        fs::File::create(path).map_err(|why| format!("could not create {} : {}", path, why))?;
+    let mut encoder = png::Encoder::new(std::io::BufWriter::new(file), width as u32, height as u32);
+    encoder.set_color(png::ColorType::Rgba);
+    encoder.set_depth(png::BitDepth::Eight);
+    let mut writer =
+        encoder.write_header().map_err(|why| format!("could not write {} : {}", path, why))?;
+    writer.write_image_data(data).map_err(|why| format!("could not write {} : {}", path, why))
+}
+
+///pushes a color as RGBA bytes, non rendered pixels become fully transparent
+#[cfg(feature = "png")]
+fn push_rgba(data: &mut Vec<u8>, pixel: &Pixel) {
+    let (r, g, b) = crate::color::to_rgb(pixel.color);
+    let alpha = if pixel.isrendered { pixel.alpha } else { 0 };
+    data.extend_from_slice(&[r, g, b, alpha]);
+}
+
+///exports a Sprite to a PNG file
+///non rendered pixels are written fully transparent, other pixels keep their alpha
+///# Parameters
+///- `sprite` : sprite to export
+///- `path` : path of the PNG file to create
+///# Example
+///```
+///save_sprite_png(&dino,"dino.png")?;
+///```
+#[cfg(feature = "png")]
+pub fn save_sprite_png(sprite: &Sprite, path: &str) -> Result<(), String> {
+    println!("saving Sprite to {}", path);
+    let mut data: Vec<u8> = vec![0; sprite.width as usize * sprite.height as usize * 4];
+    //sprite pixels are not guaranteed to start at 0, so place them relative to the top left one
+    let min_x = sprite.pixels.iter().map(|pixel| pixel.x).min().unwrap_or(0);
+    let min_y = sprite.pixels.iter().map(|pixel| pixel.y).min().unwrap_or(0);
+    for pixel in sprite.pixels.iter() {
+        let (x, y) = ((pixel.x - min_x) as usize, (pixel.y - min_y) as usize);
+        if x < sprite.width as usize && y < sprite.height as usize {
+            let mut rgba: Vec<u8> = vec![];
+            push_rgba(&mut rgba, pixel);
+            let index = (y * sprite.width as usize + x) * 4;
+            data[index..index + 4].copy_from_slice(&rgba);
+        }
+    }
+    write_png(path, sprite.width, sprite.height, &data)
+}
+
+///exports a FrameBuffer to a PNG file, one image pixel per framebuffer pixel
+///# Parameters
+///- `framebuffer` : frame to export, usually `Renderer::last_frame()`
+///- `path` : path of the PNG file to create
+///# Example
+///```
+///save_frame_png(renderer.last_frame(),"bug_report.png")?;
+///```
+#[cfg(feature = "png")]
+pub fn save_frame_png(framebuffer: &FrameBuffer, path: &str) -> Result<(), String> {
+    println!("saving FrameBuffer to {}", path);
+    let mut data: Vec<u8> = Vec::with_capacity(framebuffer.buffer.len() * 4);
+    for pixel in framebuffer.buffer.iter() {
+        push_rgba(&mut data, pixel);
+    }
+    write_png(path, framebuffer.width(), framebuffer.height(), &data)
+}
@@ -0,0 +1,37 @@
+//lib.rs
+
+//rendering engine library, main.rs is a demo built on top of it
+
+//collision -- bounding box and pixel perfect sprite collision
+//color -- color conversion and blending
+//draw -- primitive shapes and flood fill
+//export -- serializing frames to text formats
+//font -- bitmap fonts and text drawing
+//game -- loadable game module
+//image -- loading and saving image files
+//pack -- single file asset archives
+//particle -- particle emitters drawn to a layer in bulk
+//record -- recording terminal sessions
+//stack -- renderstack logic for actually writing to frames
+//snapshot -- golden frame snapshot testing
+//spatial -- spatial hash for broad phase collision and picking
+//sprite -- handling of sprite actions
+//tiled -- importing maps from the Tiled editor (feature `tiled`, enables `png`)
+//tilemap -- tile grids drawn from tilesets
+pub mod collision;
+pub mod color;
+pub mod draw;
+pub mod export;
+pub mod font;
+pub mod game;
+pub mod image;
+pub mod pack;
+pub mod particle;
+pub mod record;
+pub mod snapshot;
+pub mod spatial;
+pub mod sprite;
+pub mod stack;
+#[cfg(feature = "tiled")]
+pub mod tiled;
+pub mod tilemap;
@@ -11,16 +11,11 @@
 
 //possible libs to use?
 //crossterm
-mod sprite;
-mod stack;
-
-use crate::sprite::{compile_sprite, Metadata, Sprite, SpriteSource};
+use rs_cli_renderer::sprite::{compile_sprite, Metadata, Sprite, SpriteSource};
+use rs_cli_renderer::stack::Renderer;
 use std::collections::HashMap;
-use std::{thread, time};
 
-use crossterm::{execute, style::Color, terminal};
-use stack::Renderer;
-use std::io::{self, Write};
+use crossterm::style::Color;
 
 fn main() {
     println!("main");
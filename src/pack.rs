@@ -0,0 +1,401 @@
+//pack.rs
+use crate::color::NAMED_16;
+use crate::image::{self, IMAGE_EXTENSIONS};
+use crate::sprite::{self, x_pos, y_pos, Pixel, Sprite};
+use crossterm::style::Color;
+use std::fs;
+use std::path::Path;
+
+///first bytes of every pack file
+const MAGIC: &[u8; 4] = b"RSPK";
+///version of the pack format written by `Pack::to_bytes`
+const VERSION: u16 = 1;
+
+///how the data of a pack entry is stored
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EntryKind {
+    ///compiled sprites of a sprite file, in file order
+    Sprites,
+    ///a compiled image, stored without a transparency key
+    Image,
+    ///the file as it is on disk (maps, palettes and anything else)
+    Raw,
+}
+
+///index entry of a pack
+#[derive(Clone, Debug, PartialEq)]
+pub struct PackEntry {
+    ///path of the file relative to the packed directory, with `/` separators
+    pub name: String,
+    pub kind: EntryKind,
+    ///position of the entry data after the index
+    offset: usize,
+    length: usize,
+    ///CRC-32 of the entry data
+    checksum: u32,
+}
+
+///single file archive of a whole asset directory
+///sprite files and images are stored compiled, so loading from a pack skips parsing and decoding
+///# Layout
+///```
+///"RSPK" version:u16 entry_count:u32
+///entry_count x (name_length:u16 name kind:u8 offset:u64 length:u64 crc32:u32)
+///entry data
+///```
+///all numbers are little endian
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Pack {
+    entries: Vec<PackEntry>,
+    data: Vec<u8>,
+}
+
+///returns the CRC-32 (IEEE) of `bytes`
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xFFFF_FFFFu32;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+    }
+    !crc
+}
+
+///bounds checked little endian reads from a byte slice
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offset: usize,
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, count: usize) -> Result<&'a [u8], String> {
+        let end = self.offset.checked_add(count).filter(|end| *end <= self.bytes.len());
+        let end = end.ok_or("unexpected end of pack data".to_string())?;
+        let taken = &self.bytes[self.offset..end];
+        self.offset = end;
+        Ok(taken)
+    }
+    fn u8(&mut self) -> Result<u8, String> {
+        Ok(self.take(1)?[0])
+    }
+    fn u16(&mut self) -> Result<u16, String> {
+        Ok(u16::from_le_bytes(self.take(2)?.try_into().unwrap()))
+    }
+    fn u32(&mut self) -> Result<u32, String> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+    fn u64(&mut self) -> Result<u64, String> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+    fn string(&mut self) -> Result<String, String> {
+        let length = self.u16()? as usize;
+        String::from_utf8(self.take(length)?.to_vec())
+            .map_err(|_| "invalid text in pack".to_string())
+    }
+}
+
+fn write_string(bytes: &mut Vec<u8>, text: &str) {
+    bytes.extend((text.len() as u16).to_le_bytes());
+    bytes.extend(text.as_bytes());
+}
+
+fn write_color(bytes: &mut Vec<u8>, color: Color) {
+    match color {
+        Color::Rgb { r, g, b } => bytes.extend([1, r, g, b]),
+        Color::AnsiValue(value) => bytes.extend([2, value]),
+        named => match NAMED_16.iter().position(|candidate| *candidate == named) {
+            Some(index) => bytes.extend([3, index as u8]),
+            None => bytes.push(0),
+        },
+    }
+}
+
+fn read_color(reader: &mut Reader) -> Result<Color, String> {
+    match reader.u8()? {
+        0 => Ok(Color::Reset),
+        1 => {
+            let rgb = reader.take(3)?;
+            Ok(Color::Rgb {
+                r: rgb[0],
+                g: rgb[1],
+                b: rgb[2],
+            })
+        }
+        2 => Ok(Color::AnsiValue(reader.u8()?)),
+        3 => {
+            NAMED_16.get(reader.u8()? as usize).copied().ok_or("invalid color in pack".to_string())
+        }
+        _ => Err("invalid color in pack".to_string()),
+    }
+}
+
+///serializes named sprites into the compiled form stored in packs
+fn write_sprites(bytes: &mut Vec<u8>, sprites: &[(String, Sprite)]) {
+    bytes.extend((sprites.len() as u32).to_le_bytes());
+    for (name, sprite) in sprites {
+        write_string(bytes, name);
+        for value in [
+            sprite.width,
+            sprite.height,
+            sprite.center.0,
+            sprite.center.1,
+        ] {
+            bytes.extend(value.to_le_bytes());
+        }
+        write_string(bytes, sprite.tag.as_deref().unwrap_or_default());
+        bytes.extend((sprite.pixels.len() as u32).to_le_bytes());
+        for pixel in sprite.pixels.iter() {
+            bytes.extend(pixel.x.to_le_bytes());
+            bytes.extend(pixel.y.to_le_bytes());
+            bytes.extend([pixel.isrendered as u8, pixel.alpha]);
+            write_color(bytes, pixel.color);
+        }
+    }
+}
+
+///reads sprites written by `write_sprites`
+fn read_sprites(bytes: &[u8]) -> Result<Vec<(String, Sprite)>, String> {
+    let mut reader = Reader { bytes, offset: 0 };
+    let count = reader.u32()?;
+    let mut sprites: Vec<(String, Sprite)> = vec![];
+    for _ in 0..count {
+        let name = reader.string()?;
+        let (width, height) = (reader.u16()?, reader.u16()?);
+        let center = (reader.u16()?, reader.u16()?);
+        let tag = Some(reader.string()?).filter(|tag| !tag.is_empty());
+        let pixel_count = reader.u32()?;
+        let mut pixels: Vec<Pixel> = vec![];
+        for _ in 0..pixel_count {
+            let x = reader.u16()? as x_pos;
+            let y = reader.u16()? as y_pos;
+            let (isrendered, alpha) = (reader.u8()? != 0, reader.u8()?);
+            pixels.push(Pixel {
+                x,
+                y,
+                color: read_color(&mut reader)?,
+                isrendered,
+                alpha,
+            });
+        }
+        sprites.push((
+            name,
+            Sprite {
+                pixels,
+                height,
+                width,
+                center,
+                tag,
+            },
+        ));
+    }
+    Ok(sprites)
+}
+
+///returns every file below `directory` relative to `root`, sorted so packs are reproducible
+fn list_files(root: &Path, directory: &Path, files: &mut Vec<String>) -> Result<(), String> {
+    let entries = fs::read_dir(directory)
+        .map_err(|why| format!("could not read {} : {}", directory.display(), why))?;
+    let mut paths: Vec<_> =
+        entries.filter_map(|entry| entry.ok()).map(|entry| entry.path()).collect();
+    paths.sort();
+    for path in paths {
+        if path.is_dir() {
+            list_files(root, &path, files)?;
+        } else {
+            let relative = path.strip_prefix(root).unwrap_or(&path);
+            let parts: Vec<String> = relative
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy().to_string())
+                .collect();
+            files.push(parts.join("/"));
+        }
+    }
+    Ok(())
+}
+
+impl Pack {
+    ///returns an empty pack
+    pub fn new() -> Self {
+        Pack::default()
+    }
+    ///packs every file below `directory`, compiling sprite files and images
+    ///# Example
+    ///```
+    ///Pack::from_directory("assets")?.save("game.pack")?;
+    ///```
+    pub fn from_directory(directory: &str) -> Result<Self, String> {
+        println!("packing asset directory {}", directory);
+        let root = Path::new(directory);
+        let mut files: Vec<String> = vec![];
+        list_files(root, root, &mut files)?;
+        let mut pack = Pack::new();
+        for file in files {
+            let path = root.join(&file);
+            let path_text = path.to_string_lossy();
+            let extension = file.rsplit_once('.').map(|(_, extension)| extension.to_lowercase());
+            match extension.as_deref() {
+                Some("sprites") => {
+                    let sprites = sprite::load_sprite_file(&path_text)?;
+                    pack.add_sprites(&file, &sprites);
+                }
+                Some(extension) if IMAGE_EXTENSIONS.contains(&extension) => {
+                    let image = image::load_image(&path_text, None)?;
+                    pack.add_image(&file, &image);
+                }
+                _ => {
+                    let bytes = fs::read(&path)
+                        .map_err(|why| format!("could not read {} : {}", path_text, why))?;
+                    pack.add(&file, EntryKind::Raw, bytes);
+                }
+            }
+        }
+        Ok(pack)
+    }
+    ///adds an entry, replacing any entry called `name`
+    pub fn add(&mut self, name: &str, kind: EntryKind, bytes: Vec<u8>) {
+        if self.entry(name).is_some() {
+            self.entries.retain(|entry| entry.name != name);
+            //copy the remaining entries into fresh data, dropping the replaced bytes
+            let mut data: Vec<u8> = Vec::with_capacity(self.data.len());
+            for entry in self.entries.iter_mut() {
+                let start = data.len();
+                data.extend_from_slice(&self.data[entry.offset..entry.offset + entry.length]);
+                entry.offset = start;
+            }
+            self.data = data;
+        }
+        self.entries.push(PackEntry {
+            name: name.to_string(),
+            kind,
+            offset: self.data.len(),
+            length: bytes.len(),
+            checksum: crc32(&bytes),
+        });
+        self.data.extend(bytes);
+    }
+    ///adds the compiled sprites of a sprite file
+    pub fn add_sprites(&mut self, name: &str, sprites: &[(String, Sprite)]) {
+        let mut bytes: Vec<u8> = vec![];
+        write_sprites(&mut bytes, sprites);
+        self.add(name, EntryKind::Sprites, bytes);
+    }
+    ///adds a compiled image
+    pub fn add_image(&mut self, name: &str, image: &Sprite) {
+        let mut bytes: Vec<u8> = vec![];
+        write_sprites(&mut bytes, &[(String::new(), image.clone())]);
+        self.add(name, EntryKind::Image, bytes);
+    }
+    ///returns the index of the pack
+    pub fn entries(&self) -> &[PackEntry] {
+        &self.entries
+    }
+    ///returns the entry called `name`
+    pub fn entry(&self, name: &str) -> Option<&PackEntry> {
+        self.entries.iter().find(|entry| entry.name == name)
+    }
+    ///returns the data of the entry called `name` after verifying its checksum
+    pub fn bytes(&self, name: &str) -> Result<&[u8], String> {
+        let entry = self.entry(name).ok_or(format!("pack has no entry {}", name))?;
+        let bytes = &self.data[entry.offset..entry.offset + entry.length];
+        if crc32(bytes) != entry.checksum {
+            return Err(format!(
+                "pack entry {} is corrupt (checksum mismatch)",
+                name
+            ));
+        }
+        Ok(bytes)
+    }
+    ///returns the compiled sprites of a packed sprite file
+    pub fn sprites(&self, name: &str) -> Result<Vec<(String, Sprite)>, String> {
+        self.expect_kind(name, EntryKind::Sprites)?;
+        read_sprites(self.bytes(name)?)
+    }
+    ///returns a packed image
+    pub fn image(&self, name: &str) -> Result<Sprite, String> {
+        self.expect_kind(name, EntryKind::Image)?;
+        let mut sprites = read_sprites(self.bytes(name)?)?;
+        sprites.pop().map(|(_, image)| image).ok_or(format!("pack entry {} holds no image", name))
+    }
+    ///returns a packed text file
+    pub fn text(&self, name: &str) -> Result<String, String> {
+        self.expect_kind(name, EntryKind::Raw)?;
+        String::from_utf8(self.bytes(name)?.to_vec())
+            .map_err(|_| format!("pack entry {} is not text", name))
+    }
+    fn expect_kind(&self, name: &str, kind: EntryKind) -> Result<(), String> {
+        match self.entry(name) {
+            Some(entry) if entry.kind == kind => Ok(()),
+            Some(entry) => Err(format!(
+                "pack entry {} is {:?}, expected {:?}",
+                name, entry.kind, kind
+            )),
+            None => Err(format!("pack has no entry {}", name)),
+        }
+    }
+    ///serializes the pack, see the layout above
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let mut bytes: Vec<u8> = MAGIC.to_vec();
+        bytes.extend(VERSION.to_le_bytes());
+        bytes.extend((self.entries.len() as u32).to_le_bytes());
+        for entry in self.entries.iter() {
+            write_string(&mut bytes, &entry.name);
+            bytes.push(entry.kind as u8);
+            bytes.extend((entry.offset as u64).to_le_bytes());
+            bytes.extend((entry.length as u64).to_le_bytes());
+            bytes.extend(entry.checksum.to_le_bytes());
+        }
+        bytes.extend(self.data.iter());
+        bytes
+    }
+    ///parses a pack serialized with `to_bytes`
+    ///the index is validated here, entry checksums when an entry is read
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, String> {
+        let mut reader = Reader { bytes, offset: 0 };
+        if reader.take(4)? != MAGIC {
+            return Err("not an asset pack".to_string());
+        }
+        let version = reader.u16()?;
+        if version != VERSION {
+            return Err(format!("unsupported pack version {}", version));
+        }
+        let count = reader.u32()?;
+        let mut entries: Vec<PackEntry> = vec![];
+        for _ in 0..count {
+            let name = reader.string()?;
+            let kind = match reader.u8()? {
+                0 => EntryKind::Sprites,
+                1 => EntryKind::Image,
+                2 => EntryKind::Raw,
+                other => return Err(format!("pack entry {} has unknown kind {}", name, other)),
+            };
+            entries.push(PackEntry {
+                name,
+                kind,
+                offset: reader.u64()? as usize,
+                length: reader.u64()? as usize,
+                checksum: reader.u32()?,
+            });
+        }
+        let data = bytes[reader.offset..].to_vec();
+        for entry in entries.iter() {
+            if entry.offset.checked_add(entry.length).is_none_or(|end| end > data.len()) {
+                return Err(format!("pack entry {} lies outside the pack", entry.name));
+            }
+        }
+        Ok(Pack { entries, data })
+    }
+    ///saves the pack to a file
+    pub fn save(&self, path: &str) -> Result<(), String> {
+        println!("saving pack ({} entries) to {}", self.entries.len(), path);
+        fs::write(path, self.to_bytes())
+            .map_err(|why| format!("could not write {} : {}", path, why))
+    }
+    ///loads a pack file
+    pub fn load(path: &str) -> Result<Self, String> {
+        println!("loading pack {}", path);
+        let bytes = fs::read(path).map_err(|why| format!("could not read {} : {}", path, why))?;
+        Pack::from_bytes(&bytes).map_err(|why| format!("{} : {}", path, why))
+    }
+}
@@ -0,0 +1,268 @@
+//particle.rs
+use crate::color::{blend, BlendMode};
+use crate::sprite::{x_pos, y_pos, Pixel, Sprite};
+use crate::stack::{LayerID, Layerstack, SpriteVector};
+use crossterm::style::Color;
+use std::f32::consts::PI;
+use std::rc::Rc;
+use std::time::Duration;
+
+///what a particle looks like
+#[derive(Clone)]
+pub enum ParticleShape {
+    ///a single pixel
+    Pixel,
+    ///a small sprite centred on the particle, its rendered pixels take the gradient color
+    ///(the sprite's own colors are kept when the gradient is empty)
+    Sprite(Rc<Sprite>),
+}
+
+///settings of an Emitter, ranges are (min, max) and picked at random per particle
+#[derive(Clone)]
+pub struct EmitterConfig {
+    ///particles spawned per second while emitting
+    pub rate: f32,
+    ///seconds a particle lives
+    pub lifetime: (f32, f32),
+    ///pixels per second a particle starts with
+    pub speed: (f32, f32),
+    ///angle particles are emitted at in radians, 0.0 points right and -PI/2 up
+    pub direction: f32,
+    ///width of the cone around `direction` particles are emitted in, in radians
+    ///2*PI emits in every direction
+    pub spread: f32,
+    ///acceleration of every particle in pixels per second squared
+    pub gravity: (f32, f32),
+    ///(fraction of the lifetime, color) stops, particles blend between them in RGB as they age
+    pub gradient: Vec<(f32, Color)>,
+    ///particles turn transparent towards the end of their life
+    pub fade_out: bool,
+    pub shape: ParticleShape,
+    ///width and height of the rectangle around the emitter particles spawn in, (0,0) for a point
+    pub area: (u16, u16),
+    ///most particles alive at once, spawning stops while the emitter is full
+    pub max_particles: usize,
+}
+
+impl Default for EmitterConfig {
+    fn default() -> Self {
+        EmitterConfig {
+            rate: 20.0,
+            lifetime: (1.0, 1.0),
+            speed: (10.0, 20.0),
+            direction: -PI / 2.0,
+            spread: PI / 4.0,
+            gravity: (0.0, 0.0),
+            gradient: vec![(0.0, Color::White)],
+            fade_out: false,
+            shape: ParticleShape::Pixel,
+            area: (0, 0),
+            max_particles: 1000,
+        }
+    }
+}
+
+///a live particle, positions are in worldspace pixels
+#[derive(Clone, Debug, PartialEq)]
+pub struct Particle {
+    pub x: f32,
+    pub y: f32,
+    ///pixels per second
+    pub velocity: (f32, f32),
+    ///seconds since the particle spawned
+    pub age: f32,
+    ///seconds the particle lives
+    pub lifetime: f32,
+}
+
+///xorshift64* generator, particles only need cheap, repeatable randomness
+struct Random(u64);
+
+impl Random {
+    fn next_f32(&mut self) -> f32 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        let value = self.0.wrapping_mul(0x2545_F491_4F6C_DD1D);
+        (value >> 40) as f32 / (1u64 << 24) as f32
+    }
+    ///returns a value between `min` and `max`
+    fn range(&mut self, (min, max): (f32, f32)) -> f32 {
+        min + (max - min) * self.next_f32()
+    }
+}
+
+///spawns, moves and ages particles, drawn with `Layerstack::draw_particles`
+///the random numbers come from a seeded generator, so replaying the same updates (e.g. from an
+///input recording) produces the same particles
+///# Example
+///```
+///let mut sparks = Emitter::new(20.0,10.0,EmitterConfig{
+///    gravity:(0.0,40.0),
+///    gradient:vec![(0.0,Color::Yellow),(1.0,Color::DarkRed)],
+///    ..EmitterConfig::default()
+///});
+///loop {
+///    sparks.update(Duration::from_secs(1)/60);
+///    renderer.layerstack.draw_particles(&sparks,3);
+///    renderer.render_update();
+///}
+///```
+pub struct Emitter {
+    ///position particles spawn at, the top left corner of `EmitterConfig::area`
+    pub x: f32,
+    pub y: f32,
+    pub config: EmitterConfig,
+    ///spawns particles at `EmitterConfig::rate` while true
+    pub emitting: bool,
+    particles: Vec<Particle>,
+    ///fraction of a particle owed by earlier updates
+    owed: f32,
+    random: Random,
+}
+
+impl Emitter {
+    ///returns an emitting Emitter at (`x`,`y`)
+    pub fn new(x: f32, y: f32, config: EmitterConfig) -> Self {
+        println!("initializing particle Emitter");
+        Emitter {
+            x,
+            y,
+            config,
+            emitting: true,
+            particles: vec![],
+            owed: 0.0,
+            random: Random(0x9E37_79B9_7F4A_7C15),
+        }
+    }
+    ///reseeds the random generator, emitters with different seeds scatter differently
+    pub fn seed(&mut self, seed: u64) {
+        //xorshift never leaves zero
+        self.random = Random(seed.max(1));
+    }
+    ///returns the live particles, oldest first
+    pub fn particles(&self) -> &[Particle] {
+        &self.particles
+    }
+    ///returns the number of live particles
+    pub fn len(&self) -> usize {
+        self.particles.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.particles.is_empty()
+    }
+    ///returns true once the emitter stopped emitting and every particle died,
+    ///e.g. to remove the emitter of an explosion
+    pub fn is_finished(&self) -> bool {
+        !self.emitting && self.particles.is_empty()
+    }
+    ///spawns `count` particles at once, as far as `max_particles` allows
+    ///# Example
+    ///```
+    ///let mut explosion = Emitter::new(x,y,EmitterConfig{spread:2.0*PI,..EmitterConfig::default()});
+    ///explosion.emitting = false;
+    ///explosion.burst(40);
+    ///```
+    pub fn burst(&mut self, count: usize) {
+        let count = count.min(self.config.max_particles.saturating_sub(self.particles.len()));
+        for _ in 0..count {
+            let config = &self.config;
+            let angle = config.direction + (self.random.next_f32() - 0.5) * config.spread;
+            let speed = self.random.range(config.speed);
+            let x = self.x + self.random.next_f32() * config.area.0 as f32;
+            let y = self.y + self.random.next_f32() * config.area.1 as f32;
+            let lifetime = self.random.range(config.lifetime);
+            self.particles.push(Particle {
+                x,
+                y,
+                velocity: (angle.cos() * speed, angle.sin() * speed),
+                age: 0.0,
+                lifetime,
+            });
+        }
+    }
+    ///moves and ages every particle by `delta`, removes dead particles and spawns new ones
+    ///new particles start at the emitter, so they are drawn where they spawned first
+    pub fn update(&mut self, delta: Duration) {
+        let seconds = delta.as_secs_f32();
+        let gravity = self.config.gravity;
+        for particle in self.particles.iter_mut() {
+            particle.velocity.0 += gravity.0 * seconds;
+            particle.velocity.1 += gravity.1 * seconds;
+            particle.x += particle.velocity.0 * seconds;
+            particle.y += particle.velocity.1 * seconds;
+            particle.age += seconds;
+        }
+        self.particles.retain(|particle| particle.age < particle.lifetime);
+        if self.emitting {
+            self.owed += self.config.rate * seconds;
+            let count = self.owed.floor();
+            self.owed -= count;
+            self.burst(count as usize);
+        }
+    }
+    ///returns the color and alpha of a particle at its age
+    fn appearance(&self, particle: &Particle) -> (Option<Color>, u8) {
+        let t = (particle.age / particle.lifetime.max(f32::EPSILON)).clamp(0.0, 1.0);
+        let gradient = &self.config.gradient;
+        let color = match gradient.iter().position(|(stop, _)| *stop > t) {
+            None => gradient.last().map(|(_, color)| *color),
+            Some(0) => Some(gradient[0].1),
+            Some(next) => {
+                let ((start, from), (end, to)) = (gradient[next - 1], gradient[next]);
+                Some(blend(
+                    from,
+                    to,
+                    (t - start) / (end - start),
+                    BlendMode::Normal,
+                ))
+            }
+        };
+        let alpha = if self.config.fade_out {
+            ((1.0 - t) * 255.0).round() as u8
+        } else {
+            255
+        };
+        (color, alpha)
+    }
+}
+
+impl Layerstack {
+    ///writes every particle of an emitter to the target layer in a single SpriteVector
+    ///# Parameters
+    ///- `emitter` : emitter to draw
+    ///- `layer_id` : target layer
+    ///# Example
+    ///```
+    ///layer_draw_particles(&rain,4);
+    ///```
+    pub fn draw_particles(&mut self, emitter: &Emitter, layer_id: LayerID) {
+        let mut pixels: SpriteVector = Vec::with_capacity(emitter.len());
+        for particle in emitter.particles() {
+            let (color, alpha) = emitter.appearance(particle);
+            let (x, y) = (particle.x.round() as x_pos, particle.y.round() as y_pos);
+            match &emitter.config.shape {
+                ParticleShape::Pixel => pixels.push(Pixel {
+                    x,
+                    y,
+                    color: color.unwrap_or(Color::White),
+                    isrendered: true,
+                    alpha,
+                }),
+                ParticleShape::Sprite(sprite) => {
+                    let (center_x, center_y) = (sprite.center.0 as x_pos, sprite.center.1 as y_pos);
+                    pixels.extend(sprite.pixels.iter().filter(|pixel| pixel.isrendered).map(
+                        |pixel| Pixel {
+                            x: x + pixel.x - center_x,
+                            y: y + pixel.y - center_y,
+                            color: color.unwrap_or(pixel.color),
+                            isrendered: true,
+                            alpha: (pixel.alpha as u16 * alpha as u16 / 255) as u8,
+                        },
+                    ));
+                }
+            }
+        }
+        self.write_pixels(pixels, layer_id);
+    }
+}
@@ -0,0 +1,95 @@
+//record.rs
+use std::{fs, io, time};
+
+///recording of everything the renderer wrote to the terminal, frame by frame
+///timestamps add up the frame durations rather than reading the wall clock,
+///so recording the same session twice produces identical files
+pub struct Recording {
+    ///width of the recorded terminal in columns
+    width: u16,
+    ///height of the recorded terminal in rows
+    height: u16,
+    ///time the next recorded frame starts at
+    elapsed: time::Duration,
+    ///(start time, output) of each recorded frame in order
+    frames: Vec<(time::Duration, String)>,
+}
+
+impl Recording {
+    ///returns a new empty Recording
+    ///# Parameters
+    ///- `width` : width of the terminal in columns
+    ///- `height` : height of the terminal in rows
+    pub fn new(width: u16, height: u16) -> Self {
+        println!("initializing new Recording ({}x{})", width, height);
+        Recording {
+            width,
+            height,
+            elapsed: time::Duration::ZERO,
+            frames: vec![],
+        }
+    }
+    ///appends the terminal output of one frame
+    ///# Parameters
+    ///- `output` : bytes written to the terminal for the frame
+    ///- `frame_duration` : time the frame stays on screen, usually the renderer framerate
+    pub fn record_frame(&mut self, output: &[u8], frame_duration: time::Duration) {
+        self.frames.push((self.elapsed, String::from_utf8_lossy(output).to_string()));
+        self.elapsed += frame_duration;
+    }
+    ///returns the number of recorded frames
+    pub fn frame_count(&self) -> usize {
+        self.frames.len()
+    }
+    ///serializes the recording into an asciicast v2 file
+    ///# Example
+    ///```
+    ///let cast : String = recording.to_asciicast();
+    ///```
+    ///cast can be played back with `asciinema play`
+    pub fn to_asciicast(&self) -> String {
+        let mut cast = format!(
+            "{{\"version\": 2, \"width\": {}, \"height\": {}}}\n",
+            self.width, self.height
+        );
+        for (start, output) in self.frames.iter() {
+            cast += &format!(
+                "[{:.6}, \"o\", {}]\n",
+                start.as_secs_f64(),
+                json_string(output)
+            );
+        }
+        cast
+    }
+    ///saves the recording as an asciicast v2 file
+    ///# Parameters
+    ///- `path` : path of the `.cast` file to create
+    pub fn save(&self, path: &str) -> io::Result<()> {
+        println!(
+            "saving Recording ({} frames) to {}",
+            self.frame_count(),
+            path
+        );
+        fs::write(path, self.to_asciicast())
+    }
+}
+
+///returns `text` as a quoted JSON string
+fn json_string(text: &str) -> String {
+    let mut quoted = String::from("\"");
+    for character in text.chars() {
+        match character {
+            '"' => quoted += "\\\"",
+            '\\' => quoted += "\\\\",
+            '\n' => quoted += "\\n",
+            '\r' => quoted += "\\r",
+            '\t' => quoted += "\\t",
+            control if (control as u32) < 0x20 || control == '\u{7f}' => {
+                quoted += &format!("\\u{:04x}", control as u32)
+            }
+            _ => quoted.push(character),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
@@ -0,0 +1,135 @@
+//snapshot.rs
+use crate::stack::FrameBuffer;
+use crossterm::style::Color;
+use std::{env, fs, path};
+
+///glyphs assigned to colors in order of first appearance
+const GLYPHS: &str = ".#@%*+=-:oxOX0123456789abcdefghijklmnpqrstuvwyzABCDEFGHIJKLMNPQRSTUVWYZ";
+
+///environment variable that makes `assert_snapshot` rewrite snapshots instead of comparing them
+pub const UPDATE_VARIABLE: &str = "UPDATE_SNAPSHOTS";
+
+///serializes a frame into a text snapshot, a glyph grid followed by a color legend
+///every distinct color gets its own glyph, non rendered pixels are spaces
+///frames with text cells end with a `text` section, one `column,row` position per cell
+///followed by its character, color and background color if it has one
+///# Example
+///```
+///size 4x2
+///....
+///.##.
+///legend
+///. Black
+///# Red
+///text
+///0,0 'A' White
+///2,1 '!' Yellow on DarkBlue
+///```
+pub fn frame_to_snapshot(framebuffer: &FrameBuffer) -> String {
+    let mut legend: Vec<Color> = vec![];
+    let mut grid = String::new();
+    let glyphs: Vec<char> = GLYPHS.chars().collect();
+    for row in framebuffer.buffer.chunks(framebuffer.width().max(1) as usize) {
+        for pixel in row {
+            if !pixel.isrendered {
+                grid.push(' ');
+                continue;
+            }
+            let index = match legend.iter().position(|color| *color == pixel.color) {
+                Some(index) => index,
+                None => {
+                    legend.push(pixel.color);
+                    legend.len() - 1
+                }
+            };
+            grid.push(*glyphs.get(index).unwrap_or(&'?'));
+        }
+        grid.push('\n');
+    }
+    let mut snapshot = format!("size {}x{}\n", framebuffer.width(), framebuffer.height());
+    snapshot += &grid;
+    snapshot += "legend\n";
+    for (index, color) in legend.iter().enumerate() {
+        snapshot += &format!("{} {:?}\n", glyphs.get(index).unwrap_or(&'?'), color);
+    }
+    let text = framebuffer.text();
+    if !text.is_empty() {
+        snapshot += "text\n";
+    }
+    for cell in text {
+        snapshot += &format!(
+            "{},{} {:?} {:?}",
+            cell.column, cell.row, cell.character, cell.fg
+        );
+        if let Some(bg) = cell.bg {
+            snapshot += &format!(" on {:?}", bg);
+        }
+        snapshot.push('\n');
+    }
+    snapshot
+}
+
+///returns a readable line by line diff of two snapshots
+fn diff(expected: &str, actual: &str) -> String {
+    let expected_lines: Vec<&str> = expected.lines().collect();
+    let actual_lines: Vec<&str> = actual.lines().collect();
+    let mut text = String::new();
+    for line in 0..expected_lines.len().max(actual_lines.len()) {
+        match (expected_lines.get(line), actual_lines.get(line)) {
+            (Some(wanted), Some(got)) if wanted == got => {
+                text += &format!("  {:>3} {}\n", line + 1, got)
+            }
+            (wanted, got) => {
+                if let Some(wanted) = wanted {
+                    text += &format!("- {:>3} {}\n", line + 1, wanted);
+                }
+                if let Some(got) = got {
+                    text += &format!("+ {:>3} {}\n", line + 1, got);
+                }
+            }
+        }
+    }
+    text
+}
+
+///returns the path of the snapshot called `name`, snapshots live in `tests/snapshots`
+fn snapshot_path(name: &str) -> path::PathBuf {
+    let root = env::var("CARGO_MANIFEST_DIR").unwrap_or_else(|_| ".".to_string());
+    path::Path::new(&root).join("tests").join("snapshots").join(format!("{}.snap", name))
+}
+
+///compares a frame against the checked in snapshot `tests/snapshots/<name>.snap`
+///panics with a diff (`-` expected, `+` actual) when they differ or the snapshot is missing,
+///running the tests with `UPDATE_SNAPSHOTS=1` writes the current frame as the new snapshot
+///# Parameters
+///- `name` : name of the snapshot file
+///- `framebuffer` : frame to compare, usually `Renderer::last_frame()` of a headless Renderer
+///# Example
+///```
+///renderer.render_update();
+///assert_snapshot("title_screen",renderer.last_frame());
+///```
+pub fn assert_snapshot(name: &str, framebuffer: &FrameBuffer) {
+    let actual = frame_to_snapshot(framebuffer);
+    let path = snapshot_path(name);
+    if env::var_os(UPDATE_VARIABLE).is_some() {
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(&path, &actual).unwrap();
+        return;
+    }
+    match fs::read_to_string(&path) {
+        Ok(expected) if expected == actual => {}
+        Ok(expected) => panic!(
+            "frame does not match snapshot {}\n{}\nrerun with {}=1 to accept the new frame",
+            path.display(),
+            diff(&expected, &actual),
+            UPDATE_VARIABLE
+        ),
+        Err(_) => panic!(
+            "snapshot {} does not exist\n{}\nrerun with {}=1 to create it",
+            path.display(),
+            actual,
+            UPDATE_VARIABLE
+        ),
+    }
+}
@@ -0,0 +1,235 @@
+//spatial.rs
+use crate::collision::Rect;
+use crate::sprite::{x_pos, y_pos, Sprite};
+use std::collections::{HashMap, HashSet};
+use std::hash::Hash;
+
+///uniform grid spatial hash for broad phase collision and picking
+///objects are registered under a key of the game's choice (an entity id, an index...) with
+///their worldspace bounding box, queries only look at the grid cells a box covers
+///results are returned in insertion order so games behave the same on every run
+pub struct SpatialHash<K> {
+    cell_size: u16,
+    ///keys of the objects overlapping each cell
+    cells: HashMap<(i32, i32), Vec<K>>,
+    ///(bounding box, insertion order) of every object
+    objects: HashMap<K, (Rect, u64)>,
+    next_order: u64,
+}
+
+///returns the squared distance from a point to the closest point of a rectangle
+fn distance_squared(rect: &Rect, x: i32, y: i32) -> i64 {
+    let dx = (rect.x as i32 - x).max(x - (rect.right() - 1)).max(0) as i64;
+    let dy = (rect.y as i32 - y).max(y - (rect.bottom() - 1)).max(0) as i64;
+    dx * dx + dy * dy
+}
+
+impl<K: Copy + Eq + Hash> SpatialHash<K> {
+    ///returns an empty spatial hash with square cells of `cell_size` pixels
+    ///cells about the size of a typical sprite work best
+    ///# Example
+    ///```
+    ///let mut grid : SpatialHash<u32> = SpatialHash::new(8);
+    ///grid.insert_sprite(player_id,x,y,&player);
+    ///```
+    pub fn new(cell_size: u16) -> Self {
+        println!("initializing SpatialHash with {}px cells", cell_size);
+        SpatialHash {
+            cell_size: cell_size.max(1),
+            cells: HashMap::new(),
+            objects: HashMap::new(),
+            next_order: 0,
+        }
+    }
+    ///returns the cell holding a worldspace point
+    fn cell(&self, x: i32, y: i32) -> (i32, i32) {
+        let size = self.cell_size as i32;
+        (x.div_euclid(size), y.div_euclid(size))
+    }
+    ///returns the range of cells (left, top, right, bottom) a rectangle covers,
+    ///an empty rectangle covers the cell of its corner
+    fn cell_range(&self, rect: &Rect) -> (i32, i32, i32, i32) {
+        let (left, top) = self.cell(rect.x as i32, rect.y as i32);
+        let (right, bottom) = self.cell(
+            (rect.right() - 1).max(rect.x as i32),
+            (rect.bottom() - 1).max(rect.y as i32),
+        );
+        (left, top, right, bottom)
+    }
+    ///registers an object, replacing the bounding box of a key that is already registered
+    ///a moved object keeps its place in the result order
+    pub fn insert(&mut self, key: K, bounds: Rect) {
+        let order = match self.remove_cells(&key) {
+            Some((_, order)) => order,
+            None => {
+                self.next_order += 1;
+                self.next_order
+            }
+        };
+        let (left, top, right, bottom) = self.cell_range(&bounds);
+        for cell_y in top..=bottom {
+            for cell_x in left..=right {
+                self.cells.entry((cell_x, cell_y)).or_default().push(key);
+            }
+        }
+        self.objects.insert(key, (bounds, order));
+    }
+    ///registers a sprite placed at (`x`,`y`) by its bounding box, see `insert`
+    pub fn insert_sprite(&mut self, key: K, x: x_pos, y: y_pos, sprite: &Sprite) {
+        self.insert(key, Rect::of_sprite(x, y, sprite));
+    }
+    ///removes a key from the cells it covers and returns its entry
+    fn remove_cells(&mut self, key: &K) -> Option<(Rect, u64)> {
+        let (bounds, order) = self.objects.remove(key)?;
+        let (left, top, right, bottom) = self.cell_range(&bounds);
+        for cell_y in top..=bottom {
+            for cell_x in left..=right {
+                if let Some(keys) = self.cells.get_mut(&(cell_x, cell_y)) {
+                    keys.retain(|existing| existing != key);
+                    if keys.is_empty() {
+                        self.cells.remove(&(cell_x, cell_y));
+                    }
+                }
+            }
+        }
+        Some((bounds, order))
+    }
+    ///unregisters an object and returns its bounding box
+    pub fn remove(&mut self, key: &K) -> Option<Rect> {
+        self.remove_cells(key).map(|(bounds, _)| bounds)
+    }
+    ///returns the bounding box an object was registered with
+    pub fn bounds(&self, key: &K) -> Option<Rect> {
+        self.objects.get(key).map(|(bounds, _)| *bounds)
+    }
+    ///returns the number of registered objects
+    pub fn len(&self) -> usize {
+        self.objects.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+    ///unregisters every object
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.objects.clear();
+    }
+    ///sorts keys by insertion order
+    fn sorted(&self, mut keys: Vec<K>) -> Vec<K> {
+        keys.sort_by_key(|key| self.objects[key].1);
+        keys
+    }
+    ///returns every distinct key registered in the cells a rectangle covers
+    fn candidates(&self, area: &Rect) -> Vec<K> {
+        let (left, top, right, bottom) = self.cell_range(area);
+        let mut seen: HashSet<K> = HashSet::new();
+        let mut keys: Vec<K> = vec![];
+        for cell_y in top..=bottom {
+            for cell_x in left..=right {
+                for key in self.cells.get(&(cell_x, cell_y)).into_iter().flatten() {
+                    if seen.insert(*key) {
+                        keys.push(*key);
+                    }
+                }
+            }
+        }
+        keys
+    }
+    ///returns every object whose bounding box overlaps `area`
+    ///# Example
+    ///```
+    ///for id in grid.query_rect(&Rect::of_sprite(x,y,&explosion)) { damage(id); }
+    ///```
+    pub fn query_rect(&self, area: &Rect) -> Vec<K> {
+        let keys = self
+            .candidates(area)
+            .into_iter()
+            .filter(|key| self.objects[key].0.intersection(area).is_some())
+            .collect();
+        self.sorted(keys)
+    }
+    ///returns every object whose bounding box holds the point, for mouse picking
+    ///# Example
+    ///```
+    ///let clicked = grid.query_point(mouse_x,mouse_y).last();
+    ///```
+    pub fn query_point(&self, x: x_pos, y: y_pos) -> Vec<K> {
+        let (cell_x, cell_y) = self.cell(x as i32, y as i32);
+        let keys = self
+            .cells
+            .get(&(cell_x, cell_y))
+            .into_iter()
+            .flatten()
+            .filter(|key| self.objects[key].0.contains(x, y))
+            .copied()
+            .collect();
+        self.sorted(keys)
+    }
+    ///returns the object whose bounding box is closest to the point, objects holding the
+    ///point are at distance 0, ties go to the object inserted first
+    ///searches rings of cells around the point until no closer object can exist
+    pub fn nearest(&self, x: x_pos, y: y_pos) -> Option<K> {
+        if self.objects.is_empty() {
+            return None;
+        }
+        let (x, y) = (x as i32, y as i32);
+        let (center_x, center_y) = self.cell(x, y);
+        //every object lies within this many rings of the starting cell
+        let max_ring = self
+            .cells
+            .keys()
+            .map(|(cell_x, cell_y)| (cell_x - center_x).abs().max((cell_y - center_y).abs()))
+            .max()
+            .unwrap_or(0);
+        let size = self.cell_size as i64;
+        let mut best: Option<(i64, u64, K)> = None;
+        for ring in 0..=max_ring {
+            //objects first found in this ring are at least this far away
+            if let Some((distance, _, _)) = best {
+                let reach = (ring as i64 - 1) * size;
+                if reach * reach > distance {
+                    break;
+                }
+            }
+            for cell_y in center_y - ring..=center_y + ring {
+                for cell_x in center_x - ring..=center_x + ring {
+                    if (cell_x - center_x).abs() != ring && (cell_y - center_y).abs() != ring {
+                        continue;
+                    }
+                    for key in self.cells.get(&(cell_x, cell_y)).into_iter().flatten() {
+                        let (bounds, order) = self.objects[key];
+                        let distance = distance_squared(&bounds, x, y);
+                        if best.is_none_or(|(best_distance, best_order, _)| {
+                            (distance, order) < (best_distance, best_order)
+                        }) {
+                            best = Some((distance, order, *key));
+                        }
+                    }
+                }
+            }
+        }
+        best.map(|(_, _, key)| key)
+    }
+    ///returns every pair of objects whose bounding boxes overlap, each pair once,
+    ///for broad phase collision before pixel perfect checks
+    ///# Example
+    ///```
+    ///for (a,b) in grid.pairs() {
+    ///    if pixels_collide(placed(a),placed(b)) { hit(a,b); }
+    ///}
+    ///```
+    pub fn pairs(&self) -> Vec<(K, K)> {
+        let mut keys: Vec<K> = self.objects.keys().copied().collect();
+        keys = self.sorted(keys);
+        let mut pairs: Vec<(K, K)> = vec![];
+        for key in keys.iter() {
+            let (bounds, order) = self.objects[key];
+            for other in self.query_rect(&bounds) {
+                if self.objects[&other].1 > order {
+                    pairs.push((*key, other));
+                }
+            }
+        }
+        pairs
+    }
+}
@@ -1,9 +1,13 @@
 //sprite.rs
+use crate::color;
 use crossterm::style::Color;
 use regex::Regex;
 use std::collections::HashMap;
+use std::fs;
 
+#[allow(non_camel_case_types)]
 pub type x_pos = i16;
+#[allow(non_camel_case_types)]
 pub type y_pos = i16;
 
 ///struct to represent one pixel
@@ -19,6 +23,8 @@ pub struct Pixel {
     pub color: Color,
     ///flag stating wether to render the pixel
     pub isrendered: bool,
+    ///opacity of pixel, 0 is fully transparent and 255 is opaque
+    pub alpha: u8,
 }
 ///A human readable string represenation of a sprite
 pub type SpriteSource = &'static str;
@@ -27,6 +33,7 @@ pub type SpriteSource = &'static str;
 //▒
 //▓
 ///defines a compiled sprite
+#[derive(Clone)]
 pub struct Sprite {
     /// Vector of all pixels held within the sprite in local spritespace
     pub pixels: Vec<Pixel>,
@@ -40,6 +47,7 @@ pub struct Sprite {
     pub tag: Option<String>,
 }
 /// metadata used in tandem with a SpriteSource to properly compile into a Sprite
+#[derive(Clone)]
 pub struct Metadata {
     ///mapping of characters in sprite_source into associated colors
     pub color_map: HashMap<char, Color>,
@@ -61,11 +69,14 @@ pub struct Metadata {
 /// let compiled_sprite : Sprite = compile_sprite(source,metadata);
 /// ```
 /// a new Sprite instance is created from source and metadata
-pub fn compile_sprite(sprite_source: SpriteSource, metadata: Metadata) -> Result<Sprite, String> {
+pub fn compile_sprite(sprite_source: &str, metadata: Metadata) -> Result<Sprite, String> {
     //sanitizes char array
     let mut valid_characters: String = metadata.color_map.keys().collect();
     valid_characters.push(metadata.transparent);
-    let pattern = Regex::new(&format!("[^{}]", valid_characters)).unwrap();
+    //escaped so characters like `-` or `]` are not read as part of the character class
+    let escaped: String =
+        valid_characters.chars().map(|character| regex::escape(&character.to_string())).collect();
+    let pattern = Regex::new(&format!("[^{}]", escaped)).unwrap();
     let sprite_array = pattern.replace_all(sprite_source, "").to_string();
 
     //verifies aspect ratio is valid
@@ -86,12 +97,11 @@ pub fn compile_sprite(sprite_source: SpriteSource, metadata: Metadata) -> Result
         height: metadata.height,
         width: metadata.width,
         center: (metadata.width / 2, metadata.height / 2),
-        tag: None,
+        tag: metadata.tag.clone(),
     };
     let mut y = 0;
     let mut x = 0;
     for pixel in sprite_array.chars() {
-        x += 1;
         print!("{}", pixel);
         let isrendered_bool;
         let pixel_color;
@@ -110,10 +120,12 @@ pub fn compile_sprite(sprite_source: SpriteSource, metadata: Metadata) -> Result
             //layer: 1,
             color: pixel_color,
             isrendered: isrendered_bool,
+            alpha: 255,
         };
 
         compiled_sprite.pixels.push(generated_pixel);
 
+        x += 1;
         if x == metadata.width {
             //at end of width
             y += 1;
@@ -122,3 +134,239 @@ pub fn compile_sprite(sprite_source: SpriteSource, metadata: Metadata) -> Result
     }
     Ok(compiled_sprite)
 }
+
+///keywords of the sprite file format
+const SPRITE_FILE_KEYWORDS: [&str; 6] =
+    ["sprite", "width", "height", "transparent", "color", "tag"];
+
+///sprite entry of a sprite file being parsed
+struct SpriteEntry {
+    name: String,
+    metadata: Metadata,
+    width: Option<u16>,
+    height: Option<u16>,
+    rows: Vec<String>,
+}
+
+impl SpriteEntry {
+    ///compiles the entry, inferring width and height from the rows when they were not given
+    fn compile(self) -> Result<(String, Sprite), String> {
+        let mut metadata = self.metadata;
+        metadata.height = self.height.unwrap_or(self.rows.len() as u16);
+        metadata.width = self
+            .width
+            .unwrap_or(self.rows.iter().map(|row| row.chars().count()).max().unwrap_or(0) as u16);
+        let sprite = compile_sprite(&self.rows.join("\n"), metadata)
+            .map_err(|why| format!("sprite {} : {}", self.name, why))?;
+        Ok((self.name, sprite))
+    }
+}
+
+///parses a sprite file, a text file holding any number of named sprites
+///keys given before the first `sprite` line are defaults for every sprite in the file,
+///`width` and `height` are inferred from the pixel rows when left out,
+///`transparent` defaults to `.` and lines starting with `//` are comments
+///# Example
+///```
+///transparent .
+///color # White
+///
+///sprite arrow
+///color @ #ff0000
+///.#.
+///#@#
+///.#.
+///
+///sprite dot
+///width 1
+///height 1
+///#
+///```
+///returns the sprites `arrow` (3x3) and `dot` (1x1), in file order
+pub fn parse_sprite_file(text: &str) -> Result<Vec<(String, Sprite)>, String> {
+    let mut defaults = Metadata {
+        color_map: HashMap::new(),
+        transparent: '.',
+        height: 0,
+        width: 0,
+        tag: None,
+    };
+    let (mut default_width, mut default_height): (Option<u16>, Option<u16>) = (None, None);
+    let mut sprites: Vec<(String, Sprite)> = vec![];
+    let mut entry: Option<SpriteEntry> = None;
+
+    for (number, line) in text.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with("//") {
+            continue;
+        }
+        let in_rows = entry.as_ref().is_some_and(|entry| !entry.rows.is_empty());
+        let (keyword, value) = line.split_once(' ').unwrap_or((line, ""));
+        let value = value.trim();
+        let is_key = SPRITE_FILE_KEYWORDS.contains(&keyword) && !value.is_empty();
+        if !is_key || (in_rows && keyword != "sprite") {
+            match entry.as_mut() {
+                Some(entry) => entry.rows.push(line.to_string()),
+                None => {
+                    return Err(format!(
+                        "line {} : pixel row outside of a sprite",
+                        number + 1
+                    ))
+                }
+            }
+            continue;
+        }
+
+        let invalid = |what: &str| format!("line {} : invalid {} : {}", number + 1, what, value);
+        if keyword == "sprite" {
+            if let Some(finished) = entry.take() {
+                sprites.push(finished.compile()?);
+            }
+            entry = Some(SpriteEntry {
+                name: value.to_string(),
+                metadata: defaults.clone(),
+                width: default_width,
+                height: default_height,
+                rows: vec![],
+            });
+            continue;
+        }
+        //keys before the first sprite set the defaults
+        let (metadata, width, height) = match entry.as_mut() {
+            Some(entry) => (&mut entry.metadata, &mut entry.width, &mut entry.height),
+            None => (&mut defaults, &mut default_width, &mut default_height),
+        };
+        match keyword {
+            "width" => *width = Some(value.parse::<u16>().map_err(|_| invalid("width"))?),
+            "height" => *height = Some(value.parse::<u16>().map_err(|_| invalid("height"))?),
+            "transparent" => {
+                metadata.transparent = value.chars().next().ok_or(invalid("transparent"))?
+            }
+            "tag" => metadata.tag = Some(value.to_string()),
+            _ => {
+                let (character, color_text) = value.split_once(' ').ok_or(invalid("color"))?;
+                let character = character.chars().next().ok_or(invalid("color"))?;
+                let parsed = color::parse_color(color_text.trim())
+                    .map_err(|why| format!("line {} : {}", number + 1, why))?;
+                metadata.color_map.insert(character, parsed);
+            }
+        }
+    }
+    if let Some(finished) = entry.take() {
+        sprites.push(finished.compile()?);
+    }
+    Ok(sprites)
+}
+
+///loads every sprite of a sprite file, see `parse_sprite_file`
+///# Parameters
+///- `path` : path of the sprite file
+///# Example
+///```
+///let sprites = load_sprite_file("assets/player.sprites")?;
+///```
+pub fn load_sprite_file(path: &str) -> Result<Vec<(String, Sprite)>, String> {
+    println!("loading sprite file {}", path);
+    let text =
+        fs::read_to_string(path).map_err(|why| format!("could not read {} : {}", path, why))?;
+    parse_sprite_file(&text).map_err(|why| format!("{} : {}", path, why))
+}
+
+///ticks each frame of an animation is shown for unless set otherwise
+pub const DEFAULT_FRAME_DURATION: u64 = 8;
+
+///sequence of Sprites shown one after another
+#[derive(Clone)]
+pub struct Animation {
+    pub frames: Vec<Sprite>,
+    ///ticks each frame is shown for
+    pub frame_duration: u64,
+    ///restarts after the last frame when true, stays on the last frame otherwise
+    pub looping: bool,
+}
+
+impl Animation {
+    ///returns a looping Animation of `frames` in order
+    pub fn new(frames: Vec<Sprite>, frame_duration: u64) -> Self {
+        Animation {
+            frames,
+            frame_duration,
+            looping: true,
+        }
+    }
+    ///builds a looping Animation from the sprites of a sprite file, frames are in file order
+    ///# Example
+    ///```
+    ///let walk = Animation::from_sprites(load_sprite_file("assets/walk.sprites")?,DEFAULT_FRAME_DURATION)?;
+    ///```
+    pub fn from_sprites(
+        sprites: Vec<(String, Sprite)>,
+        frame_duration: u64,
+    ) -> Result<Self, String> {
+        if sprites.is_empty() {
+            return Err("animation has no frames".to_string());
+        }
+        Ok(Animation::new(
+            sprites.into_iter().map(|(_, sprite)| sprite).collect(),
+            frame_duration,
+        ))
+    }
+    ///returns the number of ticks one pass through the animation takes
+    pub fn length(&self) -> u64 {
+        self.frames.len() as u64 * self.frame_duration.max(1)
+    }
+    ///returns the frame to draw `tick` ticks after the animation started
+    ///# Example
+    ///```
+    ///layer_write_sprite(x,y,walk.frame(input.frame()),1);
+    ///```
+    pub fn frame(&self, tick: u64) -> &Sprite {
+        let mut index = (tick / self.frame_duration.max(1)) as usize;
+        if self.looping {
+            index %= self.frames.len();
+        }
+        &self.frames[index.min(self.frames.len() - 1)]
+    }
+    ///returns true once a non looping animation has shown its last frame for its full duration
+    pub fn finished(&self, tick: u64) -> bool {
+        !self.looping && tick >= self.length()
+    }
+}
+
+///returns the rectangle of a Sprite starting at (`x`,`y`) as a new Sprite, used to cut up sprite sheets
+///parts of the rectangle outside the Sprite are left transparent
+///# Example
+///```
+///let second_frame : Sprite = crop_sprite(&sheet,16,0,16,16);
+///```
+pub fn crop_sprite(sprite: &Sprite, x: u16, y: u16, width: u16, height: u16) -> Sprite {
+    let mut cropped = Sprite {
+        pixels: vec![],
+        height,
+        width,
+        center: (width / 2, height / 2),
+        tag: sprite.tag.clone(),
+    };
+    for row in 0..height as y_pos {
+        for column in 0..width as x_pos {
+            cropped.pixels.push(Pixel {
+                x: column,
+                y: row,
+                color: Color::Reset,
+                isrendered: false,
+                alpha: 0,
+            });
+        }
+    }
+    for pixel in sprite.pixels.iter() {
+        let (column, row) = (pixel.x - x as x_pos, pixel.y - y as y_pos);
+        if (0..width as x_pos).contains(&column) && (0..height as y_pos).contains(&row) {
+            cropped.pixels[row as usize * width as usize + column as usize] = Pixel {
+                x: column,
+                y: row,
+                ..pixel.clone()
+            };
+        }
+    }
+    cropped
+}
@@ -1,17 +1,20 @@
 //stack.rs
+use crate::color::{self, BlendMode, ColorDepth, Dither};
+use crate::export::{self, ScreenshotFormat};
+use crate::record::Recording;
 use crate::sprite::{x_pos, y_pos, Pixel, Sprite};
 use crossterm::{
-    cursor, execute, queue,
+    cursor, queue,
     style::{self, Color, Stylize},
     terminal,
 };
-use std::{collections::HashMap, io};
+use std::io::Write;
+use std::{collections::HashMap, fs, io};
 use std::{thread, time};
 //██
 //▆
-static DEBUG: bool = false;
 
-///string to represent each pixel by.
+//string to represent each pixel by.
 
 ///FrameBuffer type holds worldspace before commit
 pub type SpriteVector = Vec<Pixel>;
@@ -25,6 +28,30 @@ pub struct Layer {
     pub buffer: Vec<SpriteVector>,
     stack_pos: u16,
     is_rendered: bool,
+    ///how pixels of this layer are combined with the layers beneath it
+    blend_mode: BlendMode,
+    ///opacity of the whole layer from 0.0 (invisible) to 1.0 (opaque)
+    opacity: f32,
+    ///color every pixel of the layer is pulled towards, with strength from 0.0 to 1.0
+    tint: Option<(Color, f32)>,
+    ///dithering applied to this layer alone during rasterization, `None` leaves it to the presenter
+    dither: Option<Dither>,
+    ///terminal characters of a text layer, `None` for pixel layers
+    text: Option<Vec<TextCell>>,
+}
+
+///one terminal character of a text layer, positioned in terminal cells rather than pixels
+#[derive(Clone, Debug, PartialEq)]
+pub struct TextCell {
+    ///terminal column, a pixel is `pixel_element` columns wide
+    pub column: u16,
+    ///terminal row, one row per pixel
+    pub row: u16,
+    pub character: char,
+    ///color of the character
+    pub fg: Color,
+    ///color behind the character, `None` shows the color of the pixel beneath
+    pub bg: Option<Color>,
 }
 
 ///RustTermRenderer Rendering Engine API
@@ -35,6 +62,13 @@ pub struct FrameBuffer {
     color: Color,
     height: u16,
     width: u16,
+    ///flags pixels already quantized by a per layer dither so the presenter leaves them alone
+    dithered: Vec<bool>,
+    ///stack position of the highest layer that wrote each pixel opaquely, used to hide covered text
+    top_layer: Vec<Option<u16>>,
+    ///text cells of visible text layers with the stack position of their layer,
+    ///in a presented frame only the cells that were drawn
+    text: Vec<(u16, TextCell)>,
 }
 /// collection of sprites to draw at a depth
 pub struct Layerstack {
@@ -49,12 +83,30 @@ pub struct Renderer {
     pub layerstack: Layerstack,
     ///string to render each pixel as
     pub pixel_element: String,
-    ///stdout of the Renderer
-    stdout: std::io::Stdout,
+    ///where presented frames are written
+    output: Output,
     ///framerate of Renderer, default value is 25fps (40ms)
     pub framerate: time::Duration,
     ///debug flag
     pub debug: bool,
+    ///color capability of the terminal, framebuffer colors are quantized to it when presented
+    pub color_depth: ColorDepth,
+    ///dithering used when quantizing the framebuffer for presentation
+    pub dither: Dither,
+    ///copy of the last presented frame, with colors as they were sent to the terminal
+    last_frame: FrameBuffer,
+    ///active session recording, `None` when not recording
+    recording: Option<Recording>,
+    ///(key, message) pairs of the debug overlay, drawn over every layer in the top rows
+    overlay: Vec<(String, String)>,
+}
+
+///destination of the presenter's output
+enum Output {
+    ///stdout of the Renderer
+    Terminal(std::io::Stdout),
+    ///frames are only kept in memory (`last_frame` and recordings), nothing is drawn
+    Headless,
 }
 
 //TODO might move to render_api.rs
@@ -64,9 +116,12 @@ impl FrameBuffer {
         println!("initializing FrameBuffer instance");
         let mut framebuffer: FrameBuffer = FrameBuffer {
             buffer: vec![],
-            color: color,
-            height: x,
-            width: y,
+            color,
+            height: y,
+            width: x,
+            dithered: vec![false; x as usize * y as usize],
+            top_layer: vec![None; x as usize * y as usize],
+            text: vec![],
         };
         for y_framebuffer in 0..y {
             for x_framebuffer in 0..x {
@@ -74,8 +129,9 @@ impl FrameBuffer {
                     x: x_framebuffer as x_pos,
                     y: y_framebuffer as y_pos,
                     //layer: 0,
-                    color: color,
+                    color,
                     isrendered: true,
+                    alpha: 255,
                 };
                 framebuffer.buffer.push(working_pixel);
             }
@@ -84,22 +140,97 @@ impl FrameBuffer {
     }
     ///write to framebuffer
     ///this function should only be called by the layerstack rasterizer during rasterization
-    fn write(&mut self, layer: &Layer) {
+    ///pixels are blended onto the framebuffer using the layer's blend mode,
+    ///weighted by the pixel alpha and the layer opacity
+    ///if the layer has its own dithering the written pixels are quantized to `depth` straight away
+    fn write(&mut self, layer: &Layer, depth: ColorDepth) {
         println!("writing Layer to FrameBuffer");
+        let mut written: Vec<bool> = vec![false; self.buffer.len()];
         for sprite_vector in layer.buffer.iter() {
             for sprite_pixel in sprite_vector.iter() {
-                if sprite_pixel.x >= 0 && sprite_pixel.y >= 0 && sprite_pixel.isrendered {
+                let in_bounds = sprite_pixel.x >= 0
+                    && sprite_pixel.y >= 0
+                    && sprite_pixel.x < self.width as x_pos
+                    && sprite_pixel.y < self.height as y_pos;
+                if in_bounds && sprite_pixel.isrendered {
                     //converts to u16
                     let raw_index: usize =
                         self.get_raw_index(sprite_pixel.x as x_pos, sprite_pixel.y as y_pos);
                     if let Some(buffer_pixel) = self.buffer.get_mut(raw_index) {
-                        buffer_pixel.color = sprite_pixel.color;
+                        let alpha = sprite_pixel.alpha as f32 / 255.0 * layer.opacity;
+                        let pixel_color = match layer.tint {
+                            Some((tint, strength)) => {
+                                color::blend(sprite_pixel.color, tint, strength, BlendMode::Normal)
+                            }
+                            None => sprite_pixel.color,
+                        };
+                        buffer_pixel.color =
+                            color::blend(buffer_pixel.color, pixel_color, alpha, layer.blend_mode);
+                        written[raw_index] = true;
+                        self.dithered[raw_index] = layer.dither.is_some();
+                        //text stays visible through translucent pixels, e.g. during a fade
+                        if alpha >= 1.0 {
+                            self.top_layer[raw_index] = Some(layer.stack_pos);
+                        }
                     } else {
                         println!("FrameBuffer does not contain referenced pixel");
                     }
                 }
             }
         }
+        if let Some(dither) = layer.dither {
+            let mut colors: Vec<Color> = self.buffer.iter().map(|pixel| pixel.color).collect();
+            color::dither(&mut colors, self.width as usize, depth, dither, &written);
+            for (pixel, dithered_color) in self.buffer.iter_mut().zip(colors) {
+                pixel.color = dithered_color;
+            }
+        }
+    }
+    ///returns the width (x aspect) of the framebuffer
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+    ///returns the height (y aspect) of the framebuffer
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+    ///returns the pixel at (`x`,`y`), `None` if the position is outside the framebuffer
+    pub fn get(&self, x: u16, y: u16) -> Option<&Pixel> {
+        if x >= self.width || y >= self.height {
+            return None;
+        }
+        self.buffer.get(self.get_raw_index(x as x_pos, y as y_pos))
+    }
+    ///returns the text cells of the frame ordered by row and column
+    ///for a presented frame (`Renderer::last_frame`) these are exactly the characters drawn,
+    ///including the debug overlay, with covered cells left out
+    pub fn text(&self) -> Vec<&TextCell> {
+        self.text.iter().map(|(_, cell)| cell).collect()
+    }
+    ///resolves the text cells that are not covered by opaque pixels of a higher layer
+    ///cells written later or by higher text layers replace cells at the same position,
+    ///returns the remaining cells ordered by row and column
+    ///# Parameters
+    ///- `element_width` : number of terminal columns a pixel takes
+    fn visible_text(&self, element_width: u16) -> Vec<(u16, TextCell)> {
+        let mut text = self.text.clone();
+        //stable sort keeps the write order of cells within a layer
+        text.sort_by_key(|(stack_pos, _)| *stack_pos);
+        let mut cells: HashMap<(u16, u16), (u16, TextCell)> = HashMap::new();
+        for (stack_pos, cell) in text {
+            let x = cell.column / element_width.max(1);
+            if x >= self.width || cell.row >= self.height {
+                continue;
+            }
+            let index = cell.row as usize * self.width as usize + x as usize;
+            if self.top_layer[index].is_some_and(|top| top > stack_pos) {
+                continue;
+            }
+            cells.insert((cell.column, cell.row), (stack_pos, cell));
+        }
+        let mut visible: Vec<(u16, TextCell)> = cells.into_values().collect();
+        visible.sort_by_key(|(_, cell)| (cell.row, cell.column));
+        visible
     }
     ///returns a transformed SpriteVector of a Sprite in a worldspace position
     ///# Example
@@ -115,8 +246,8 @@ impl FrameBuffer {
             let x_pixel = pixel.x + x_world;
             let y_pixel = pixel.y + y_world;
             //if these are higher than the x y aspect of framebuffer then skip creation
-            if !(x_pixel < self.width as i16) && (y_pixel < self.height as i16) {
-                break;
+            if x_pixel >= self.width as i16 || y_pixel >= self.height as i16 {
+                continue;
             }
             let working_pixel: Pixel = Pixel {
                 x: pixel.x + x_world,
@@ -124,6 +255,7 @@ impl FrameBuffer {
                 //layer: layer_world,
                 color: pixel.color,
                 isrendered: pixel.isrendered,
+                alpha: pixel.alpha,
             };
             pixels.push(working_pixel);
         }
@@ -148,7 +280,6 @@ impl FrameBuffer {
 }
 impl Layerstack {
     ///returns initialized layerstack
-
     fn new(width: u16, height: u16, bg_color: Color) -> Self {
         println!("initializing Layerstack instance");
         Layerstack {
@@ -166,8 +297,7 @@ impl Layerstack {
     ///layerstack_rasterize()
     ///```
     ///adds layer data to framebuffer
-
-    fn rasterize(&mut self) {
+    fn rasterize(&mut self, depth: ColorDepth) {
         println!("rasterizing layerstack");
         let sequence = self.sequence.clone();
         for id in sequence {
@@ -175,8 +305,15 @@ impl Layerstack {
                 let layer = self.fetch(&id);
                 layer.clone() // Clone the layer data if needed.
             };
-            if layer.is_rendered {
-                self.framebuffer.write(&layer);
+            if !layer.is_rendered {
+                continue;
+            }
+            match &layer.text {
+                Some(cells) => {
+                    let positioned = cells.iter().map(|cell| (layer.stack_pos, cell.clone()));
+                    self.framebuffer.text.extend(positioned);
+                }
+                None => self.framebuffer.write(&layer, depth),
             }
         }
     }
@@ -185,8 +322,18 @@ impl Layerstack {
         for pixel in self.framebuffer.buffer.iter_mut() {
             pixel.color = self.framebuffer.color;
         }
+        for dithered in self.framebuffer.dithered.iter_mut() {
+            *dithered = false;
+        }
+        for top_layer in self.framebuffer.top_layer.iter_mut() {
+            *top_layer = None;
+        }
+        self.framebuffer.text = vec![];
         for (_, layerbuffer) in self.stack.iter_mut() {
             layerbuffer.buffer = vec![];
+            if let Some(cells) = layerbuffer.text.as_mut() {
+                cells.clear();
+            }
         }
     }
     ///returns a mutable Layer from the layerstack
@@ -198,7 +345,6 @@ impl Layerstack {
     /// foreground : Layer = fetch(2);
     /// ```
     ///foreground becomes a mutable reference to the layer with id 2
-
     fn fetch_mut(&mut self, id: &LayerID) -> &mut Layer {
         if let Some(layer) = self.stack.get_mut(id) {
             layer
@@ -225,21 +371,15 @@ impl Layerstack {
     fn rebuild_sequence(&mut self) {
         println!("rebuilding layerstack sequence");
 
-        self.sequence = vec![];
-        for position in 0..self.stack.len() {
-            for (id, layer) in self.stack.iter() {
-                if layer.stack_pos as usize == position {
-                    self.sequence.push(*id);
-                }
-            }
-        }
+        //sorted rather than looked up by position so gaps in positions never hide a layer
+        self.sequence = self.stack.keys().copied().collect();
+        self.sequence.sort_by_key(|id| (self.stack[id].stack_pos, *id));
     }
 
     ///creates a new layer entry in the layerstack in the specified position, returns layerID
     ///# Parameters
     ///- `layer_id` : a new unique identification for the layer
     ///- `pos` : position to insert new layer
-    /// Removes the specified layer from the layerstack.
     ///
     /// # Behavior
     /// **Shifting behavior**:
@@ -252,12 +392,38 @@ impl Layerstack {
     /// // adds layer with ID 1.
     /// // This shifts all subsequent layers after `pos` in the layerstack by 1. then fills the void `pos` with the added layer
     /// ```
-
     pub fn add(&mut self, layer_id: LayerID, pos: u16) -> LayerID {
         println!(
             "adding new layer (id: {}) to layerstack at position {}",
             layer_id, pos
         );
+        self.insert_layer(layer_id, pos, None)
+    }
+    ///creates a new text layer entry in the layerstack in the specified position, returns layerID
+    ///text layers hold real terminal characters instead of pixels, drawn by the presenter
+    ///over the pixels of every layer beneath them and covered by pixels of layers above them
+    ///# Parameters
+    ///- `layer_id` : a new unique identification for the layer
+    ///- `pos` : position to insert new layer, shifting like `add`
+    ///# Example
+    ///```
+    ///layer_add_text(5,3);
+    ///```
+    ///adds text layer 5 at position 3, use `write_text` to fill it
+    pub fn add_text(&mut self, layer_id: LayerID, pos: u16) -> LayerID {
+        println!(
+            "adding new text layer (id: {}) to layerstack at position {}",
+            layer_id, pos
+        );
+        self.insert_layer(layer_id, pos, Some(vec![]))
+    }
+    ///inserts a new layer at `pos`, shared by `add` and `add_text`
+    fn insert_layer(
+        &mut self,
+        layer_id: LayerID,
+        pos: u16,
+        text: Option<Vec<TextCell>>,
+    ) -> LayerID {
         if self.stack.contains_key(&layer_id) {
             println!("error! layer already exists");
         }
@@ -266,6 +432,11 @@ impl Layerstack {
             buffer: vec![],
             stack_pos: pos,
             is_rendered: true,
+            blend_mode: BlendMode::Normal,
+            opacity: 1.0,
+            tint: None,
+            dither: None,
+            text,
         };
 
         self.shift(pos, ShiftDirection::Up);
@@ -280,9 +451,9 @@ impl Layerstack {
     ///
     /// # Behavior
     /// **Shifting behavior**:
-    /// - Shifts all layers with positions higher than `new_pos` up by one to make room for the layer.
-    /// - The layer specified by `layer_id` is moved to `new_pos`.
     /// - All layers with positions higher than the original position of `layer_id` are shifted down by one.
+    /// - Shifts all layers with positions from `new_pos` up by one to make room for the layer.
+    /// - The layer specified by `layer_id` is moved to `new_pos`.
     /// - The function ensures that layers are re-ordered in a way that maintains the correct hierarchy in the stack.
     ///
     /// # Example
@@ -290,14 +461,15 @@ impl Layerstack {
     /// layer_move(1, 0);
     /// ```
     /// Moves layer with ID 1 to position 0.
-
     pub fn move_layer(&mut self, layer_id: LayerID, new_pos: u16) {
         println!("moving layer (id: {}) to position {}", layer_id, new_pos);
         let old_pos = (self.fetch(&layer_id)).stack_pos;
+        //taken out of the stack so the shifts only move the other layers
+        let mut target_layer = self.stack.remove(&layer_id).unwrap();
+        self.shift(old_pos, ShiftDirection::Down);
         self.shift(new_pos, ShiftDirection::Up);
-        let target_layer = self.fetch_mut(&layer_id);
         target_layer.stack_pos = new_pos;
-        self.shift(old_pos, ShiftDirection::Down);
+        self.stack.insert(layer_id, target_layer);
     }
     /// Removes the specified layer from the layerstack.
     ///
@@ -322,6 +494,14 @@ impl Layerstack {
         self.stack.remove(&layer_id);
         self.shift(void_pos, ShiftDirection::Down);
     }
+    ///returns true if the layerstack has a layer with ID `layer_id`
+    pub fn contains(&self, layer_id: LayerID) -> bool {
+        self.stack.contains_key(&layer_id)
+    }
+    ///returns the position above every layer, adding a layer there puts it on top
+    pub(crate) fn top_pos(&self) -> u16 {
+        self.stack.values().map(|layer| layer.stack_pos + 1).max().unwrap_or(0)
+    }
     ///Toggles layer visibilty
     ///# Parameters
     ///- `layer_id` : target layer
@@ -339,6 +519,83 @@ impl Layerstack {
         let layer = self.fetch_mut(&layer_id);
         layer.is_rendered = isvisible;
     }
+    ///Sets how a layer is composited onto the layers beneath it
+    ///# Parameters
+    ///- `layer_id` : target layer
+    ///- `mode` : blend mode used during rasterization
+    ///# Example
+    ///```
+    ///layer_set_blend_mode(1,BlendMode::Additive);
+    ///```
+    ///`layer_id` 1 now brightens everything beneath it
+    pub fn set_blend_mode(&mut self, layer_id: LayerID, mode: BlendMode) {
+        println!(
+            "setting layer (id : {}) blend mode to : {:?}",
+            layer_id, mode
+        );
+        let layer = self.fetch_mut(&layer_id);
+        layer.blend_mode = mode;
+    }
+    ///Sets the opacity of a whole layer
+    ///# Parameters
+    ///- `layer_id` : target layer
+    ///- `opacity` : 0.0 is invisible, 1.0 is opaque, values in between fade the layer
+    ///# Example
+    ///```
+    ///layer_set_opacity(1,0.5);
+    ///```
+    ///`layer_id` 1 is drawn at half strength, useful for ghosted UI and fades
+    pub fn set_opacity(&mut self, layer_id: LayerID, opacity: f32) {
+        println!("setting layer (id : {}) opacity to : {}", layer_id, opacity);
+        let layer = self.fetch_mut(&layer_id);
+        layer.opacity = opacity.clamp(0.0, 1.0);
+    }
+    ///Tints every pixel of a layer towards a color
+    ///# Parameters
+    ///- `layer_id` : target layer
+    ///- `tint` : color to tint towards
+    ///- `strength` : 0.0 leaves the layer untouched, 1.0 paints every pixel in `tint`
+    ///# Example
+    ///```
+    ///layer_set_tint(1,Color::White,0.8);
+    ///```
+    ///`layer_id` 1 flashes white, stepping `strength` down to 0.0 over a few frames fades it back
+    ///
+    ///```
+    ///layer_set_tint(0,Color::Black,1.0);
+    ///```
+    ///`layer_id` 0 is faded to black
+    pub fn set_tint(&mut self, layer_id: LayerID, tint: Color, strength: f32) {
+        println!(
+            "setting layer (id : {}) tint to : {:?} ({})",
+            layer_id, tint, strength
+        );
+        let layer = self.fetch_mut(&layer_id);
+        layer.tint = Some((tint, strength.clamp(0.0, 1.0)));
+    }
+    ///Removes the tint from a layer
+    ///# Parameters
+    ///- `layer_id` : target layer
+    pub fn clear_tint(&mut self, layer_id: LayerID) {
+        println!("clearing layer (id : {}) tint", layer_id);
+        let layer = self.fetch_mut(&layer_id);
+        layer.tint = None;
+    }
+    ///Dithers a layer on its own instead of as part of the whole frame
+    ///useful for gradients and imported images that band on limited color terminals
+    ///# Parameters
+    ///- `layer_id` : target layer
+    ///- `dither` : dithering for this layer, `None` to leave it to the Renderer
+    ///# Example
+    ///```
+    ///layer_set_dither(0,Some(Dither::FloydSteinberg));
+    ///```
+    ///`layer_id` 0 is error diffused while the rest of the frame keeps the Renderer's dithering
+    pub fn set_dither(&mut self, layer_id: LayerID, dither: Option<Dither>) {
+        println!("setting layer (id : {}) dither to : {:?}", layer_id, dither);
+        let layer = self.fetch_mut(&layer_id);
+        layer.dither = dither;
+    }
 
     /// Moves layers relative to the starting position.
     ///
@@ -348,7 +605,7 @@ impl Layerstack {
     ///
     /// # Behavior
     ///
-    /// - **Up**: Shifts all values higher than the starting position up by one,
+    /// - **Up**: Shifts the starting position and all values higher than it up by one,
     ///   opening the starting position for a move or addition of a new layer.
     ///
     /// - **Down**: Shifts all values higher than the starting position down by one,
@@ -356,14 +613,12 @@ impl Layerstack {
     fn shift(&mut self, starting_pos: LayerID, direction: ShiftDirection) {
         self.sequence_rebuild_flag = true;
         for layer in self.stack.values_mut() {
-            //if greater than starting pos
-            if layer.stack_pos > starting_pos {
-                match direction {
-                    //shift all values up
-                    ShiftDirection::Up => layer.stack_pos += 1,
-                    //shift all values down
-                    ShiftDirection::Down => layer.stack_pos -= 1,
-                }
+            match direction {
+                //shift all values from starting pos up
+                ShiftDirection::Up if layer.stack_pos >= starting_pos => layer.stack_pos += 1,
+                //shift all values greater than starting pos down
+                ShiftDirection::Down if layer.stack_pos > starting_pos => layer.stack_pos -= 1,
+                _ => {}
             }
         }
     }
@@ -379,14 +634,93 @@ impl Layerstack {
     ///layer_write_sprite(10,15,Dino,1);
     ///```
     ///writes the `dino` Sprite to (10,15) on layer 1.
-
-    pub fn write_sprite(&mut self, x: x_pos, y: y_pos, sprite: &Sprite, layer_id: LayerID) -> () {
+    pub fn write_sprite(&mut self, x: x_pos, y: y_pos, sprite: &Sprite, layer_id: LayerID) {
         println!("writing Sprite to layer (id : {})", layer_id);
-        let worldspace_spritevector = self.framebuffer.to_worldspace(x, y, &sprite);
+        let worldspace_spritevector = self.framebuffer.to_worldspace(x, y, sprite);
         let layer = self.fetch_mut(&layer_id);
         layer.buffer.push(worldspace_spritevector);
     }
 
+    ///writes a SpriteVector of worldspace pixels to the target layer in one go
+    ///used to draw many loose pixels without a Sprite or an allocation per pixel
+    ///# Parameters
+    ///- `pixels` : pixels in worldspace
+    ///- `layer_id` : target layer
+    ///# Example
+    ///```
+    ///layer_write_pixels(line_pixels,1);
+    ///```
+    ///writes every pixel of `line_pixels` to layer 1.
+    pub fn write_pixels(&mut self, pixels: SpriteVector, layer_id: LayerID) {
+        let layer = self.fetch_mut(&layer_id);
+        layer.buffer.push(pixels);
+    }
+
+    ///returns the width and height of the framebuffer
+    pub(crate) fn size(&self) -> (u16, u16) {
+        (self.framebuffer.width, self.framebuffer.height)
+    }
+
+    ///returns the color of every pixel written to the target layer so far this frame,
+    ///one entry per framebuffer pixel in row major order, `None` where nothing was written
+    pub(crate) fn layer_colors(&self, layer_id: LayerID) -> Vec<Option<Color>> {
+        let (width, height) = self.size();
+        let mut colors = vec![None; width as usize * height as usize];
+        for pixel in self.fetch(&layer_id).buffer.iter().flatten() {
+            let in_bounds =
+                (0..width as x_pos).contains(&pixel.x) && (0..height as y_pos).contains(&pixel.y);
+            if pixel.isrendered && in_bounds {
+                colors[pixel.y as usize * width as usize + pixel.x as usize] = Some(pixel.color);
+            }
+        }
+        colors
+    }
+
+    ///writes terminal characters to a text layer
+    ///# Parameters
+    ///- `column` : terminal column of the first character
+    ///- `row` : terminal row of the first character, `\n` in `text` moves to the next row
+    ///- `text` : characters to write
+    ///- `fg` : color of the characters
+    ///- `bg` : color behind the characters, `None` keeps the pixels beneath visible
+    ///- `layer_id` : target text layer
+    ///# Example
+    ///```
+    ///layer_write_text(2,0,"SCORE 100",Color::White,None,5);
+    ///```
+    ///writes "SCORE 100" starting in the third column of the top row of text layer 5
+    pub fn write_text(
+        &mut self,
+        column: u16,
+        row: u16,
+        text: &str,
+        fg: Color,
+        bg: Option<Color>,
+        layer_id: LayerID,
+    ) {
+        println!("writing text to layer (id : {})", layer_id);
+        let layer = self.fetch_mut(&layer_id);
+        let cells = match layer.text.as_mut() {
+            Some(cells) => cells,
+            None => {
+                println!("error! layer (id : {}) is not a text layer", layer_id);
+                return;
+            }
+        };
+        //characters past the last terminal row or column can never be drawn
+        for (row, line) in (row..=u16::MAX).zip(text.split('\n')) {
+            for (column, character) in (column..=u16::MAX).zip(line.chars()) {
+                cells.push(TextCell {
+                    column,
+                    row,
+                    character,
+                    fg,
+                    bg,
+                });
+            }
+        }
+    }
+
     ///directly writes a pixel to the target layer
     ///# Parameters
     ///- `x` : target worldspace x position.
@@ -402,10 +736,11 @@ impl Layerstack {
         println!("writing pixel to layer (id : {})", layer_id);
         let layer = self.fetch_mut(&layer_id);
         let new_pixel: Pixel = Pixel {
-            x: x,
-            y: y,
-            color: color,
+            x,
+            y,
+            color,
             isrendered: true,
+            alpha: 255,
         };
         layer.buffer.push(vec![new_pixel]);
     }
@@ -428,19 +763,50 @@ impl Renderer {
     ///engine is now an instance of Renderer with a size of 50x50px
     pub fn new(width: u16, height: u16, bg_color: Color) -> Self {
         println!("initializing new Renderer instance");
+        let last_frame = FrameBuffer::new(width, height, bg_color);
         Renderer {
             layerstack: Layerstack::new(width, height, bg_color),
             //layerstack_sequence: vec![],
             //layerstack_sequence_rebuild: true,
             pixel_element: "██".to_string(),
-            stdout: std::io::stdout(),
+            output: Output::Terminal(std::io::stdout()),
             framerate: time::Duration::from_millis(40),
             debug: false,
+            color_depth: ColorDepth::detect(),
+            dither: Dither::None,
+            last_frame,
+            recording: None,
+            overlay: vec![],
         }
     }
+    ///returns a new instance of the Renderer that never touches the terminal
+    ///frames are rasterized and presented in memory only and `render_update` does not sleep,
+    ///colors are presented in truecolor unless changed with `set_color_depth`
+    ///# Parameters
+    ///- `width` : length of row (x aspect)
+    ///- `height` : number of rows (y aspect)
+    ///- `bg_color` : color of background
+    ///# Example
+    ///```
+    ///let engine : Renderer = Renderer::headless(50,50,Color::Black);
+    ///```
+    ///engine can be driven from tests and CI
+    pub fn headless(width: u16, height: u16, bg_color: Color) -> Self {
+        let mut renderer = Renderer::new(width, height, bg_color);
+        renderer.output = Output::Headless;
+        renderer.color_depth = ColorDepth::TrueColor;
+        renderer
+    }
+    ///returns true if the Renderer was created with `Renderer::headless`
+    pub fn is_headless(&self) -> bool {
+        matches!(self.output, Output::Headless)
+    }
     /// Clears terminal display
     /// analogous to POSIX `clear` and DOS `cls`
     pub fn clear(&self) {
+        if self.is_headless() {
+            return;
+        }
         print!("\x1b[2J\x1b[H");
     }
     ///sets framerate interval in milliseconds,
@@ -462,11 +828,24 @@ impl Renderer {
     ///set_framerate(16);
     ///```
     ///sets framerate to 60fps (16ms)
-
     pub fn set_framerate(&mut self, new_framerate: u64) {
         println!("setting framerate to {}ms", new_framerate);
         self.framerate = time::Duration::from_millis(new_framerate);
     }
+    ///overrides the detected color capability of the terminal
+    ///# Parameters
+    ///- `depth` : color depth every presented color is quantized to
+    ///- `dither` : dithering used when quantizing
+    ///# Example
+    ///```
+    ///set_color_depth(ColorDepth::Ansi256,Dither::Ordered);
+    ///```
+    ///all `Color::Rgb` pixels are presented as the nearest of the 256 ANSI colors, dithered
+    pub fn set_color_depth(&mut self, depth: ColorDepth, dither: Dither) {
+        println!("setting color depth to {:?} (dither : {:?})", depth, dither);
+        self.color_depth = depth;
+        self.dither = dither;
+    }
 
     /// updates display by rasterizing layers then pushes framebuffer to the display,
     /// then sleeps for the framerate interval
@@ -474,20 +853,53 @@ impl Renderer {
     /// ```
     /// render_update();
     /// ```
-
     pub fn render_update(&mut self) {
         println!("updating screen...");
         if self.layerstack.sequence_rebuild_flag {
             self.layerstack.rebuild_sequence();
         }
-        execute!(self.stdout, terminal::Clear(terminal::ClearType::All)).unwrap();
+        let mut frame_output: Vec<u8> = vec![];
+        queue!(frame_output, terminal::Clear(terminal::ClearType::All)).unwrap();
         //push_render(self.buffer.buffer.clone());
         //pushes render
-        self.layerstack.rasterize();
-        self.render_push();
-        //self.stdout.flush().unwrap();
+        self.layerstack.rasterize(self.color_depth);
+        self.render_push(&mut frame_output);
+        if let Some(recording) = self.recording.as_mut() {
+            recording.record_frame(&frame_output, self.framerate);
+        }
+        if let Output::Terminal(stdout) = &mut self.output {
+            stdout.write_all(&frame_output).unwrap();
+            stdout.flush().unwrap();
+        }
         self.layerstack.wipe_buffers();
-        thread::sleep(self.framerate);
+        if !self.is_headless() {
+            thread::sleep(self.framerate);
+        }
+    }
+    ///starts recording every presented frame, replacing any recording in progress
+    ///timestamps advance by the framerate of each frame so recordings are deterministic
+    ///# Example
+    ///```
+    ///start_recording();
+    ///```
+    pub fn start_recording(&mut self) {
+        println!("starting recording");
+        let columns = self.layerstack.framebuffer.width * self.element_width();
+        self.recording = Some(Recording::new(columns, self.layerstack.framebuffer.height));
+    }
+    ///stops recording and returns the recorded session, `None` if nothing was being recorded
+    ///# Example
+    ///```
+    ///stop_recording().unwrap().save("demo.cast");
+    ///```
+    ///saves everything presented since `start_recording` as an asciicast v2 file
+    pub fn stop_recording(&mut self) -> Option<Recording> {
+        println!("stopping recording");
+        self.recording.take()
+    }
+    ///returns true while a recording is in progress
+    pub fn is_recording(&self) -> bool {
+        self.recording.is_some()
     }
     /// pushes framebuffer to Display
     /// by drawing all pixels in the framebuffer to the Display
@@ -496,19 +908,152 @@ impl Renderer {
     /// render_push();
     /// ```
     /// displays framebuffer
-    fn render_push(&mut self) {
+    fn render_push(&mut self, frame_output: &mut Vec<u8>) {
         println!("pushing FrameBuffer to display");
-        for (_, pixel) in (self.layerstack.framebuffer.buffer).clone().iter().enumerate() {
-            if pixel.isrendered {
-                self.draw_pixel(pixel.x as u16, pixel.y as u16, pixel.color).unwrap();
+        let framebuffer = &self.layerstack.framebuffer;
+        let mut colors: Vec<Color> = framebuffer.buffer.iter().map(|pixel| pixel.color).collect();
+        //pixels dithered by their layer are already quantized
+        let mask: Vec<bool> = framebuffer.dithered.iter().map(|dithered| !dithered).collect();
+        color::dither(
+            &mut colors,
+            framebuffer.width as usize,
+            self.color_depth,
+            self.dither,
+            &mask,
+        );
+        let mut frame = framebuffer.clone();
+        for (pixel, pixel_color) in frame.buffer.iter_mut().zip(colors) {
+            pixel.color = pixel_color;
+        }
+        let overlay = self.overlay_cells(frame.width);
+        frame.text.extend(overlay);
+        //the frame keeps the drawn cells in their presented colors for screenshots and snapshots
+        frame.text = frame.visible_text(self.element_width());
+        for (_, cell) in frame.text.iter_mut() {
+            cell.fg = color::quantize(cell.fg, self.color_depth);
+            cell.bg = cell.bg.map(|bg| color::quantize(bg, self.color_depth));
+        }
+        let cells: HashMap<(u16, u16), &TextCell> =
+            frame.text.iter().map(|(_, cell)| ((cell.column, cell.row), cell)).collect();
+        for pixel in frame.buffer.iter() {
+            let (x, y) = (pixel.x as u16, pixel.y as u16);
+            let columns = x * self.element_width()..(x + 1) * self.element_width();
+            if !columns.clone().any(|column| cells.contains_key(&(column, y))) {
+                if pixel.isrendered {
+                    self.draw_pixel(frame_output, x, y, pixel.color).unwrap();
+                }
+                continue;
+            }
+            //pixel partially covered by text, drawn column by column
+            for (column, element) in columns.zip(self.pixel_element.chars()) {
+                let drawn = match cells.get(&(column, y)) {
+                    Some(cell) => {
+                        let bg = cell.bg.unwrap_or(if pixel.isrendered {
+                            pixel.color
+                        } else {
+                            Color::Reset
+                        });
+                        cell.character.with(cell.fg).on(bg)
+                    }
+                    None if pixel.isrendered => element.with(pixel.color),
+                    None => continue,
+                };
+                queue!(
+                    frame_output,
+                    cursor::MoveTo(column, y),
+                    style::PrintStyledContent(drawn)
+                )
+                .unwrap();
             }
         }
+        self.last_frame = frame;
+    }
+    ///returns the number of terminal columns a single pixel takes
+    fn element_width(&self) -> u16 {
+        self.pixel_element.chars().count() as u16
+    }
+    ///returns the text cells of the debug overlay, one message per row from the top,
+    ///positioned above every layer so nothing can cover them
+    fn overlay_cells(&self, width: u16) -> Vec<(u16, TextCell)> {
+        let columns = (width * self.element_width()) as usize;
+        let mut cells: Vec<(u16, TextCell)> = vec![];
+        for (row, (key, message)) in self.overlay.iter().enumerate() {
+            let line = format!("{} : {}", key, message).replace('\n', " ");
+            for (column, character) in line.chars().take(columns).enumerate() {
+                let cell = TextCell {
+                    column: column as u16,
+                    row: row as u16,
+                    character,
+                    fg: Color::White,
+                    bg: Some(Color::DarkRed),
+                };
+                cells.push((u16::MAX, cell));
+            }
+        }
+        cells
+    }
+    ///shows a message on the debug overlay until it is cleared, replacing the message of `key`
+    ///used to surface errors during development without crashing, such as failed asset reloads
+    ///# Parameters
+    ///- `key` : what the message is about, shown before the message
+    ///- `message` : text to show
+    ///# Example
+    ///```
+    ///overlay_message("assets/dino.sprites","line 3 : invalid color");
+    ///```
+    pub fn overlay_message(&mut self, key: &str, message: &str) {
+        println!("debug overlay : {} : {}", key, message);
+        match self.overlay.iter_mut().find(|(existing, _)| existing == key) {
+            Some((_, existing)) => *existing = message.to_string(),
+            None => self.overlay.push((key.to_string(), message.to_string())),
+        }
+    }
+    ///removes the message of `key` from the debug overlay
+    pub fn clear_overlay_message(&mut self, key: &str) {
+        self.overlay.retain(|(existing, _)| existing != key);
+    }
+    ///returns the (key, message) pairs shown on the debug overlay
+    pub fn overlay_messages(&self) -> &[(String, String)] {
+        &self.overlay
+    }
+    ///returns the last frame pushed to the display
+    ///colors are quantized and dithered exactly as they were presented
+    ///# Example
+    ///```
+    ///let screen : &FrameBuffer = last_frame();
+    ///```
+    pub fn last_frame(&self) -> &FrameBuffer {
+        &self.last_frame
+    }
+    ///saves the last presented frame to a file
+    ///# Parameters
+    ///- `path` : path of the file to create
+    ///- `format` : ANSI text, HTML page or SVG image
+    ///# Example
+    ///```
+    ///save_screenshot("title.html",ScreenshotFormat::Html);
+    ///```
+    ///writes the last frame as it appeared on screen to title.html
+    pub fn save_screenshot(&self, path: &str, format: ScreenshotFormat) -> io::Result<()> {
+        println!("saving screenshot to {} ({:?})", path, format);
+        let contents = match format {
+            ScreenshotFormat::Ansi => export::frame_to_ansi(&self.last_frame, &self.pixel_element),
+            ScreenshotFormat::Html => export::frame_to_html(&self.last_frame, &self.pixel_element),
+            ScreenshotFormat::Svg => export::frame_to_svg(&self.last_frame, &self.pixel_element),
+        };
+        fs::write(path, contents)
     }
-    fn draw_pixel(&mut self, x: u16, y: u16, color: Color) -> io::Result<()> {
+    fn draw_pixel(
+        &self,
+        frame_output: &mut Vec<u8>,
+        x: u16,
+        y: u16,
+        color: Color,
+    ) -> io::Result<()> {
         //let element = format!("|{} ", i);
         queue!(
-            self.stdout,
-            cursor::MoveTo(x * 2, y),
+            frame_output,
+            cursor::MoveTo(x * self.element_width(), y),
             style::PrintStyledContent(self.pixel_element.clone().with(color))
         )?;
 
@@ -525,5 +1070,6 @@ impl Renderer {
     ///enables debug logging
     pub fn debug_mode(&mut self, toggle: bool) {
         println!("toggles debug logging");
+        self.debug = toggle;
     }
 }
@@ -0,0 +1,844 @@
+//tiled.rs
+use crate::image;
+use crate::sprite::{self, Sprite};
+use crate::tilemap::{Tile, TileFlags, Tilemap, Tileset};
+use crossterm::style::Color;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::{fs, time};
+
+///bits of a global tile id Tiled uses to flip or rotate a tile
+const FLIP_FLAGS: u32 = 0xF000_0000;
+
+///a map exported from the Tiled editor, converted to engine types
+pub struct TiledMap {
+    ///tile layers of the map in Tiled's order, tiles are named `<tileset>/<tile id>`
+    pub tilemap: Tilemap,
+    ///object layers of the map in Tiled's order
+    pub object_layers: Vec<ObjectLayer>,
+}
+
+///a Tiled object layer
+#[derive(Clone, Debug, PartialEq)]
+pub struct ObjectLayer {
+    pub name: String,
+    pub objects: Vec<TiledObject>,
+}
+
+///outline of a Tiled object, points are relative to the object position
+#[derive(Clone, Debug, PartialEq)]
+pub enum ObjectShape {
+    Rectangle,
+    Ellipse,
+    Point,
+    Polygon(Vec<(f32, f32)>),
+    Polyline(Vec<(f32, f32)>),
+}
+
+///a single object of an object layer, positions and sizes are in map pixels
+#[derive(Clone, Debug, PartialEq)]
+pub struct TiledObject {
+    pub id: u32,
+    pub name: String,
+    ///class (`type` in older Tiled versions) of the object
+    pub class: String,
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+    pub shape: ObjectShape,
+    ///tileset index of the tile shown by a tile object
+    pub tile: Option<u16>,
+    ///custom properties, values are kept as text
+    pub properties: HashMap<String, String>,
+}
+
+///image referenced by a tileset
+struct RawImage {
+    path: PathBuf,
+    transparent: Option<Color>,
+}
+
+///tile entry of a tileset holding per tile data
+struct RawTile {
+    id: u32,
+    image: Option<RawImage>,
+    properties: HashMap<String, String>,
+    ///(tile id, duration in milliseconds) of every animation frame
+    animation: Vec<(u32, u32)>,
+}
+
+struct RawTileset {
+    first_gid: u32,
+    name: String,
+    tile_width: u16,
+    tile_height: u16,
+    tile_count: u32,
+    columns: u32,
+    margin: u16,
+    spacing: u16,
+    image: Option<RawImage>,
+    tiles: Vec<RawTile>,
+}
+
+enum RawLayer {
+    Tiles { name: String, gids: Vec<u32> },
+    Objects(ObjectLayer, Vec<Option<u32>>),
+}
+
+///format independent contents of a map file
+struct RawMap {
+    width: u16,
+    height: u16,
+    tile_width: u16,
+    tile_height: u16,
+    tilesets: Vec<RawTileset>,
+    layers: Vec<RawLayer>,
+}
+
+///loads a map saved by Tiled, the format is picked by extension (`.tmx` or `.json`/`.tmj`)
+///supports orthogonal maps with finite tile layers (CSV, Base64 or XML data), object layers,
+///embedded and external tilesets (`.tsx` or `.json`/`.tsj`) built from one image or a collection
+///of images, tile animations with one duration for every frame and a `solid` bool tile property,
+///anything else (e.g. hidden, translucent, offset or parallax layers) is an error
+///tileset images may be PNG, PPM, PAM or BMP, the `tiled` feature turns on `png` for that
+///# Parameters
+///- `path` : path of the map file, tileset and image paths are relative to it
+///- `frame_duration` : duration of one tick, used to convert animation frame times to ticks
+///# Example
+///```
+///let level : TiledMap = load_tiled("levels/cave.tmx",renderer.framerate)?;
+///```
+pub fn load_tiled(path: &str, frame_duration: time::Duration) -> Result<TiledMap, String> {
+    println!("loading Tiled map {}", path);
+    let text =
+        fs::read_to_string(path).map_err(|why| format!("could not read {} : {}", path, why))?;
+    let base = Path::new(path).parent().unwrap_or(Path::new("."));
+    let raw = match extension(Path::new(path)).as_str() {
+        "tmx" => tmx_map(&text, base),
+        "json" | "tmj" => json_map(&text, base),
+        other => Err(format!("unknown Tiled map extension .{}", other)),
+    };
+    raw.and_then(|raw| build(raw, frame_duration)).map_err(|why| format!("{} : {}", path, why))
+}
+
+///parses a TMX map, see `load_tiled`
+///# Parameters
+///- `text` : contents of the `.tmx` file
+///- `base` : directory tileset and image paths are relative to
+///- `frame_duration` : duration of one tick
+pub fn parse_tmx(
+    text: &str,
+    base: &Path,
+    frame_duration: time::Duration,
+) -> Result<TiledMap, String> {
+    build(tmx_map(text, base)?, frame_duration)
+}
+
+///parses a Tiled JSON map, see `load_tiled`
+///# Parameters
+///- `text` : contents of the `.json` file
+///- `base` : directory tileset and image paths are relative to
+///- `frame_duration` : duration of one tick
+pub fn parse_tiled_json(
+    text: &str,
+    base: &Path,
+    frame_duration: time::Duration,
+) -> Result<TiledMap, String> {
+    build(json_map(text, base)?, frame_duration)
+}
+
+fn extension(path: &Path) -> String {
+    path.extension().and_then(|extension| extension.to_str()).unwrap_or_default().to_lowercase()
+}
+
+///parses the `#rrggbb` (or `rrggbb`) transparent color of a tileset image
+fn parse_transparent(text: &str) -> Result<Color, String> {
+    let hex = text.trim_start_matches('#');
+    let channel = |range: std::ops::Range<usize>| {
+        hex.get(range)
+            .and_then(|digits| u8::from_str_radix(digits, 16).ok())
+            .ok_or(format!("invalid transparent color {}", text))
+    };
+    if hex.len() != 6 {
+        return Err(format!("invalid transparent color {}", text));
+    }
+    Ok(Color::Rgb {
+        r: channel(0..2)?,
+        g: channel(2..4)?,
+        b: channel(4..6)?,
+    })
+}
+
+///decodes standard Base64, whitespace is skipped
+fn decode_base64(text: &str) -> Result<Vec<u8>, String> {
+    let mut bytes: Vec<u8> = vec![];
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for character in text.bytes().filter(|byte| !byte.is_ascii_whitespace()) {
+        let value = match character {
+            b'A'..=b'Z' => character - b'A',
+            b'a'..=b'z' => character - b'a' + 26,
+            b'0'..=b'9' => character - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            b'=' => break,
+            _ => return Err("invalid Base64 layer data".to_string()),
+        };
+        buffer = buffer << 6 | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            bytes.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Ok(bytes)
+}
+
+///decodes Base64 layer data into global tile ids
+fn base64_gids(text: &str, compression: &str) -> Result<Vec<u32>, String> {
+    if !compression.is_empty() {
+        return Err(format!(
+            "{} compressed layer data is not supported, save the map with CSV or uncompressed Base64",
+            compression
+        ));
+    }
+    Ok(decode_base64(text)?
+        .chunks_exact(4)
+        .map(|gid| u32::from_le_bytes([gid[0], gid[1], gid[2], gid[3]]))
+        .collect())
+}
+
+///parses CSV layer data into global tile ids
+fn csv_gids(text: &str) -> Result<Vec<u32>, String> {
+    text.split(',')
+        .map(|gid| gid.trim())
+        .filter(|gid| !gid.is_empty())
+        .map(|gid| gid.parse::<u32>().map_err(|_| format!("invalid tile id {}", gid)))
+        .collect()
+}
+
+///reads the tileset referenced by `source`, relative to `base`
+fn external_tileset(source: &str, base: &Path, first_gid: u32) -> Result<RawTileset, String> {
+    let path = base.join(source);
+    let text = fs::read_to_string(&path)
+        .map_err(|why| format!("could not read tileset {} : {}", path.display(), why))?;
+    let tileset_base = path.parent().unwrap_or(base);
+    match extension(&path).as_str() {
+        "tsx" => {
+            let document = roxmltree::Document::parse(&text)
+                .map_err(|why| format!("invalid tileset {} : {}", source, why))?;
+            tmx_tileset(document.root_element(), tileset_base, first_gid)
+        }
+        "json" | "tsj" => {
+            let value: Value = serde_json::from_str(&text)
+                .map_err(|why| format!("invalid tileset {} : {}", source, why))?;
+            json_tileset(&value, tileset_base, first_gid)
+        }
+        other => Err(format!("unknown Tiled tileset extension .{}", other)),
+    }
+}
+
+///returns a required attribute of a TMX element
+fn attribute<T: FromStr>(node: roxmltree::Node, name: &str) -> Result<T, String> {
+    let text =
+        node.attribute(name).ok_or(format!("<{}> is missing {}", node.tag_name().name(), name))?;
+    text.parse::<T>().map_err(|_| {
+        format!(
+            "<{}> has an invalid {} : {}",
+            node.tag_name().name(),
+            name,
+            text
+        )
+    })
+}
+
+///returns an attribute of a TMX element or `default` when it is missing
+fn attribute_or<T: FromStr>(node: roxmltree::Node, name: &str, default: T) -> Result<T, String> {
+    match node.attribute(name) {
+        Some(_) => attribute(node, name),
+        None => Ok(default),
+    }
+}
+
+///returns an error when a TMX layer is hidden, translucent, offset or scrolls with parallax
+fn tmx_layer_defaults(node: roxmltree::Node, name: &str) -> Result<(), String> {
+    let hidden = attribute_or(node, "visible", 1u8)? == 0;
+    let opacity = attribute_or(node, "opacity", 1.0f32)? != 1.0;
+    let offset = attribute_or(node, "offsetx", 0.0f32)? != 0.0
+        || attribute_or(node, "offsety", 0.0f32)? != 0.0;
+    let parallax = attribute_or(node, "parallaxx", 1.0f32)? != 1.0
+        || attribute_or(node, "parallaxy", 1.0f32)? != 1.0;
+    layer_defaults(name, hidden, opacity, offset, parallax)
+}
+
+///returns an error naming the first layer setting the engine can not represent
+fn layer_defaults(
+    name: &str,
+    hidden: bool,
+    opacity: bool,
+    offset: bool,
+    parallax: bool,
+) -> Result<(), String> {
+    let feature = [
+        (hidden, "hidden layers"),
+        (opacity, "layer opacity"),
+        (offset, "layer offsets"),
+        (parallax, "layer parallax"),
+    ]
+    .into_iter()
+    .find(|(used, _)| *used);
+    match feature {
+        Some((_, feature)) => Err(format!("{} (layer {}) are not supported", feature, name)),
+        None => Ok(()),
+    }
+}
+
+///returns the element children of a TMX element called `name`
+fn elements<'a, 'input>(
+    node: roxmltree::Node<'a, 'input>,
+    name: &'a str,
+) -> impl Iterator<Item = roxmltree::Node<'a, 'input>> {
+    node.children().filter(move |child| child.has_tag_name(name))
+}
+
+///reads the `<properties>` of a TMX element
+fn tmx_properties(node: roxmltree::Node) -> HashMap<String, String> {
+    let mut properties: HashMap<String, String> = HashMap::new();
+    for property in elements(node, "properties").flat_map(|list| elements(list, "property")) {
+        let value = property.attribute("value").or(property.text()).unwrap_or_default();
+        properties.insert(
+            property.attribute("name").unwrap_or_default().to_string(),
+            value.to_string(),
+        );
+    }
+    properties
+}
+
+///reads an `<image>` element
+fn tmx_image(node: roxmltree::Node, base: &Path) -> Result<RawImage, String> {
+    let transparent = node.attribute("trans").map(parse_transparent).transpose()?;
+    Ok(RawImage {
+        path: base.join(attribute::<String>(node, "source")?),
+        transparent,
+    })
+}
+
+fn tmx_map(text: &str, base: &Path) -> Result<RawMap, String> {
+    let document =
+        roxmltree::Document::parse(text).map_err(|why| format!("invalid TMX : {}", why))?;
+    let map = document.root_element();
+    if !map.has_tag_name("map") {
+        return Err("TMX root element is not <map>".to_string());
+    }
+    let orientation = map.attribute("orientation").unwrap_or("orthogonal");
+    if orientation != "orthogonal" {
+        return Err(format!(
+            "{} maps are not supported, only orthogonal",
+            orientation
+        ));
+    }
+    if map.attribute("infinite") == Some("1") {
+        return Err("infinite maps are not supported".to_string());
+    }
+    let mut raw = RawMap {
+        width: attribute(map, "width")?,
+        height: attribute(map, "height")?,
+        tile_width: attribute(map, "tilewidth")?,
+        tile_height: attribute(map, "tileheight")?,
+        tilesets: vec![],
+        layers: vec![],
+    };
+    for child in map.children().filter(|child| child.is_element()) {
+        match child.tag_name().name() {
+            "tileset" => {
+                let first_gid = attribute(child, "firstgid")?;
+                raw.tilesets.push(match child.attribute("source") {
+                    Some(source) => external_tileset(source, base, first_gid)?,
+                    None => tmx_tileset(child, base, first_gid)?,
+                });
+            }
+            "layer" => raw.layers.push(tmx_tile_layer(child)?),
+            "objectgroup" => raw.layers.push(tmx_object_layer(child)?),
+            "imagelayer" => return Err("image layers are not supported".to_string()),
+            "group" => return Err("group layers are not supported".to_string()),
+            _ => {}
+        }
+    }
+    Ok(raw)
+}
+
+fn tmx_tileset(node: roxmltree::Node, base: &Path, first_gid: u32) -> Result<RawTileset, String> {
+    let image = elements(node, "image").next().map(|image| tmx_image(image, base)).transpose()?;
+    let mut tiles: Vec<RawTile> = vec![];
+    for tile in elements(node, "tile") {
+        let mut animation: Vec<(u32, u32)> = vec![];
+        for frame in elements(tile, "animation").flat_map(|frames| elements(frames, "frame")) {
+            animation.push((attribute(frame, "tileid")?, attribute(frame, "duration")?));
+        }
+        tiles.push(RawTile {
+            id: attribute(tile, "id")?,
+            image: elements(tile, "image")
+                .next()
+                .map(|image| tmx_image(image, base))
+                .transpose()?,
+            properties: tmx_properties(tile),
+            animation,
+        });
+    }
+    Ok(RawTileset {
+        first_gid,
+        name: attribute_or(node, "name", String::new())?,
+        tile_width: attribute(node, "tilewidth")?,
+        tile_height: attribute(node, "tileheight")?,
+        tile_count: attribute_or(node, "tilecount", 0)?,
+        columns: attribute_or(node, "columns", 0)?,
+        margin: attribute_or(node, "margin", 0)?,
+        spacing: attribute_or(node, "spacing", 0)?,
+        image,
+        tiles,
+    })
+}
+
+fn tmx_tile_layer(node: roxmltree::Node) -> Result<RawLayer, String> {
+    let name: String = attribute_or(node, "name", String::new())?;
+    tmx_layer_defaults(node, &name)?;
+    let data = elements(node, "data").next().ok_or(format!("layer {} has no data", name))?;
+    if elements(data, "chunk").next().is_some() {
+        return Err(format!(
+            "layer {} is chunked, infinite maps are not supported",
+            name
+        ));
+    }
+    let text = data.text().unwrap_or_default();
+    let gids = match data.attribute("encoding") {
+        Some("csv") => csv_gids(text)?,
+        Some("base64") => base64_gids(text, data.attribute("compression").unwrap_or_default())?,
+        Some(encoding) => return Err(format!("layer {} has unknown encoding {}", name, encoding)),
+        None => elements(data, "tile")
+            .map(|tile| attribute_or(tile, "gid", 0))
+            .collect::<Result<Vec<u32>, String>>()?,
+    };
+    Ok(RawLayer::Tiles { name, gids })
+}
+
+fn tmx_object_layer(node: roxmltree::Node) -> Result<RawLayer, String> {
+    let name: String = attribute_or(node, "name", String::new())?;
+    tmx_layer_defaults(node, &name)?;
+    let mut objects: Vec<TiledObject> = vec![];
+    let mut gids: Vec<Option<u32>> = vec![];
+    for object in elements(node, "object") {
+        let points = |list: roxmltree::Node| -> Result<Vec<(f32, f32)>, String> {
+            let invalid = || format!("object in layer {} has invalid points", name);
+            attribute::<String>(list, "points")?
+                .split_whitespace()
+                .map(|point| {
+                    let (x, y) = point.split_once(',').ok_or_else(invalid)?;
+                    Ok((
+                        x.parse().map_err(|_| invalid())?,
+                        y.parse().map_err(|_| invalid())?,
+                    ))
+                })
+                .collect()
+        };
+        let mut shape = ObjectShape::Rectangle;
+        for child in object.children().filter(|child| child.is_element()) {
+            shape = match child.tag_name().name() {
+                "ellipse" => ObjectShape::Ellipse,
+                "point" => ObjectShape::Point,
+                "polygon" => ObjectShape::Polygon(points(child)?),
+                "polyline" => ObjectShape::Polyline(points(child)?),
+                "text" => return Err(format!("text objects (layer {}) are not supported", name)),
+                _ => shape,
+            };
+        }
+        gids.push(object.attribute("gid").map(|_| attribute(object, "gid")).transpose()?);
+        objects.push(TiledObject {
+            id: attribute_or(object, "id", 0)?,
+            name: attribute_or(object, "name", String::new())?,
+            class: object
+                .attribute("class")
+                .or(object.attribute("type"))
+                .unwrap_or_default()
+                .to_string(),
+            x: attribute_or(object, "x", 0.0)?,
+            y: attribute_or(object, "y", 0.0)?,
+            width: attribute_or(object, "width", 0.0)?,
+            height: attribute_or(object, "height", 0.0)?,
+            shape,
+            tile: None,
+            properties: tmx_properties(object),
+        });
+    }
+    Ok(RawLayer::Objects(ObjectLayer { name, objects }, gids))
+}
+
+///returns a required whole number field of a JSON object
+fn json_number<T: TryFrom<u64>>(value: &Value, key: &str) -> Result<T, String> {
+    value
+        .get(key)
+        .and_then(Value::as_u64)
+        .and_then(|number| T::try_from(number).ok())
+        .ok_or(format!("missing or invalid {}", key))
+}
+
+///returns a whole number field of a JSON object or `default` when it is missing
+fn json_number_or<T: TryFrom<u64>>(value: &Value, key: &str, default: T) -> Result<T, String> {
+    match value.get(key) {
+        Some(_) => json_number(value, key),
+        None => Ok(default),
+    }
+}
+
+fn json_float(value: &Value, key: &str) -> f32 {
+    json_float_or(value, key, 0.0)
+}
+
+fn json_float_or(value: &Value, key: &str, default: f32) -> f32 {
+    value.get(key).and_then(Value::as_f64).map_or(default, |number| number as f32)
+}
+
+///JSON version of `tmx_layer_defaults`
+fn json_layer_defaults(layer: &Value, name: &str) -> Result<(), String> {
+    layer_defaults(
+        name,
+        layer.get("visible").and_then(Value::as_bool) == Some(false),
+        json_float_or(layer, "opacity", 1.0) != 1.0,
+        json_float(layer, "offsetx") != 0.0 || json_float(layer, "offsety") != 0.0,
+        json_float_or(layer, "parallaxx", 1.0) != 1.0
+            || json_float_or(layer, "parallaxy", 1.0) != 1.0,
+    )
+}
+
+fn json_string<'a>(value: &'a Value, key: &str) -> &'a str {
+    value.get(key).and_then(Value::as_str).unwrap_or_default()
+}
+
+fn json_array<'a>(value: &'a Value, key: &str) -> &'a [Value] {
+    value.get(key).and_then(Value::as_array).map(Vec::as_slice).unwrap_or_default()
+}
+
+///reads the `properties` list of a JSON object
+fn json_properties(value: &Value) -> HashMap<String, String> {
+    json_array(value, "properties")
+        .iter()
+        .map(|property| {
+            let text = match property.get("value") {
+                Some(Value::String(text)) => text.clone(),
+                Some(other) => other.to_string(),
+                None => String::new(),
+            };
+            (json_string(property, "name").to_string(), text)
+        })
+        .collect()
+}
+
+///reads the image of a JSON tileset or tile
+fn json_image(value: &Value, base: &Path) -> Result<Option<RawImage>, String> {
+    match value.get("image").and_then(Value::as_str) {
+        Some(source) => Ok(Some(RawImage {
+            path: base.join(source),
+            transparent: value
+                .get("transparentcolor")
+                .and_then(Value::as_str)
+                .map(parse_transparent)
+                .transpose()?,
+        })),
+        None => Ok(None),
+    }
+}
+
+fn json_map(text: &str, base: &Path) -> Result<RawMap, String> {
+    let map: Value =
+        serde_json::from_str(text).map_err(|why| format!("invalid Tiled JSON : {}", why))?;
+    let orientation = map.get("orientation").and_then(Value::as_str).unwrap_or("orthogonal");
+    if orientation != "orthogonal" {
+        return Err(format!(
+            "{} maps are not supported, only orthogonal",
+            orientation
+        ));
+    }
+    if map.get("infinite").and_then(Value::as_bool) == Some(true) {
+        return Err("infinite maps are not supported".to_string());
+    }
+    let mut raw = RawMap {
+        width: json_number(&map, "width")?,
+        height: json_number(&map, "height")?,
+        tile_width: json_number(&map, "tilewidth")?,
+        tile_height: json_number(&map, "tileheight")?,
+        tilesets: vec![],
+        layers: vec![],
+    };
+    for tileset in json_array(&map, "tilesets") {
+        let first_gid = json_number(tileset, "firstgid")?;
+        raw.tilesets.push(match tileset.get("source").and_then(Value::as_str) {
+            Some(source) => external_tileset(source, base, first_gid)?,
+            None => json_tileset(tileset, base, first_gid)?,
+        });
+    }
+    for layer in json_array(&map, "layers") {
+        let name = json_string(layer, "name").to_string();
+        if matches!(json_string(layer, "type"), "tilelayer" | "objectgroup") {
+            json_layer_defaults(layer, &name)?;
+        }
+        match json_string(layer, "type") {
+            "tilelayer" => {
+                if layer.get("chunks").is_some() {
+                    return Err(format!(
+                        "layer {} is chunked, infinite maps are not supported",
+                        name
+                    ));
+                }
+                let gids = match layer.get("data") {
+                    Some(Value::String(text)) => {
+                        base64_gids(text, json_string(layer, "compression"))?
+                    }
+                    Some(Value::Array(gids)) => gids
+                        .iter()
+                        .map(|gid| {
+                            gid.as_u64()
+                                .map(|gid| gid as u32)
+                                .ok_or(format!("invalid tile id {}", gid))
+                        })
+                        .collect::<Result<Vec<u32>, String>>()?,
+                    _ => return Err(format!("layer {} has no data", name)),
+                };
+                raw.layers.push(RawLayer::Tiles { name, gids });
+            }
+            "objectgroup" => raw.layers.push(json_object_layer(layer, name)?),
+            "imagelayer" => return Err("image layers are not supported".to_string()),
+            "group" => return Err("group layers are not supported".to_string()),
+            other => return Err(format!("unknown layer type {}", other)),
+        }
+    }
+    Ok(raw)
+}
+
+fn json_tileset(value: &Value, base: &Path, first_gid: u32) -> Result<RawTileset, String> {
+    let mut tiles: Vec<RawTile> = vec![];
+    for tile in json_array(value, "tiles") {
+        let animation = json_array(tile, "animation")
+            .iter()
+            .map(|frame| {
+                Ok((
+                    json_number(frame, "tileid")?,
+                    json_number(frame, "duration")?,
+                ))
+            })
+            .collect::<Result<Vec<(u32, u32)>, String>>()?;
+        tiles.push(RawTile {
+            id: json_number(tile, "id")?,
+            image: json_image(tile, base)?,
+            properties: json_properties(tile),
+            animation,
+        });
+    }
+    Ok(RawTileset {
+        first_gid,
+        name: json_string(value, "name").to_string(),
+        tile_width: json_number(value, "tilewidth")?,
+        tile_height: json_number(value, "tileheight")?,
+        tile_count: json_number_or(value, "tilecount", 0)?,
+        columns: json_number_or(value, "columns", 0)?,
+        margin: json_number_or(value, "margin", 0)?,
+        spacing: json_number_or(value, "spacing", 0)?,
+        image: json_image(value, base)?,
+        tiles,
+    })
+}
+
+fn json_object_layer(layer: &Value, name: String) -> Result<RawLayer, String> {
+    let mut objects: Vec<TiledObject> = vec![];
+    let mut gids: Vec<Option<u32>> = vec![];
+    for object in json_array(layer, "objects") {
+        let points = |key: &str| -> Vec<(f32, f32)> {
+            json_array(object, key)
+                .iter()
+                .map(|point| (json_float(point, "x"), json_float(point, "y")))
+                .collect()
+        };
+        let flag = |key: &str| object.get(key).and_then(Value::as_bool) == Some(true);
+        let shape = if object.get("text").is_some() {
+            return Err(format!("text objects (layer {}) are not supported", name));
+        } else if flag("ellipse") {
+            ObjectShape::Ellipse
+        } else if flag("point") {
+            ObjectShape::Point
+        } else if object.get("polygon").is_some() {
+            ObjectShape::Polygon(points("polygon"))
+        } else if object.get("polyline").is_some() {
+            ObjectShape::Polyline(points("polyline"))
+        } else {
+            ObjectShape::Rectangle
+        };
+        gids.push(object.get("gid").map(|_| json_number(object, "gid")).transpose()?);
+        let class = match json_string(object, "class") {
+            "" => json_string(object, "type"),
+            class => class,
+        };
+        objects.push(TiledObject {
+            id: json_number_or(object, "id", 0)?,
+            name: json_string(object, "name").to_string(),
+            class: class.to_string(),
+            x: json_float(object, "x"),
+            y: json_float(object, "y"),
+            width: json_float(object, "width"),
+            height: json_float(object, "height"),
+            shape,
+            tile: None,
+            properties: json_properties(object),
+        });
+    }
+    Ok(RawLayer::Objects(ObjectLayer { name, objects }, gids))
+}
+
+///loads the tile images of a tileset, keyed by tile id
+fn tileset_images(set: &RawTileset) -> Result<Vec<(u32, Sprite)>, String> {
+    let load =
+        |image: &RawImage| image::load_image(&image.path.to_string_lossy(), image.transparent);
+    let mut images: Vec<(u32, Sprite)> = vec![];
+    if let Some(image) = set.image.as_ref() {
+        let sheet = load(image)?;
+        //positions are worked out in u64 so sizes that do not match the image can not overflow
+        let (margin, spacing) = (set.margin as u64, set.spacing as u64);
+        let (tile_width, tile_height) = (set.tile_width as u64, set.tile_height as u64);
+        let (sheet_width, sheet_height) = (sheet.width as u64, sheet.height as u64);
+        let (step_x, step_y) = (
+            (tile_width + spacing).max(1),
+            (tile_height + spacing).max(1),
+        );
+        let columns = match set.columns {
+            0 => (sheet_width.saturating_sub(2 * margin) + spacing) / step_x,
+            columns => columns as u64,
+        };
+        let rows = (sheet_height.saturating_sub(2 * margin) + spacing) / step_y;
+        let tile_count = match set.tile_count {
+            0 => columns * rows,
+            tile_count => tile_count as u64,
+        };
+        for id in 0..tile_count {
+            let x = margin + id % columns.max(1) * step_x;
+            let y = margin + id / columns.max(1) * step_y;
+            if x + tile_width > sheet_width || y + tile_height > sheet_height {
+                return Err(format!(
+                    "tileset {} is larger than its image, tile {} lies outside the {}x{} image",
+                    set.name, id, sheet.width, sheet.height
+                ));
+            }
+            images.push((
+                id as u32,
+                sprite::crop_sprite(&sheet, x as u16, y as u16, set.tile_width, set.tile_height),
+            ));
+        }
+    }
+    for tile in set.tiles.iter() {
+        if let Some(image) = tile.image.as_ref() {
+            images.retain(|(id, _)| *id != tile.id);
+            images.push((tile.id, load(image)?));
+        }
+    }
+    images.sort_by_key(|(id, _)| *id);
+    Ok(images)
+}
+
+///converts the format independent map into engine types
+fn build(raw: RawMap, frame_duration: time::Duration) -> Result<TiledMap, String> {
+    let mut tileset = Tileset::new(raw.tile_width, raw.tile_height);
+    let mut tile_indices: HashMap<u32, u16> = HashMap::new();
+    let tick = frame_duration.as_millis().max(1) as u64;
+    for set in raw.tilesets.iter() {
+        if (set.tile_width, set.tile_height) != (raw.tile_width, raw.tile_height) {
+            return Err(format!(
+                "tileset {} has {}x{} tiles, tilesets with a tile size other than the map's ({}x{}) are not supported",
+                set.name, set.tile_width, set.tile_height, raw.tile_width, raw.tile_height
+            ));
+        }
+        let images = tileset_images(set)?;
+        let image = |id: u32| {
+            images
+                .iter()
+                .find(|(image_id, _)| *image_id == id)
+                .map(|(_, sprite)| sprite.clone())
+                .ok_or(format!("tileset {} has no tile {}", set.name, id))
+        };
+        for (id, sprite) in images.iter() {
+            let data = set.tiles.iter().find(|tile| tile.id == *id);
+            let (frames, frame_duration) = match data {
+                Some(data)
+                    if data
+                        .animation
+                        .iter()
+                        .any(|(_, duration)| *duration != data.animation[0].1) =>
+                {
+                    return Err(format!(
+                        "tile {}/{} has frames of different durations, animations with per frame durations are not supported",
+                        set.name, id
+                    ));
+                }
+                Some(data) if !data.animation.is_empty() => (
+                    data.animation.iter().map(|(frame, _)| image(*frame)).collect::<Result<
+                        Vec<Sprite>,
+                        String,
+                    >>(
+                    )?,
+                    (data.animation[0].1 as u64).div_ceil(tick).max(1),
+                ),
+                _ => (vec![sprite.clone()], 1),
+            };
+            let solid = data
+                .and_then(|data| data.properties.get("solid"))
+                .is_some_and(|solid| solid == "true");
+            let index = tileset.add(Tile {
+                name: format!("{}/{}", set.name, id),
+                flags: TileFlags {
+                    solid,
+                    animated: frames.len() > 1,
+                },
+                frames,
+                frame_duration,
+            })?;
+            tile_indices.insert(set.first_gid + id, index);
+        }
+    }
+    let tile_index = |gid: u32| -> Result<Option<u16>, String> {
+        if gid & FLIP_FLAGS != 0 {
+            return Err("flipped or rotated tiles are not supported".to_string());
+        }
+        match gid {
+            0 => Ok(None),
+            gid => tile_indices
+                .get(&gid)
+                .map(|index| Some(*index))
+                .ok_or(format!("unknown tile id {}", gid)),
+        }
+    };
+    let mut tilemap = Tilemap::new(tileset, raw.width, raw.height);
+    let mut object_layers: Vec<ObjectLayer> = vec![];
+    for layer in raw.layers {
+        match layer {
+            RawLayer::Tiles { name, gids } => {
+                if gids.len() != raw.width as usize * raw.height as usize {
+                    return Err(format!("layer {} does not match the map size", name));
+                }
+                let index = tilemap.add_layer(&name);
+                for (position, gid) in gids.into_iter().enumerate() {
+                    let (x, y) = (position % raw.width as usize, position / raw.width as usize);
+                    tilemap.set(index, x as i32, y as i32, tile_index(gid)?);
+                }
+            }
+            RawLayer::Objects(mut layer, gids) => {
+                for (object, gid) in layer.objects.iter_mut().zip(gids) {
+                    object.tile = gid.map(tile_index).transpose()?.flatten();
+                }
+                object_layers.push(layer);
+            }
+        }
+    }
+    Ok(TiledMap {
+        tilemap,
+        object_layers,
+    })
+}
@@ -0,0 +1,412 @@
+//tilemap.rs
+use crate::sprite::{self, x_pos, y_pos, Pixel, Sprite, DEFAULT_FRAME_DURATION};
+use crate::stack::{LayerID, Layerstack, SpriteVector};
+use std::collections::HashMap;
+use std::fs;
+
+///gameplay flags of a tile
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TileFlags {
+    ///blocks movement, see `Tilemap::is_solid`
+    pub solid: bool,
+    ///cycles through its frames while rendering
+    pub animated: bool,
+}
+
+///a single tile of a Tileset
+#[derive(Clone)]
+pub struct Tile {
+    ///name the tile is referred to by in map files
+    pub name: String,
+    ///animation frames, a still tile has one
+    pub frames: Vec<Sprite>,
+    ///ticks each frame is shown for
+    pub frame_duration: u64,
+    pub flags: TileFlags,
+}
+
+///tiles of equal size referenced by index from a Tilemap
+#[derive(Clone)]
+pub struct Tileset {
+    ///width of every tile in pixels
+    pub tile_width: u16,
+    ///height of every tile in pixels
+    pub tile_height: u16,
+    tiles: Vec<Tile>,
+}
+
+///a named grid of tile indices, `None` cells are empty
+#[derive(Clone, Debug, PartialEq)]
+pub struct TileLayer {
+    pub name: String,
+    ///tile indices in row major order
+    tiles: Vec<Option<u16>>,
+}
+
+///grid based map made of one or more tile layers drawn from a Tileset
+#[derive(Clone)]
+pub struct Tilemap {
+    pub tileset: Tileset,
+    ///width of the map in tiles
+    width: u16,
+    ///height of the map in tiles
+    height: u16,
+    layers: Vec<TileLayer>,
+}
+
+impl Tile {
+    ///returns the frame to draw at `tick`
+    pub fn frame(&self, tick: u64) -> &Sprite {
+        if !self.flags.animated || self.frames.len() < 2 {
+            return &self.frames[0];
+        }
+        let index = tick / self.frame_duration.max(1) % self.frames.len() as u64;
+        &self.frames[index as usize]
+    }
+}
+
+impl Tileset {
+    ///returns a new empty Tileset
+    ///# Parameters
+    ///- `tile_width` : width of every tile in pixels
+    ///- `tile_height` : height of every tile in pixels
+    pub fn new(tile_width: u16, tile_height: u16) -> Self {
+        Tileset {
+            tile_width,
+            tile_height,
+            tiles: vec![],
+        }
+    }
+    ///adds a tile and returns its index
+    ///# Example
+    ///```
+    ///let wall : u16 = tileset.add(Tile{name:"wall".to_string(),frames:vec![brick],frame_duration:8,flags:TileFlags{solid:true,animated:false}})?;
+    ///```
+    pub fn add(&mut self, tile: Tile) -> Result<u16, String> {
+        if tile.frames.is_empty() {
+            return Err(format!("tile {} has no frames", tile.name));
+        }
+        for frame in tile.frames.iter() {
+            if (frame.width, frame.height) != (self.tile_width, self.tile_height) {
+                return Err(format!(
+                    "tile {} is {}x{}, tileset tiles are {}x{}",
+                    tile.name, frame.width, frame.height, self.tile_width, self.tile_height
+                ));
+            }
+        }
+        if self.index_of(&tile.name).is_some() {
+            return Err(format!("tile {} already exists", tile.name));
+        }
+        self.tiles.push(tile);
+        Ok(self.tiles.len() as u16 - 1)
+    }
+    ///builds a tileset from named sprites, usually from `sprite::load_sprite_file`
+    ///sprites named `name:N` become frame N of the animated tile `name`,
+    ///a sprite tagged `solid` makes its tile solid, all sprites must be the same size
+    ///# Example
+    ///```
+    ///sprite wall
+    ///tag solid
+    ///...
+    ///sprite water:0
+    ///...
+    ///sprite water:1
+    ///...
+    ///```
+    ///returns a tileset with the solid tile `wall` and the two frame animated tile `water`
+    pub fn from_sprites(sprites: Vec<(String, Sprite)>) -> Result<Self, String> {
+        let (tile_width, tile_height) = match sprites.first() {
+            Some((_, sprite)) => (sprite.width, sprite.height),
+            None => return Err("tileset has no tiles".to_string()),
+        };
+        let mut frames: Vec<(String, Vec<(u16, Sprite)>)> = vec![];
+        for (name, sprite) in sprites {
+            let (tile_name, frame) = match name.split_once(':') {
+                Some((tile_name, frame)) => {
+                    let frame = frame
+                        .parse::<u16>()
+                        .map_err(|_| format!("invalid frame number in tile {}", name))?;
+                    (tile_name.to_string(), frame)
+                }
+                None => (name, 0),
+            };
+            match frames.iter_mut().find(|(existing, _)| *existing == tile_name) {
+                Some((_, tile_frames)) => tile_frames.push((frame, sprite)),
+                None => frames.push((tile_name, vec![(frame, sprite)])),
+            }
+        }
+        let mut tileset = Tileset::new(tile_width, tile_height);
+        for (name, mut tile_frames) in frames {
+            tile_frames.sort_by_key(|(frame, _)| *frame);
+            let solid = tile_frames.iter().any(|(_, sprite)| {
+                sprite
+                    .tag
+                    .as_deref()
+                    .is_some_and(|tag| tag.split_whitespace().any(|word| word == "solid"))
+            });
+            let frames: Vec<Sprite> = tile_frames.into_iter().map(|(_, sprite)| sprite).collect();
+            tileset.add(Tile {
+                name,
+                flags: TileFlags {
+                    solid,
+                    animated: frames.len() > 1,
+                },
+                frames,
+                frame_duration: DEFAULT_FRAME_DURATION,
+            })?;
+        }
+        Ok(tileset)
+    }
+    ///parses a tileset from text in the sprite file format, see `from_sprites`
+    pub fn from_sprite_file(text: &str) -> Result<Self, String> {
+        Tileset::from_sprites(sprite::parse_sprite_file(text)?)
+    }
+    ///loads a tileset from a sprite file, see `from_sprites`
+    pub fn load(path: &str) -> Result<Self, String> {
+        println!("loading tileset {}", path);
+        Tileset::from_sprites(sprite::load_sprite_file(path)?)
+    }
+    ///returns the tile at `index`
+    pub fn get(&self, index: u16) -> Option<&Tile> {
+        self.tiles.get(index as usize)
+    }
+    ///returns the tile at `index` for changing its flags or frames
+    pub fn get_mut(&mut self, index: u16) -> Option<&mut Tile> {
+        self.tiles.get_mut(index as usize)
+    }
+    ///returns the index of the tile called `name`
+    pub fn index_of(&self, name: &str) -> Option<u16> {
+        self.tiles.iter().position(|tile| tile.name == name).map(|index| index as u16)
+    }
+    ///returns the number of tiles
+    pub fn len(&self) -> usize {
+        self.tiles.len()
+    }
+    ///returns true if the tileset has no tiles
+    pub fn is_empty(&self) -> bool {
+        self.tiles.is_empty()
+    }
+}
+
+impl Tilemap {
+    ///returns a new Tilemap without tile layers
+    ///# Parameters
+    ///- `tileset` : tiles the map refers to by index
+    ///- `width` : width of the map in tiles
+    ///- `height` : height of the map in tiles
+    pub fn new(tileset: Tileset, width: u16, height: u16) -> Self {
+        println!("initializing new Tilemap ({}x{})", width, height);
+        Tilemap {
+            tileset,
+            width,
+            height,
+            layers: vec![],
+        }
+    }
+    ///returns the width of the map in tiles
+    pub fn width(&self) -> u16 {
+        self.width
+    }
+    ///returns the height of the map in tiles
+    pub fn height(&self) -> u16 {
+        self.height
+    }
+    ///adds an empty tile layer on top of the others, returns its index
+    pub fn add_layer(&mut self, name: &str) -> usize {
+        self.layers.push(TileLayer {
+            name: name.to_string(),
+            tiles: vec![None; self.width as usize * self.height as usize],
+        });
+        self.layers.len() - 1
+    }
+    ///returns the tile layers, bottom first
+    pub fn layers(&self) -> &[TileLayer] {
+        &self.layers
+    }
+    ///returns the index of the tile layer called `name`
+    pub fn layer_index(&self, name: &str) -> Option<usize> {
+        self.layers.iter().position(|layer| layer.name == name)
+    }
+    ///returns the position of (`x`,`y`) in a tile layer, `None` outside the map
+    fn index(&self, x: i32, y: i32) -> Option<usize> {
+        if x < 0 || y < 0 || x >= self.width as i32 || y >= self.height as i32 {
+            return None;
+        }
+        Some(y as usize * self.width as usize + x as usize)
+    }
+    ///returns the tile index at (`x`,`y`) of a tile layer, `None` when empty or outside the map
+    pub fn get(&self, layer: usize, x: i32, y: i32) -> Option<u16> {
+        let index = self.index(x, y)?;
+        self.layers.get(layer)?.tiles[index]
+    }
+    ///sets the tile index at (`x`,`y`) of a tile layer, `None` empties the cell
+    ///positions outside the map are ignored
+    ///# Example
+    ///```
+    ///map.set(0,3,4,tileset.index_of("door"));
+    ///```
+    pub fn set(&mut self, layer: usize, x: i32, y: i32, tile: Option<u16>) {
+        if let (Some(index), Some(tile_layer)) = (self.index(x, y), self.layers.get_mut(layer)) {
+            tile_layer.tiles[index] = tile;
+        }
+    }
+    ///returns true if any tile layer holds a solid tile at (`x`,`y`)
+    ///positions outside the map are solid so nothing walks off the edge
+    pub fn is_solid(&self, x: i32, y: i32) -> bool {
+        if self.index(x, y).is_none() {
+            return true;
+        }
+        (0..self.layers.len()).any(|layer| {
+            self.get(layer, x, y)
+                .and_then(|tile| self.tileset.get(tile))
+                .is_some_and(|tile| tile.flags.solid)
+        })
+    }
+    ///returns the tile position holding the worldspace pixel (`x`,`y`)
+    pub fn tile_at(&self, x: x_pos, y: y_pos) -> (i32, i32) {
+        (
+            (x as i32).div_euclid(self.tileset.tile_width.max(1) as i32),
+            (y as i32).div_euclid(self.tileset.tile_height.max(1) as i32),
+        )
+    }
+    ///parses a map from a text grid, every character of a layer row is one tile
+    ///`legend` lines map a character to a tile name of the tileset (`none` for empty cells),
+    ///`.` is empty unless the legend says otherwise, `layer` starts a new tile layer
+    ///and lines starting with `//` are comments, every layer must be the same size
+    ///# Example
+    ///```
+    ///legend # wall
+    ///legend ~ water
+    ///layer ground
+    ///#####
+    ///#~~.#
+    ///#####
+    ///```
+    ///returns a 5x3 map with one tile layer called `ground`
+    pub fn parse(text: &str, tileset: Tileset) -> Result<Self, String> {
+        let mut legend: HashMap<char, Option<u16>> = HashMap::from([('.', None)]);
+        let mut layers: Vec<(String, Vec<Vec<char>>)> = vec![];
+        for (number, line) in text.lines().enumerate() {
+            let trimmed = line.trim();
+            if trimmed.is_empty() || trimmed.starts_with("//") {
+                continue;
+            }
+            let (keyword, value) = trimmed.split_once(' ').unwrap_or((trimmed, ""));
+            let value = value.trim();
+            match keyword {
+                "legend" if !value.is_empty() => {
+                    let invalid = || format!("line {} : invalid legend : {}", number + 1, value);
+                    let (character, name) = value.split_once(' ').ok_or_else(invalid)?;
+                    let character = character.chars().next().ok_or_else(invalid)?;
+                    let tile = match name.trim() {
+                        "none" => None,
+                        name => Some(tileset.index_of(name).ok_or(format!(
+                            "line {} : tileset has no tile {}",
+                            number + 1,
+                            name
+                        ))?),
+                    };
+                    legend.insert(character, tile);
+                }
+                "layer" if !value.is_empty() => layers.push((value.to_string(), vec![])),
+                _ => match layers.last_mut() {
+                    Some((_, rows)) => rows.push(trimmed.chars().collect()),
+                    None => {
+                        return Err(format!("line {} : tile row outside of a layer", number + 1))
+                    }
+                },
+            }
+        }
+        let (width, height) = match layers.first() {
+            Some((_, rows)) => (
+                rows.iter().map(|row| row.len()).max().unwrap_or(0),
+                rows.len(),
+            ),
+            None => return Err("map has no layers".to_string()),
+        };
+        let mut map = Tilemap::new(tileset, width as u16, height as u16);
+        for (name, rows) in layers {
+            if rows.len() != height || rows.iter().any(|row| row.len() != width) {
+                return Err(format!("layer {} is not {}x{}", name, width, height));
+            }
+            let layer = map.add_layer(&name);
+            for (y, row) in rows.iter().enumerate() {
+                for (x, character) in row.iter().enumerate() {
+                    let tile = *legend.get(character).ok_or(format!(
+                        "layer {} : {} is not in the legend",
+                        name, character
+                    ))?;
+                    map.set(layer, x as i32, y as i32, tile);
+                }
+            }
+        }
+        Ok(map)
+    }
+    ///loads a map saved in the text grid format, see `parse`
+    pub fn load(path: &str, tileset: Tileset) -> Result<Self, String> {
+        println!("loading tilemap {}", path);
+        let text =
+            fs::read_to_string(path).map_err(|why| format!("could not read {} : {}", path, why))?;
+        Tilemap::parse(&text, tileset)
+    }
+}
+
+impl Layerstack {
+    ///draws a tile layer of a map onto the target layer
+    ///only tiles overlapping the screen are drawn, so maps can be much larger than the screen
+    ///# Parameters
+    ///- `map` : map to draw
+    ///- `tile_layer` : index of the tile layer to draw
+    ///- `camera` : worldspace position of the map drawn at the top left corner of the screen
+    ///- `tick` : current frame, selects the frame of animated tiles
+    ///- `layer_id` : target layer
+    ///# Example
+    ///```
+    ///for (index,_) in map.layers().iter().enumerate() {
+    ///    layer_draw_tilemap(&map,index,(scroll_x,0),frame,index as LayerID);
+    ///}
+    ///```
+    ///draws every tile layer of the map onto its own layer, scrolled horizontally
+    pub fn draw_tilemap(
+        &mut self,
+        map: &Tilemap,
+        tile_layer: usize,
+        camera: (x_pos, y_pos),
+        tick: u64,
+        layer_id: LayerID,
+    ) {
+        println!("drawing tilemap to layer (id : {})", layer_id);
+        let (screen_width, screen_height) = self.size();
+        let (tile_width, tile_height) = (
+            map.tileset.tile_width.max(1) as i32,
+            map.tileset.tile_height.max(1) as i32,
+        );
+        let (camera_x, camera_y) = (camera.0 as i32, camera.1 as i32);
+        //range of tiles overlapping the screen
+        let first_x = camera_x.div_euclid(tile_width).max(0);
+        let first_y = camera_y.div_euclid(tile_height).max(0);
+        let last_x =
+            (camera_x + screen_width as i32 - 1).div_euclid(tile_width).min(map.width as i32 - 1);
+        let last_y = (camera_y + screen_height as i32 - 1)
+            .div_euclid(tile_height)
+            .min(map.height as i32 - 1);
+        let mut pixels: SpriteVector = vec![];
+        for y in first_y..=last_y {
+            for x in first_x..=last_x {
+                let tile = match map.get(tile_layer, x, y).and_then(|tile| map.tileset.get(tile)) {
+                    Some(tile) => tile,
+                    None => continue,
+                };
+                let (origin_x, origin_y) = (x * tile_width - camera_x, y * tile_height - camera_y);
+                for pixel in tile.frame(tick).pixels.iter().filter(|pixel| pixel.isrendered) {
+                    pixels.push(Pixel {
+                        x: (origin_x + pixel.x as i32) as x_pos,
+                        y: (origin_y + pixel.y as i32) as y_pos,
+                        ..pixel.clone()
+                    });
+                }
+            }
+        }
+        self.write_pixels(pixels, layer_id);
+    }
+}
@@ -0,0 +1,80 @@
+//assets.rs
+//asset manager tests, assets are loaded from tests/assets
+use crossterm::style::Color;
+use rs_cli_renderer::game::assets::Assets;
+use rs_cli_renderer::stack::Renderer;
+use std::fs;
+use std::rc::Rc;
+use std::time::{Duration, SystemTime};
+
+fn assets() -> Assets {
+    Assets::new(&format!("{}/tests/assets", env!("CARGO_MANIFEST_DIR")))
+}
+
+#[test]
+fn assets_are_loaded_once_and_shared() {
+    let mut assets = assets();
+    let bat = assets.sprite("enemies/bat").unwrap();
+    assert_eq!((bat.width, bat.height), (3, 2));
+    assert!(Rc::ptr_eq(&bat, &assets.sprite("enemies/bat").unwrap()));
+    //loading the sprite file registered its other sprites too
+    assert_eq!(assets.sprite("enemies/rat").unwrap().width, 2);
+    assert_eq!(assets.sprite("block").unwrap().width, 4);
+
+    let walk = assets.animation("walk").unwrap();
+    assert_eq!(walk.frames.len(), 2);
+    assert!(Rc::ptr_eq(&walk, &assets.animation("walk").unwrap()));
+
+    let level = assets.tilemap("level", "tiles").unwrap();
+    assert!(level.is_solid(0, 0) && !level.is_solid(1, 1));
+    assert!(assets.font("builtin").is_ok());
+
+    assets.clear();
+    assert!(!Rc::ptr_eq(&bat, &assets.sprite("enemies/bat").unwrap()));
+}
+
+#[test]
+fn missing_assets_are_reported() {
+    let mut assets = assets();
+    let error = assets.sprite("enemies/dragon").err().unwrap();
+    assert!(
+        error.contains("sprite enemies/dragon not found"),
+        "{}",
+        error
+    );
+    assert!(assets.animation("run").is_err());
+}
+
+#[test]
+fn changed_assets_are_reloaded_and_errors_reported() {
+    let root = std::env::temp_dir().join(format!("rs_cli_renderer_assets_{}", std::process::id()));
+    fs::create_dir_all(&root).unwrap();
+    let path = root.join("dot.sprites");
+    let write = |text: &str, age: u64| {
+        fs::write(&path, text).unwrap();
+        let modified = SystemTime::now() + Duration::from_secs(age);
+        fs::File::options().write(true).open(&path).unwrap().set_modified(modified).unwrap();
+    };
+    write("color # Red\nsprite dot\n#", 0);
+
+    let mut assets = Assets::new(&root.to_string_lossy());
+    assets.reload_interval = Duration::ZERO;
+    let mut renderer = Renderer::headless(20, 2, Color::Black);
+    assert_eq!(assets.sprite("dot").unwrap().pixels[0].color, Color::Red);
+
+    write("color # Red\nsprite dot\n#?", 1);
+    assets.hot_reload(&mut renderer);
+    assert_eq!(renderer.overlay_messages()[0].0, "dot.sprites");
+    //the last good copy stays in use
+    assert_eq!(assets.sprite("dot").unwrap().width, 1);
+    renderer.start_recording();
+    renderer.render_update();
+    let cast = renderer.stop_recording().unwrap().to_asciicast();
+    assert!(cast.contains('d') && cast.contains('o') && cast.contains('t'));
+
+    write("color # Blue\nsprite dot\n#", 2);
+    assets.hot_reload(&mut renderer);
+    assert!(renderer.overlay_messages().is_empty());
+    assert_eq!(assets.sprite("dot").unwrap().pixels[0].color, Color::Blue);
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,82 @@
+//collision.rs
+//collision tests between placed sprites
+use rs_cli_renderer::collision::{
+    bounding_box_overlap, pixel_overlap, pixels_collide, Placed, Rect,
+};
+use rs_cli_renderer::sprite::{parse_sprite_file, Sprite};
+
+fn sprites() -> (Sprite, Sprite) {
+    let mut sprites = parse_sprite_file(
+        "color # Red
+
+        sprite ring
+        ###
+        #.#
+        ###
+
+        sprite dot
+        #",
+    )
+    .unwrap();
+    let dot = sprites.pop().unwrap().1;
+    (sprites.pop().unwrap().1, dot)
+}
+
+#[test]
+fn bounding_boxes_overlap() {
+    assert_eq!(
+        Rect::new(0, 0, 4, 4).intersection(&Rect::new(2, 3, 4, 4)),
+        Some(Rect::new(2, 3, 2, 1))
+    );
+    //touching edges and empty rectangles do not overlap
+    assert_eq!(
+        Rect::new(0, 0, 4, 4).intersection(&Rect::new(4, 0, 4, 4)),
+        None
+    );
+    assert_eq!(
+        Rect::new(0, 0, 4, 4).intersection(&Rect::new(1, 1, 0, 2)),
+        None
+    );
+    assert_eq!(
+        Rect::new(-3, -3, 4, 4).intersection(&Rect::new(0, 0, 2, 2)),
+        Some(Rect::new(0, 0, 1, 1))
+    );
+
+    let (ring, dot) = sprites();
+    assert_eq!(
+        bounding_box_overlap(Placed::new(5, 5, &ring), Placed::new(6, 6, &dot)),
+        Some(Rect::new(6, 6, 1, 1))
+    );
+    assert_eq!(
+        bounding_box_overlap(Placed::new(5, 5, &ring), Placed::new(8, 5, &dot)),
+        None
+    );
+}
+
+#[test]
+fn pixels_collide_only_where_rendered() {
+    let (ring, dot) = sprites();
+    //the dot sits in the transparent hole of the ring
+    assert!(!pixels_collide(
+        Placed::new(5, 5, &ring),
+        Placed::new(6, 6, &dot)
+    ));
+    assert_eq!(
+        pixel_overlap(Placed::new(5, 5, &ring), Placed::new(6, 6, &dot)),
+        None
+    );
+
+    let contact = pixel_overlap(Placed::new(5, 5, &ring), Placed::new(7, 6, &dot)).unwrap();
+    assert_eq!(contact.points, vec![(7, 6)]);
+    assert_eq!(contact.bounds, Rect::new(7, 6, 1, 1));
+
+    //two rings offset by one column share the two middle columns, each hole
+    //leaves the middle row of one column empty
+    let contact = pixel_overlap(Placed::new(0, 0, &ring), Placed::new(1, 0, &ring)).unwrap();
+    assert_eq!(contact.points, vec![(1, 0), (2, 0), (1, 2), (2, 2)]);
+    assert_eq!(contact.bounds, Rect::new(1, 0, 2, 3));
+    assert!(pixels_collide(
+        Placed::new(0, 0, &ring),
+        Placed::new(1, 0, &ring)
+    ));
+}
@@ -0,0 +1,189 @@
+//color.rs
+//quantization, blending and dithering tests
+use crossterm::style::Color;
+use rs_cli_renderer::color::{blend, dither, quantize, BlendMode, ColorDepth, Dither};
+use rs_cli_renderer::stack::Renderer;
+
+fn rgb(r: u8, g: u8, b: u8) -> Color {
+    Color::Rgb { r, g, b }
+}
+
+///returns a row major grid of 'W' and 'B' for white and black colors
+fn pattern(colors: &[Color], width: usize) -> Vec<String> {
+    colors
+        .chunks(width)
+        .map(|row| {
+            row.iter()
+                .map(|color| match color {
+                    Color::White => 'W',
+                    Color::Black => 'B',
+                    _ => '?',
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[test]
+fn blend_modes_mix_by_alpha() {
+    let (dst, src) = (rgb(100, 50, 200), rgb(200, 100, 50));
+    for (mode, half, full) in [
+        (BlendMode::Normal, rgb(150, 75, 125), src),
+        (BlendMode::Additive, rgb(178, 100, 225), rgb(255, 150, 250)),
+        (BlendMode::Multiply, rgb(89, 35, 120), rgb(78, 20, 39)),
+        (BlendMode::Screen, rgb(161, 90, 205), rgb(222, 130, 211)),
+    ] {
+        assert_eq!(blend(dst, src, 0.0, mode), dst, "{:?}", mode);
+        assert_eq!(blend(dst, src, 0.5, mode), half, "{:?}", mode);
+        assert_eq!(blend(dst, src, 1.0, mode), full, "{:?}", mode);
+    }
+    //opaque normal blending keeps named colors, other modes work in RGB
+    assert_eq!(blend(dst, Color::Red, 1.0, BlendMode::Normal), Color::Red);
+    assert_eq!(
+        blend(Color::White, Color::Red, 1.0, BlendMode::Multiply),
+        rgb(255, 0, 0)
+    );
+}
+
+#[test]
+fn colors_are_quantized_to_the_nearest_palette_color() {
+    assert_eq!(quantize(rgb(1, 2, 3), ColorDepth::TrueColor), rgb(1, 2, 3));
+
+    //6x6x6 cube
+    assert_eq!(
+        quantize(rgb(255, 0, 0), ColorDepth::Ansi256),
+        Color::AnsiValue(196)
+    );
+    assert_eq!(
+        quantize(rgb(0, 0, 0), ColorDepth::Ansi256),
+        Color::AnsiValue(16)
+    );
+    assert_eq!(
+        quantize(rgb(95, 135, 175), ColorDepth::Ansi256),
+        Color::AnsiValue(67)
+    );
+    //grayscale ramp is closer than the cube for mid grays
+    assert_eq!(
+        quantize(rgb(128, 128, 128), ColorDepth::Ansi256),
+        Color::AnsiValue(244)
+    );
+    //named colors are already part of the palette
+    assert_eq!(
+        quantize(Color::DarkCyan, ColorDepth::Ansi256),
+        Color::DarkCyan
+    );
+
+    assert_eq!(quantize(rgb(250, 10, 10), ColorDepth::Ansi16), Color::Red);
+    assert_eq!(
+        quantize(rgb(0, 100, 0), ColorDepth::Ansi16),
+        Color::DarkGreen
+    );
+    assert_eq!(
+        quantize(rgb(180, 180, 190), ColorDepth::Ansi16),
+        Color::Grey
+    );
+    assert_eq!(
+        quantize(Color::AnsiValue(21), ColorDepth::Ansi16),
+        Color::Blue
+    );
+}
+
+#[test]
+fn monochrome_splits_colors_at_half_luma() {
+    assert_eq!(
+        quantize(rgb(129, 129, 129), ColorDepth::Monochrome),
+        Color::White
+    );
+    assert_eq!(
+        quantize(rgb(127, 127, 127), ColorDepth::Monochrome),
+        Color::Black
+    );
+    //green weighs far more than blue
+    assert_eq!(
+        quantize(rgb(0, 255, 0), ColorDepth::Monochrome),
+        Color::White
+    );
+    assert_eq!(
+        quantize(rgb(0, 0, 255), ColorDepth::Monochrome),
+        Color::Black
+    );
+    assert_eq!(
+        quantize(Color::Yellow, ColorDepth::Monochrome),
+        Color::White
+    );
+    assert_eq!(
+        quantize(Color::DarkBlue, ColorDepth::Monochrome),
+        Color::Black
+    );
+}
+
+#[test]
+fn ordered_dithering_follows_the_bayer_matrix() {
+    //a light gray sits between black and white, the bayer thresholds split it into a checker
+    let mut colors = vec![rgb(140, 140, 140); 16];
+    dither(
+        &mut colors,
+        4,
+        ColorDepth::Monochrome,
+        Dither::Ordered,
+        &[true; 16],
+    );
+    assert_eq!(pattern(&colors, 4), vec!["BWBW", "WBWB", "BWBW", "WBWB"]);
+    //without dithering the same gray is plain white
+    let mut plain = vec![rgb(140, 140, 140); 16];
+    dither(
+        &mut plain,
+        4,
+        ColorDepth::Monochrome,
+        Dither::None,
+        &[true; 16],
+    );
+    assert_eq!(pattern(&plain, 4), vec!["WWWW"; 4]);
+}
+
+#[test]
+fn floyd_steinberg_diffuses_errors_inside_the_mask() {
+    let mut colors = vec![rgb(140, 140, 140); 8];
+    dither(
+        &mut colors,
+        8,
+        ColorDepth::Monochrome,
+        Dither::FloydSteinberg,
+        &[true; 8],
+    );
+    assert_eq!(pattern(&colors, 8), vec!["WBWBWBWB"]);
+
+    //masked out colors are left alone and never receive error
+    let mut colors = vec![rgb(140, 140, 140); 4];
+    let mask = [true, false, true, true];
+    dither(
+        &mut colors,
+        4,
+        ColorDepth::Monochrome,
+        Dither::FloydSteinberg,
+        &mask,
+    );
+    assert_eq!(colors[1], rgb(140, 140, 140));
+    assert_eq!(
+        (colors[0], colors[2], colors[3]),
+        (Color::White, Color::White, Color::Black)
+    );
+}
+
+#[test]
+fn layers_can_be_dithered_on_their_own() {
+    let mut renderer = Renderer::headless(4, 4, Color::Black);
+    renderer.set_color_depth(ColorDepth::Monochrome, Dither::None);
+    let layer = renderer.layerstack.add(0, 0);
+    let draw = |renderer: &mut Renderer| {
+        renderer.layerstack.draw_rect(0, 0, 4, 4, rgb(140, 140, 140), true, layer);
+        renderer.render_update();
+        let colors: Vec<Color> =
+            renderer.last_frame().buffer.iter().map(|pixel| pixel.color).collect();
+        pattern(&colors, 4)
+    };
+    renderer.layerstack.set_dither(layer, Some(Dither::Ordered));
+    assert_eq!(draw(&mut renderer), vec!["BWBW", "WBWB", "BWBW", "WBWB"]);
+    renderer.layerstack.set_dither(layer, None);
+    assert_eq!(draw(&mut renderer), vec!["WWWW"; 4]);
+}
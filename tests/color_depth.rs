@@ -0,0 +1,35 @@
+//color_depth.rs
+//color depth detection tests, kept in their own test binary because they change the environment
+//every other test reads when it creates a Renderer
+use rs_cli_renderer::color::ColorDepth;
+use std::env;
+
+const VARIABLES: [&str; 3] = ["NO_COLOR", "TERM", "COLORTERM"];
+
+#[test]
+fn color_depth_is_detected_from_the_environment() {
+    let saved: Vec<Option<String>> = VARIABLES.iter().map(|name| env::var(name).ok()).collect();
+    env::set_var("COLORTERM", "truecolor");
+    env::set_var("TERM", "xterm-256color");
+    env::set_var("NO_COLOR", "1");
+    assert_eq!(ColorDepth::detect(), ColorDepth::Monochrome);
+
+    env::remove_var("NO_COLOR");
+    assert_eq!(ColorDepth::detect(), ColorDepth::TrueColor);
+    env::set_var("COLORTERM", "24bit");
+    assert_eq!(ColorDepth::detect(), ColorDepth::TrueColor);
+
+    env::remove_var("COLORTERM");
+    assert_eq!(ColorDepth::detect(), ColorDepth::Ansi256);
+
+    env::set_var("TERM", "dumb");
+    env::set_var("COLORTERM", "truecolor");
+    assert_eq!(ColorDepth::detect(), ColorDepth::Monochrome);
+
+    for (name, value) in VARIABLES.iter().zip(saved) {
+        match value {
+            Some(value) => env::set_var(name, value),
+            None => env::remove_var(name),
+        }
+    }
+}
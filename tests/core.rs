@@ -0,0 +1,298 @@
+//core.rs
+//entity component system tests
+use crossterm::style::Color;
+use rs_cli_renderer::game::core::{
+    movement_system, render_system, Context, RenderLayer, Scene, SceneCommand, SceneStack,
+    SpriteRef, Transform, Transition, Velocity, World, TRANSITION_LAYER,
+};
+use rs_cli_renderer::sprite::{parse_sprite_file, x_pos, Animation};
+use rs_cli_renderer::stack::{LayerID, Layerstack, Renderer};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+
+#[derive(Debug, PartialEq)]
+struct Health(u32);
+
+#[test]
+fn entities_own_typed_components() {
+    let mut world = World::new();
+    let player = world.spawn();
+    let enemy = world.spawn();
+    world.insert(player, Health(3));
+    world.insert(enemy, Health(1));
+    world.insert(enemy, Transform { x: 1.0, y: 2.0 });
+    assert_eq!(world.insert(player, Health(5)), Some(Health(3)));
+    assert_eq!(world.get::<Health>(player), Some(&Health(5)));
+    assert!(world.has::<Transform>(enemy) && !world.has::<Transform>(player));
+
+    for (_, health) in world.iter_mut::<Health>() {
+        health.0 -= 1;
+    }
+    let alive: Vec<_> = world
+        .iter::<Health>()
+        .filter(|(_, health)| health.0 > 0)
+        .map(|(entity, _)| entity)
+        .collect();
+    assert_eq!(alive, vec![player]);
+
+    assert!(world.despawn(enemy));
+    assert!(!world.despawn(enemy));
+    assert_eq!(world.entities(), vec![player]);
+    //the slot is reused but the stale handle does not reach the new entity
+    let spawned = world.spawn();
+    assert_ne!(spawned, enemy);
+    assert!(!world.is_alive(enemy) && world.is_alive(spawned));
+    assert_eq!(world.get::<Health>(spawned), None);
+    world.insert(enemy, Health(9));
+    assert_eq!(world.get::<Health>(spawned), None);
+    assert_eq!(world.remove::<Health>(player), Some(Health(4)));
+}
+
+#[test]
+fn systems_run_in_order_every_tick() {
+    let mut renderer = Renderer::headless(4, 4, Color::Black);
+    let mut world = World::new();
+    let log = Rc::new(RefCell::new(vec![]));
+    for name in ["input", "update"] {
+        let log = Rc::clone(&log);
+        world.add_system(name, move |_: &mut World, context: &mut Context| {
+            log.borrow_mut().push(format!("{}:{}", name, context.tick));
+        });
+    }
+    world.add_system("movement", movement_system);
+    let ball = world.spawn();
+    world.insert(ball, Transform::default());
+    world.insert(ball, Velocity { x: 10.0, y: -5.0 });
+    for _ in 0..2 {
+        world.run(Duration::from_millis(500), &mut renderer.layerstack);
+    }
+    assert_eq!(
+        *log.borrow(),
+        vec!["input:0", "update:0", "input:1", "update:1"]
+    );
+    assert_eq!(
+        world.get::<Transform>(ball),
+        Some(&Transform { x: 10.0, y: -5.0 })
+    );
+    assert!(world.remove_system("input"));
+    assert_eq!(world.system_names(), vec!["update", "movement"]);
+    assert_eq!(world.tick(), 2);
+}
+
+#[test]
+fn render_system_draws_sprites_and_animations() {
+    let sprites =
+        parse_sprite_file("color r Red\ncolor b Blue\n\nsprite red\nr\n\nsprite blue\nb").unwrap();
+    let frames: Vec<_> = sprites.iter().map(|(_, sprite)| sprite.clone()).collect();
+    let mut renderer = Renderer::headless(4, 2, Color::Black);
+    let layer = renderer.layerstack.add(0, 0);
+    let mut world = World::new();
+    world.add_system("render", render_system);
+    let still = world.spawn();
+    world.insert(still, Transform { x: 0.0, y: 0.0 });
+    world.insert(still, SpriteRef::Sprite(Rc::new(frames[1].clone())));
+    world.insert(still, RenderLayer(layer));
+    let blinking = world.spawn();
+    world.insert(blinking, Transform { x: 2.4, y: 1.0 });
+    world.insert(
+        blinking,
+        SpriteRef::Animation {
+            animation: Rc::new(Animation::new(frames, 1)),
+            start: 0,
+        },
+    );
+    world.insert(blinking, RenderLayer(layer));
+    //without a layer nothing is drawn
+    let hidden = world.spawn();
+    world.insert(hidden, Transform { x: 3.0, y: 0.0 });
+    world.insert(hidden, SpriteRef::Sprite(Rc::new(sprites[0].1.clone())));
+
+    let color = |renderer: &Renderer, x: u16, y: u16| {
+        renderer.last_frame().get(x, y).map(|pixel| pixel.color)
+    };
+    for expected in [Color::Red, Color::Blue] {
+        world.run(Duration::from_millis(16), &mut renderer.layerstack);
+        renderer.render_update();
+        assert_eq!(color(&renderer, 0, 0), Some(Color::Blue));
+        assert_eq!(color(&renderer, 2, 1), Some(expected));
+        assert_eq!(color(&renderer, 3, 0), Some(Color::Black));
+    }
+}
+
+///scene drawing one pixel of `color` at (`x`,0) on its own layer, logging its lifecycle
+struct TestScene {
+    name: &'static str,
+    layer: LayerID,
+    x: x_pos,
+    color: Color,
+    overlay: bool,
+    ///pops itself after this many updates
+    pop_after: Option<u32>,
+    updates: u32,
+    log: Rc<RefCell<Vec<String>>>,
+}
+
+impl TestScene {
+    fn new(
+        name: &'static str,
+        layer: LayerID,
+        color: Color,
+        log: &Rc<RefCell<Vec<String>>>,
+    ) -> Self {
+        TestScene {
+            name,
+            layer,
+            x: layer as x_pos,
+            color,
+            overlay: false,
+            pop_after: None,
+            updates: 0,
+            log: Rc::clone(log),
+        }
+    }
+    fn log(&self, event: &str) {
+        self.log.borrow_mut().push(format!("{} {}", self.name, event));
+    }
+}
+
+impl Scene for TestScene {
+    fn enter(&mut self, layerstack: &mut Layerstack) {
+        self.log("enter");
+        layerstack.add(self.layer, self.layer);
+    }
+    fn layers(&self) -> Vec<LayerID> {
+        vec![self.layer]
+    }
+    fn update(&mut self, _: &mut Context) -> Option<SceneCommand> {
+        self.updates += 1;
+        self.log("update");
+        (Some(self.updates) == self.pop_after).then_some(SceneCommand::Pop(Transition::Cut))
+    }
+    fn render(&mut self, context: &mut Context) {
+        context.layerstack.direct_write(self.x, 0, self.color, self.layer);
+    }
+    fn exit(&mut self, _: &mut Layerstack) {
+        self.log("exit");
+    }
+    fn pause(&mut self) {
+        self.log("pause");
+    }
+    fn resume(&mut self) {
+        self.log("resume");
+    }
+    fn is_overlay(&self) -> bool {
+        self.overlay
+    }
+}
+
+fn pixel(renderer: &Renderer, x: u16) -> Option<Color> {
+    renderer.last_frame().get(x, 0).map(|pixel| pixel.color)
+}
+
+#[test]
+fn overlay_scenes_draw_over_paused_scenes() {
+    let log = Rc::new(RefCell::new(vec![]));
+    let mut renderer = Renderer::headless(4, 1, Color::Black);
+    let mut scenes = SceneStack::new();
+    let tick = Duration::from_millis(16);
+    scenes.push(
+        Box::new(TestScene::new("game", 1, Color::Red, &log)),
+        Transition::Cut,
+    );
+    scenes.run(tick, &mut renderer.layerstack);
+    renderer.render_update();
+    assert_eq!(pixel(&renderer, 1), Some(Color::Red));
+
+    let mut pause = TestScene::new("pause", 2, Color::Blue, &log);
+    pause.overlay = true;
+    pause.pop_after = Some(2);
+    scenes.push(Box::new(pause), Transition::Cut);
+    scenes.run(tick, &mut renderer.layerstack);
+    renderer.render_update();
+    //the paused game is still drawn under the menu
+    assert_eq!(
+        (pixel(&renderer, 1), pixel(&renderer, 2)),
+        (Some(Color::Red), Some(Color::Blue))
+    );
+    assert_eq!(scenes.len(), 2);
+
+    //the menu pops itself on its second update
+    scenes.run(tick, &mut renderer.layerstack);
+    renderer.render_update();
+    assert_eq!(scenes.len(), 1);
+    assert!(!renderer.layerstack.contains(2));
+    assert_eq!(pixel(&renderer, 2), Some(Color::Black));
+    assert_eq!(
+        *log.borrow(),
+        vec![
+            "game enter",
+            "game update",
+            "game pause",
+            "pause enter",
+            "pause update",
+            "pause update",
+            "pause exit",
+            "game resume"
+        ]
+    );
+
+    //a full screen scene hides the scene beneath it
+    scenes.push(
+        Box::new(TestScene::new("title", 3, Color::Green, &log)),
+        Transition::Cut,
+    );
+    scenes.run(tick, &mut renderer.layerstack);
+    renderer.render_update();
+    assert_eq!(
+        (pixel(&renderer, 1), pixel(&renderer, 3)),
+        (Some(Color::Black), Some(Color::Green))
+    );
+}
+
+#[test]
+fn transitions_switch_scenes_halfway() {
+    let log = Rc::new(RefCell::new(vec![]));
+    let mut renderer = Renderer::headless(4, 1, Color::Black);
+    let mut scenes = SceneStack::new();
+    let tick = Duration::from_millis(16);
+    scenes.push(
+        Box::new(TestScene::new("title", 1, Color::Red, &log)),
+        Transition::Cut,
+    );
+    scenes.run(tick, &mut renderer.layerstack);
+    let fade = Transition::Fade {
+        color: Color::White,
+        ticks: 4,
+    };
+    scenes.replace(Box::new(TestScene::new("game", 2, Color::Blue, &log)), fade);
+    let mut frames = vec![];
+    for _ in 0..4 {
+        scenes.run(tick, &mut renderer.layerstack);
+        renderer.render_update();
+        frames.push((
+            pixel(&renderer, 1),
+            pixel(&renderer, 2),
+            scenes.is_transitioning(),
+        ));
+    }
+    //fully covered at the switch, the new scene shows once the transition ends
+    assert_eq!(frames[1], (Some(Color::White), Some(Color::White), true));
+    assert_eq!(frames[3], (Some(Color::Black), Some(Color::Blue), false));
+    assert!(!renderer.layerstack.contains(TRANSITION_LAYER) && !renderer.layerstack.contains(1));
+    //no scene is updated while the transition runs
+    assert_eq!(
+        *log.borrow(),
+        vec!["title enter", "title update", "title exit", "game enter"]
+    );
+
+    scenes.pop(Transition::Wipe {
+        color: Color::White,
+        ticks: 2,
+    });
+    scenes.run(tick, &mut renderer.layerstack);
+    renderer.render_update();
+    assert_eq!(pixel(&renderer, 3), Some(Color::White));
+    scenes.run(tick, &mut renderer.layerstack);
+    assert!(scenes.is_empty());
+}
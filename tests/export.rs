@@ -0,0 +1,98 @@
+//export.rs
+//screenshot export tests
+use crossterm::style::Color;
+use rs_cli_renderer::export::{frame_to_ansi, frame_to_html, frame_to_svg};
+use rs_cli_renderer::stack::Renderer;
+
+///returns a presented 3x2 frame, a red run of two pixels over a blue pixel on black
+fn frame() -> Renderer {
+    let mut renderer = Renderer::headless(3, 2, Color::Black);
+    let layer = renderer.layerstack.add(0, 0);
+    renderer.layerstack.direct_write(0, 0, Color::Red, layer);
+    renderer.layerstack.direct_write(1, 0, Color::Red, layer);
+    renderer.layerstack.direct_write(
+        2,
+        1,
+        Color::Rgb {
+            r: 0,
+            g: 16,
+            b: 255,
+        },
+        layer,
+    );
+    renderer.render_update();
+    renderer
+}
+
+#[test]
+fn frames_export_as_ansi_text() {
+    let renderer = frame();
+    assert_eq!(
+        frame_to_ansi(renderer.last_frame(), "██"),
+        "\x1b[38;5;9m████\x1b[39m\x1b[38;5;0m██\x1b[39m\n\
+         \x1b[38;5;0m████\x1b[39m\x1b[38;2;0;16;255m██\x1b[39m\n"
+    );
+}
+
+#[test]
+fn frames_export_as_html() {
+    let renderer = frame();
+    let html = frame_to_html(renderer.last_frame(), "<>");
+    let rows = "<span style=\"color:#ff0000\">&lt;&gt;&lt;&gt;</span><span style=\"color:#000000\">&lt;&gt;</span>\n\
+                <span style=\"color:#000000\">&lt;&gt;&lt;&gt;</span><span style=\"color:#0010ff\">&lt;&gt;</span>\n";
+    assert!(html.starts_with("<!DOCTYPE html>\n"));
+    assert!(html.contains(&format!("line-height:1\">\n{}</pre>", rows)));
+    assert!(html.ends_with("</html>\n"));
+}
+
+#[test]
+fn frames_export_as_svg() {
+    let renderer = frame();
+    assert_eq!(
+        frame_to_svg(renderer.last_frame(), "██"),
+        "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"30\" height=\"20\" viewBox=\"0 0 30 20\" shape-rendering=\"crispEdges\">\n\
+         <rect x=\"0\" y=\"0\" width=\"20\" height=\"10\" fill=\"#ff0000\"/>\n\
+         <rect x=\"20\" y=\"0\" width=\"10\" height=\"10\" fill=\"#000000\"/>\n\
+         <rect x=\"0\" y=\"10\" width=\"20\" height=\"10\" fill=\"#000000\"/>\n\
+         <rect x=\"20\" y=\"10\" width=\"10\" height=\"10\" fill=\"#0010ff\"/>\n\
+         </svg>\n"
+    );
+}
+
+#[test]
+fn zero_width_frames_export_empty_rows() {
+    let mut renderer = Renderer::headless(0, 2, Color::Black);
+    renderer.render_update();
+    let frame = renderer.last_frame();
+    assert_eq!(frame_to_ansi(frame, "██"), "\n\n");
+    assert!(frame_to_html(frame, "██").contains("line-height:1\">\n\n\n</pre>"));
+    assert!(!frame_to_svg(frame, "██").contains("<rect"));
+}
+
+#[test]
+fn text_cells_are_exported_over_their_pixels() {
+    let mut renderer = Renderer::headless(2, 1, Color::Black);
+    let text = renderer.layerstack.add_text(0, 0);
+    renderer.layerstack.write_text(1, 0, "H", Color::White, None, text);
+    renderer.layerstack.write_text(3, 0, "<", Color::Yellow, Some(Color::Blue), text);
+    renderer.render_update();
+    let frame = renderer.last_frame();
+    //each pixel spans two columns, so H covers the second half of the first pixel
+    assert_eq!(
+        frame_to_ansi(frame, "██"),
+        "\x1b[38;5;0m█\x1b[39m\x1b[48;5;0m\x1b[38;5;15mH\x1b[49m\x1b[39m\
+         \x1b[38;5;0m█\x1b[39m\x1b[48;5;12m\x1b[38;5;11m<\x1b[49m\x1b[39m\n"
+    );
+    assert!(frame_to_html(frame, "██").contains(
+        "<span style=\"color:#000000\">█</span><span style=\"color:#ffffff;background:#000000\">H</span>\
+         <span style=\"color:#000000\">█</span><span style=\"color:#ffff00;background:#0000ff\">&lt;</span>\n"
+    ));
+    let svg = frame_to_svg(frame, "██");
+    assert!(svg.contains(
+        "<text x=\"5\" y=\"8\" fill=\"#ffffff\" font-family=\"monospace\" font-size=\"10\">H</text>\n"
+    ));
+    assert!(svg.contains(
+        "<rect x=\"15\" y=\"0\" width=\"5\" height=\"10\" fill=\"#0000ff\"/>\n\
+         <text x=\"15\" y=\"8\" fill=\"#ffff00\" font-family=\"monospace\" font-size=\"10\">&lt;</text>\n"
+    ));
+}
@@ -0,0 +1,260 @@
+//image.rs
+//image decoder tests, images are built byte by byte
+use crossterm::style::Color;
+use rs_cli_renderer::image::image_to_sprite;
+use rs_cli_renderer::sprite::Sprite;
+
+const RED: Color = Color::Rgb { r: 255, g: 0, b: 0 };
+const GREEN: Color = Color::Rgb { r: 0, g: 255, b: 0 };
+const BLUE: Color = Color::Rgb { r: 0, g: 0, b: 255 };
+const WHITE: Color = Color::Rgb {
+    r: 255,
+    g: 255,
+    b: 255,
+};
+
+///returns (color, isrendered, alpha) of every pixel in row major order
+fn pixels(sprite: &Sprite) -> Vec<(Color, bool, u8)> {
+    sprite.pixels.iter().map(|pixel| (pixel.color, pixel.isrendered, pixel.alpha)).collect()
+}
+
+///returns an opaque, rendered pixel
+fn opaque(color: Color) -> (Color, bool, u8) {
+    (color, true, 255)
+}
+
+///returns a BMP file with a header of `header_size` bytes followed by `extra` and the pixel data
+fn bmp(
+    header_size: u32,
+    width: i32,
+    height: i32,
+    bits: u16,
+    compression: u32,
+    extra: &[u8],
+    data: &[u8],
+) -> Vec<u8> {
+    let data_offset = 14 + header_size + extra.len() as u32;
+    let mut bytes: Vec<u8> = b"BM".to_vec();
+    bytes.extend((data_offset + data.len() as u32).to_le_bytes());
+    bytes.extend([0; 4]);
+    bytes.extend(data_offset.to_le_bytes());
+    bytes.extend(header_size.to_le_bytes());
+    bytes.extend(width.to_le_bytes());
+    bytes.extend(height.to_le_bytes());
+    bytes.extend(1u16.to_le_bytes());
+    bytes.extend(bits.to_le_bytes());
+    bytes.extend(compression.to_le_bytes());
+    bytes.resize(14 + header_size as usize, 0);
+    bytes.extend(extra);
+    bytes.extend(data);
+    bytes
+}
+
+#[test]
+fn ppm_images_are_decoded() {
+    let plain = b"P3\n# two pixels\n2 1\n15\n15 0 0  0 0 15\n";
+    let sprite = image_to_sprite(plain, None).unwrap();
+    assert_eq!((sprite.width, sprite.height), (2, 1));
+    assert_eq!(pixels(&sprite), vec![opaque(RED), opaque(BLUE)]);
+
+    let mut raw = b"P6 2 1 255\n".to_vec();
+    raw.extend([0, 255, 0, 255, 255, 255]);
+    let sprite = image_to_sprite(&raw, Some(Color::White)).unwrap();
+    //named key colors match their RGB value
+    assert_eq!(pixels(&sprite), vec![opaque(GREEN), (WHITE, false, 255)]);
+
+    let mut wide = b"P6 1 1 65535\n".to_vec();
+    wide.extend([0xff, 0xff, 0x00, 0x00, 0x80, 0x00]);
+    let sprite = image_to_sprite(&wide, None).unwrap();
+    assert_eq!(
+        pixels(&sprite),
+        vec![opaque(Color::Rgb {
+            r: 255,
+            g: 0,
+            b: 128
+        })]
+    );
+
+    assert!(image_to_sprite(b"P6 2 1 255\n\x00\x00\x00", None).is_err());
+    assert!(image_to_sprite(b"P3 2 1 255\n0 0 0", None).is_err());
+    assert!(image_to_sprite(b"P6 0 1 255\n", None).is_err());
+    assert!(image_to_sprite(b"P6 1 1 0\n\x00\x00\x00", None).is_err());
+}
+
+#[test]
+fn pam_images_keep_their_alpha() {
+    let mut rgba =
+        b"P7\nWIDTH 3\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nTUPLTYPE RGB_ALPHA\nENDHDR\n".to_vec();
+    rgba.extend([255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 255, 0]);
+    let sprite = image_to_sprite(&rgba, None).unwrap();
+    assert_eq!(
+        pixels(&sprite),
+        vec![opaque(RED), (GREEN, true, 128), (BLUE, false, 0)]
+    );
+
+    let mut gray = b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 1\nMAXVAL 255\nENDHDR\n".to_vec();
+    gray.push(255);
+    assert_eq!(
+        pixels(&image_to_sprite(&gray, None).unwrap()),
+        vec![opaque(WHITE)]
+    );
+
+    assert!(image_to_sprite(b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 3\nMAXVAL 255\n", None).is_err());
+    assert!(image_to_sprite(
+        b"P7\nWIDTH 1\nHEIGHT 1\nDEPTH 5\nMAXVAL 255\nENDHDR\n",
+        None
+    )
+    .is_err());
+    assert!(image_to_sprite(
+        b"P7\nWIDTH 2\nHEIGHT 1\nDEPTH 1\nMAXVAL 255\nENDHDR\n\x00",
+        None
+    )
+    .is_err());
+}
+
+#[test]
+fn bmp_rows_are_read_bottom_up_unless_height_is_negative() {
+    //2x2 BGR rows padded to 4 bytes, the first stored row holds red and green
+    let data = [0, 0, 255, 0, 255, 0, 0, 0, 255, 0, 0, 255, 255, 255, 0, 0];
+    let bottom_up = image_to_sprite(&bmp(40, 2, 2, 24, 0, &[], &data), None).unwrap();
+    assert_eq!((bottom_up.width, bottom_up.height), (2, 2));
+    assert_eq!(
+        pixels(&bottom_up),
+        vec![opaque(BLUE), opaque(WHITE), opaque(RED), opaque(GREEN)]
+    );
+    let top_down = image_to_sprite(&bmp(40, 2, -2, 24, 0, &[], &data), None).unwrap();
+    assert_eq!(
+        pixels(&top_down),
+        vec![opaque(RED), opaque(GREEN), opaque(BLUE), opaque(WHITE)]
+    );
+
+    let keyed = image_to_sprite(&bmp(40, 2, -2, 24, 0, &[], &data), Some(GREEN)).unwrap();
+    assert!(!keyed.pixels[1].isrendered && keyed.pixels[0].isrendered);
+}
+
+#[test]
+fn bmp_32_bit_pixels_use_masks_and_alpha() {
+    //BI_RGB leaves the alpha byte unused, all zero alpha is read as opaque
+    let data = [0, 0, 255, 0, 255, 0, 0, 0];
+    let sprite = image_to_sprite(&bmp(40, 2, 1, 32, 0, &[], &data), None).unwrap();
+    assert_eq!(pixels(&sprite), vec![opaque(RED), opaque(BLUE)]);
+
+    //BI_BITFIELDS masks after a 40 byte header, red in the low byte
+    let mut masks: Vec<u8> = vec![];
+    for mask in [0x0000_00ffu32, 0x0000_ff00, 0x00ff_0000] {
+        masks.extend(mask.to_le_bytes());
+    }
+    let data = [255, 0, 0, 0, 0, 0, 255, 0];
+    let sprite = image_to_sprite(&bmp(40, 2, 1, 32, 3, &masks, &data), None).unwrap();
+    assert_eq!(pixels(&sprite), vec![opaque(RED), opaque(BLUE)]);
+
+    //a V4 header carries its own masks including alpha
+    let mut v4: Vec<u8> = vec![];
+    for mask in [0x00ff_0000u32, 0x0000_ff00, 0x0000_00ff, 0xff00_0000] {
+        v4.extend(mask.to_le_bytes());
+    }
+    let mut file = bmp(
+        108,
+        3,
+        1,
+        32,
+        3,
+        &[],
+        &[0, 0, 255, 255, 0, 255, 0, 64, 255, 0, 0, 0],
+    );
+    file[54..70].copy_from_slice(&v4);
+    let sprite = image_to_sprite(&file, None).unwrap();
+    assert_eq!(
+        pixels(&sprite),
+        vec![opaque(RED), (GREEN, true, 64), (BLUE, false, 0)]
+    );
+
+    //a mask may span all 32 bits
+    let mut wide: Vec<u8> = vec![];
+    for mask in [0xffff_ffffu32, 0, 0, 0] {
+        wide.extend(mask.to_le_bytes());
+    }
+    let mut data: Vec<u8> = vec![];
+    for value in [0xffff_ffffu32, 0x8000_0000, 0] {
+        data.extend(value.to_le_bytes());
+    }
+    let mut file = bmp(108, 3, 1, 32, 3, &[], &data);
+    file[54..70].copy_from_slice(&wide);
+    let sprite = image_to_sprite(&file, None).unwrap();
+    let black = Color::Rgb { r: 0, g: 0, b: 0 };
+    assert_eq!(
+        pixels(&sprite),
+        vec![
+            opaque(RED),
+            opaque(Color::Rgb { r: 128, g: 0, b: 0 }),
+            opaque(black)
+        ]
+    );
+}
+
+#[test]
+fn unsupported_and_truncated_bmps_are_rejected() {
+    let data = [0; 16];
+    let valid = bmp(40, 2, 2, 24, 0, &[], &data);
+    assert!(image_to_sprite(&valid, None).is_ok());
+    //header cut before the bit depth
+    assert!(image_to_sprite(&valid[..26], None).is_err());
+    //pixel data cut short
+    assert!(image_to_sprite(&valid[..valid.len() - 4], None).is_err());
+    //BITMAPCOREHEADER
+    assert!(image_to_sprite(&bmp(12, 2, 2, 24, 0, &[], &data), None).is_err());
+    //palette and 16 bit images
+    assert!(image_to_sprite(&bmp(40, 2, 2, 8, 0, &[], &data), None).is_err());
+    assert!(image_to_sprite(&bmp(40, 2, 2, 16, 0, &[], &data), None).is_err());
+    //run length encoding
+    assert!(image_to_sprite(&bmp(40, 2, 2, 24, 1, &[], &data), None).is_err());
+    //bitfields masks missing
+    assert!(image_to_sprite(&bmp(40, 1, 1, 32, 3, &[], &[]), None).is_err());
+    //negative and zero width
+    assert!(image_to_sprite(&bmp(40, -2, 2, 24, 0, &[], &data), None).is_err());
+    assert!(image_to_sprite(&bmp(40, 0, 2, 24, 0, &[], &data), None).is_err());
+
+    assert!(image_to_sprite(b"GIF89a", None).is_err());
+    assert!(image_to_sprite(b"", None).is_err());
+}
+
+#[cfg(feature = "png")]
+#[test]
+fn png_sprites_and_frames_survive_a_round_trip() {
+    use rs_cli_renderer::image::{load_image, save_frame_png, save_sprite_png};
+    use rs_cli_renderer::stack::Renderer;
+
+    let dir = std::env::temp_dir().join(format!("rs_cli_renderer_png_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = |name: &str| dir.join(name).to_str().unwrap().to_string();
+
+    let mut rgba = b"P7\nWIDTH 3\nHEIGHT 1\nDEPTH 4\nMAXVAL 255\nENDHDR\n".to_vec();
+    rgba.extend([255, 0, 0, 255, 0, 255, 0, 128, 0, 0, 255, 0]);
+    let sprite = image_to_sprite(&rgba, None).unwrap();
+    save_sprite_png(&sprite, &path("sprite.png")).unwrap();
+    let loaded = load_image(&path("sprite.png"), None).unwrap();
+    assert_eq!((loaded.width, loaded.height), (3, 1));
+    assert_eq!(pixels(&loaded), pixels(&sprite));
+
+    let mut renderer = Renderer::headless(3, 2, Color::Black);
+    let layer = renderer.layerstack.add(0, 0);
+    renderer.layerstack.write_sprite(0, 1, &sprite, layer);
+    renderer.render_update();
+    save_frame_png(renderer.last_frame(), &path("frame.png")).unwrap();
+    let frame = load_image(&path("frame.png"), None).unwrap();
+    let black = Color::Rgb { r: 0, g: 0, b: 0 };
+    //the half transparent green pixel is blended onto the black background
+    let dark_green = Color::Rgb { r: 0, g: 128, b: 0 };
+    assert_eq!(
+        pixels(&frame),
+        vec![
+            opaque(black),
+            opaque(black),
+            opaque(black),
+            opaque(RED),
+            opaque(dark_green),
+            opaque(black)
+        ]
+    );
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,84 @@
+//input.rs
+//input recording and replay tests, sessions are scripted and replayed headless
+use crossterm::event::KeyCode;
+use crossterm::style::Color;
+use rs_cli_renderer::game::input::{Input, InputRecording, InputState};
+use rs_cli_renderer::snapshot::assert_snapshot;
+use rs_cli_renderer::stack::Renderer;
+
+const SCRIPT: &str = "frames 7
+1 Right
+2 Right Down
+4 Space
+5 Left x
+";
+
+///runs a tiny game until the replay ends, a dot moved by the arrow keys that drops a marker
+///on space, returns the input of every frame and the renderer holding the final frame
+fn play(input: &mut Input) -> (Vec<InputState>, Renderer) {
+    let mut renderer = Renderer::headless(6, 4, Color::Black);
+    let layer = renderer.layerstack.add(0, 0);
+    let (mut x, mut y) = (1, 1);
+    let mut markers: Vec<(i16, i16)> = vec![];
+    let mut states: Vec<InputState> = vec![];
+    while !input.replay_finished() {
+        let state = input.poll().clone();
+        for key in state.keys.iter() {
+            match key {
+                KeyCode::Right => x += 1,
+                KeyCode::Left => x -= 1,
+                KeyCode::Down => y += 1,
+                KeyCode::Up => y -= 1,
+                KeyCode::Char(' ') => markers.push((x, y)),
+                _ => {}
+            }
+        }
+        for (marker_x, marker_y) in markers.iter() {
+            renderer.layerstack.direct_write(*marker_x, *marker_y, Color::Red, layer);
+        }
+        renderer.layerstack.direct_write(x, y, Color::White, layer);
+        renderer.render_update();
+        states.push(state);
+    }
+    (states, renderer)
+}
+
+#[test]
+fn recorded_sessions_replay_to_the_same_frame() {
+    let mut input = Input::replay(InputRecording::from_text(SCRIPT).unwrap());
+    input.start_recording();
+    let (states, renderer) = play(&mut input);
+    let recording = input.stop_recording().unwrap();
+    assert_eq!(states.len(), 7);
+    assert_eq!(states[1].keys, vec![KeyCode::Right, KeyCode::Down]);
+    assert!(states[2].keys.is_empty() && states[6].keys.is_empty());
+    assert!(states[4].pressed(KeyCode::Char('x')));
+    assert_snapshot("input_replay_final_frame", renderer.last_frame());
+
+    let text = recording.to_text();
+    assert_eq!(text, SCRIPT);
+    assert_eq!(InputRecording::from_text(&text).unwrap(), recording);
+
+    let path = std::env::temp_dir().join(format!("rs_cli_renderer_{}.input", std::process::id()));
+    let path = path.to_str().unwrap();
+    recording.save(path).unwrap();
+    let loaded = InputRecording::load(path).unwrap();
+    std::fs::remove_file(path).unwrap();
+    assert_eq!(loaded.length(), 7);
+
+    let mut replay = Input::replay(loaded);
+    let (replayed_states, replayed) = play(&mut replay);
+    assert_eq!(replayed_states, states);
+    assert_eq!(replay.frame(), 7);
+    assert_snapshot("input_replay_final_frame", replayed.last_frame());
+}
+
+#[test]
+fn recordings_reject_frames_that_never_replay() {
+    assert!(InputRecording::from_text("frames 3\n1 a\n3 b\n").is_ok());
+    assert!(InputRecording::from_text("frames 3\n0 a\n").is_err());
+    assert!(InputRecording::from_text("frames 3\n4 a\n").is_err());
+    assert!(InputRecording::from_text("frames 3\n2 a\n1 b\n").is_err());
+    assert!(InputRecording::from_text("frames 3\n1 NoSuchKey\n").is_err());
+    assert!(InputRecording::from_text("1 a\n").is_err());
+}
@@ -0,0 +1,97 @@
+//pack.rs
+//asset pack tests, packs are built from tests/assets
+use crossterm::style::Color;
+use rs_cli_renderer::game::assets::Assets;
+use rs_cli_renderer::pack::{EntryKind, Pack};
+use rs_cli_renderer::sprite::{load_sprite_file, Sprite};
+
+fn assets_dir() -> String {
+    format!("{}/tests/assets", env!("CARGO_MANIFEST_DIR"))
+}
+
+///(x, y, color, isrendered, alpha)
+type PixelContents = (i16, i16, Color, bool, u8);
+
+///comparable contents of a sprite
+fn contents(sprite: &Sprite) -> (u16, u16, (u16, u16), Vec<PixelContents>) {
+    let pixels = sprite
+        .pixels
+        .iter()
+        .map(|pixel| (pixel.x, pixel.y, pixel.color, pixel.isrendered, pixel.alpha))
+        .collect();
+    (sprite.width, sprite.height, sprite.center, pixels)
+}
+
+#[test]
+fn packs_round_trip_compiled_assets() {
+    let pack = Pack::from_directory(&assets_dir()).unwrap();
+    let kinds: Vec<(&str, EntryKind)> =
+        pack.entries().iter().map(|entry| (entry.name.as_str(), entry.kind)).collect();
+    assert!(kinds.contains(&("enemies.sprites", EntryKind::Sprites)));
+    assert!(kinds.contains(&("block.ppm", EntryKind::Image)));
+    assert!(kinds.contains(&("level.map", EntryKind::Raw)));
+
+    let loaded = Pack::from_bytes(&pack.to_bytes()).unwrap();
+    assert_eq!(loaded, pack);
+    let enemies = load_sprite_file(&format!("{}/enemies.sprites", assets_dir())).unwrap();
+    let packed = loaded.sprites("enemies.sprites").unwrap();
+    assert_eq!(packed.len(), enemies.len());
+    for ((packed_name, packed), (name, sprite)) in packed.iter().zip(enemies.iter()) {
+        assert_eq!(packed_name, name);
+        assert_eq!(contents(packed), contents(sprite));
+        assert_eq!(packed.tag, sprite.tag);
+    }
+    assert!(loaded.image("level.map").is_err());
+}
+
+#[test]
+fn assets_load_from_packs_like_directories() {
+    let mut directory = Assets::new(&assets_dir());
+    let mut packed = Assets::from_pack(Pack::from_directory(&assets_dir()).unwrap());
+    assert!(packed.root().is_none());
+    for name in ["enemies/bat", "enemies/rat", "block"] {
+        assert_eq!(
+            contents(&packed.sprite(name).unwrap()),
+            contents(&directory.sprite(name).unwrap())
+        );
+    }
+    assert_eq!(packed.animation("walk").unwrap().frames.len(), 2);
+    let level = packed.tilemap("level", "tiles").unwrap();
+    assert!(level.is_solid(0, 0) && !level.is_solid(1, 1));
+
+    let palette = packed.palette("colors").unwrap();
+    assert_eq!(palette.get("grass"), Some(Color::DarkGreen));
+    assert_eq!(palette, directory.palette("colors").unwrap());
+    assert!(packed.sprite("enemies/dragon").is_err());
+}
+
+#[test]
+fn corrupt_packs_are_rejected() {
+    let bytes = Pack::from_directory(&assets_dir()).unwrap().to_bytes();
+    assert!(Pack::from_bytes(&bytes[..bytes.len() - 1]).is_err());
+    assert!(Pack::from_bytes(b"nope").is_err());
+
+    //flip the last byte of the data, which belongs to the last entry
+    let mut corrupt = bytes.clone();
+    *corrupt.last_mut().unwrap() ^= 0xFF;
+    let pack = Pack::from_bytes(&corrupt).unwrap();
+    let last = pack.entries().last().unwrap();
+    let error = pack.bytes(&last.name).err().unwrap();
+    assert!(error.contains("checksum"), "{}", error);
+}
+
+#[test]
+fn replaced_entries_do_not_keep_their_old_bytes() {
+    let mut pack = Pack::new();
+    pack.add("a.txt", EntryKind::Raw, b"first".to_vec());
+    pack.add("b.txt", EntryKind::Raw, b"kept".to_vec());
+    pack.add("a.txt", EntryKind::Raw, b"second".to_vec());
+    assert_eq!(pack.text("a.txt").unwrap(), "second");
+    assert_eq!(pack.text("b.txt").unwrap(), "kept");
+
+    //same size as a pack that never held the first version
+    let mut fresh = Pack::new();
+    fresh.add("b.txt", EntryKind::Raw, b"kept".to_vec());
+    fresh.add("a.txt", EntryKind::Raw, b"second".to_vec());
+    assert_eq!(pack.to_bytes(), fresh.to_bytes());
+}
@@ -0,0 +1,109 @@
+//particle.rs
+//particle emitters and drawing them to layers
+use crossterm::style::Color;
+use rs_cli_renderer::particle::{Emitter, EmitterConfig, ParticleShape};
+use rs_cli_renderer::sprite::parse_sprite_file;
+use rs_cli_renderer::stack::Renderer;
+use std::rc::Rc;
+use std::time::Duration;
+
+///emitter whose particles stand still for one second
+fn still(config: EmitterConfig) -> Emitter {
+    Emitter::new(
+        1.0,
+        0.0,
+        EmitterConfig {
+            speed: (0.0, 0.0),
+            ..config
+        },
+    )
+}
+
+#[test]
+fn particles_spawn_move_and_die() {
+    let mut rain = Emitter::new(
+        0.0,
+        0.0,
+        EmitterConfig {
+            rate: 10.0,
+            lifetime: (2.0, 3.0),
+            speed: (5.0, 10.0),
+            direction: std::f32::consts::PI / 2.0,
+            spread: 0.0,
+            gravity: (0.0, 20.0),
+            area: (10, 0),
+            max_particles: 8,
+            ..EmitterConfig::default()
+        },
+    );
+    rain.update(Duration::from_millis(500));
+    assert_eq!(rain.len(), 5);
+    assert!(rain.particles().iter().all(|particle| particle.y == 0.0 && particle.x < 10.0));
+    rain.update(Duration::from_millis(500));
+    //capped by max_particles
+    assert_eq!(rain.len(), 8);
+    let falling = &rain.particles()[0];
+    assert!(falling.velocity.1 > 5.0 && falling.y > 2.5, "{:?}", falling);
+
+    rain.emitting = false;
+    rain.update(Duration::from_secs(3));
+    assert!(rain.is_finished());
+
+    let mut explosion = Emitter::new(0.0, 0.0, EmitterConfig::default());
+    explosion.burst(3);
+    assert_eq!(explosion.len(), 3);
+}
+
+#[test]
+fn particles_are_drawn_with_their_gradient() {
+    let mut renderer = Renderer::headless(4, 2, Color::Black);
+    let layer = renderer.layerstack.add(0, 0);
+    let mut spark = still(EmitterConfig {
+        gradient: vec![
+            (0.0, Color::Rgb { r: 200, g: 0, b: 0 }),
+            (1.0, Color::Rgb { r: 0, g: 0, b: 200 }),
+        ],
+        ..EmitterConfig::default()
+    });
+    spark.emitting = false;
+    spark.burst(1);
+    spark.update(Duration::from_millis(500));
+    renderer.layerstack.draw_particles(&spark, layer);
+    renderer.render_update();
+    let color = |renderer: &Renderer, x: u16, y: u16| {
+        renderer.last_frame().get(x, y).map(|pixel| pixel.color)
+    };
+    assert_eq!(
+        color(&renderer, 1, 0),
+        Some(Color::Rgb {
+            r: 100,
+            g: 0,
+            b: 100
+        })
+    );
+
+    //sprite particles are centred on the particle and fade out
+    let dash = parse_sprite_file("color # Red\n\nsprite dash\n##").unwrap().remove(0).1;
+    let mut smoke = still(EmitterConfig {
+        gradient: vec![],
+        fade_out: true,
+        shape: ParticleShape::Sprite(Rc::new(dash)),
+        ..EmitterConfig::default()
+    });
+    smoke.y = 1.0;
+    smoke.emitting = false;
+    smoke.burst(1);
+    renderer.layerstack.draw_particles(&smoke, layer);
+    renderer.render_update();
+    assert_eq!(
+        (color(&renderer, 0, 1), color(&renderer, 1, 1)),
+        (Some(Color::Red), Some(Color::Red))
+    );
+    smoke.update(Duration::from_millis(500));
+    renderer.layerstack.draw_particles(&smoke, layer);
+    renderer.render_update();
+    assert_eq!(
+        color(&renderer, 0, 1),
+        Some(Color::Rgb { r: 128, g: 0, b: 0 })
+    );
+}
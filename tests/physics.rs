@@ -0,0 +1,90 @@
+//physics.rs
+//fixed step physics against tilemaps and other bodies
+use rs_cli_renderer::game::physics::{Body, PhysicsWorld};
+use rs_cli_renderer::tilemap::{Tilemap, Tileset};
+use std::time::Duration;
+
+///a 2x2 pixel tile room, 8 tiles wide and 5 tiles high
+fn room() -> Tilemap {
+    let tileset =
+        Tileset::from_sprite_file("color # Red\n\nsprite wall\ntag solid\n##\n##").unwrap();
+    Tilemap::parse(
+        "
+        legend # wall
+        layer walls
+        ########
+        #......#
+        #......#
+        #......#
+        ########
+        ",
+        tileset,
+    )
+    .unwrap()
+}
+
+#[test]
+fn bodies_fall_and_land_on_solid_tiles() {
+    let map = room();
+    let mut world = PhysicsWorld::new((0.0, 120.0));
+    let player = world.add(Body::new(4.0, 2.0, 2, 2));
+    assert_eq!(world.update(Duration::from_millis(50), Some(&map)), 3);
+    assert!(world.get(player).unwrap().y > 2.0);
+
+    //a long stall runs a bounded number of steps
+    assert_eq!(world.update(Duration::from_secs(2), Some(&map)), 8);
+    for _ in 0..30 {
+        world.update(Duration::from_millis(50), Some(&map));
+    }
+    let body = world.get(player).unwrap();
+    //the floor row starts at y 8
+    assert_eq!(body.y, 6.0);
+    assert_eq!(body.velocity.1, 0.0);
+    assert!(body.on_ground());
+    assert_eq!(body.position(), (4, 6));
+}
+
+#[test]
+fn fast_bodies_stop_at_walls_without_tunneling() {
+    let map = room();
+    let mut world = PhysicsWorld::new((0.0, 0.0));
+    let runner = world.add(Body::new(4.0, 4.0, 2, 2));
+    world.get_mut(runner).unwrap().velocity = (10_000.0, 0.0);
+    world.step(Some(&map));
+    let body = world.get(runner).unwrap();
+    //the right wall column starts at x 14
+    assert_eq!(body.x, 12.0);
+    assert!(body.contacts.right && !body.contacts.left);
+    assert_eq!(body.velocity.0, 0.0);
+
+    world.get_mut(runner).unwrap().velocity = (-10_000.0, 0.0);
+    world.step(Some(&map));
+    assert_eq!(world.get(runner).unwrap().x, 2.0);
+    //without a map nothing stops the body
+    world.get_mut(runner).unwrap().velocity = (-60.0, 0.0);
+    world.step(None);
+    assert_eq!(world.get(runner).unwrap().x, 1.0);
+}
+
+#[test]
+fn bodies_collide_with_each_other() {
+    let mut world = PhysicsWorld::new((0.0, 600.0));
+    let platform = world.add(Body::fixed(0.0, 10.0, 10, 2));
+    let crate_body = world.add(Body::new(2.0, 0.0, 2, 2));
+    let ghost = world.add(Body {
+        solid: false,
+        ..Body::new(6.0, 0.0, 2, 2)
+    });
+    for _ in 0..60 {
+        world.step(None);
+    }
+    assert_eq!(world.get(crate_body).unwrap().y, 8.0);
+    assert!(world.get(crate_body).unwrap().on_ground());
+    assert!(world.get(ghost).unwrap().y > 12.0);
+    assert_eq!(world.get(platform).unwrap().y, 10.0);
+    assert_eq!(world.touching(), &[(crate_body, platform)]);
+
+    world.remove(platform);
+    world.step(None);
+    assert!(!world.get(crate_body).unwrap().on_ground());
+}
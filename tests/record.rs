@@ -0,0 +1,87 @@
+//record.rs
+//session recording tests, recordings are asciicast v2 files
+use crossterm::style::Color;
+use rs_cli_renderer::record::Recording;
+use rs_cli_renderer::stack::Renderer;
+use std::time::Duration;
+
+///returns the timestamp of every event line of a cast
+fn timestamps(cast: &str) -> Vec<&str> {
+    cast.lines()
+        .skip(1)
+        .map(|line| line.trim_start_matches('[').split(',').next().unwrap())
+        .collect()
+}
+
+#[test]
+fn recordings_are_asciicast_files_with_framerate_timestamps() {
+    let mut renderer = Renderer::headless(3, 2, Color::Black);
+    renderer.set_framerate(40);
+    renderer.start_recording();
+    assert!(renderer.is_recording());
+    for _ in 0..3 {
+        renderer.render_update();
+    }
+    //the new framerate applies from the next recorded frame on
+    renderer.set_framerate(100);
+    renderer.render_update();
+    renderer.render_update();
+    let recording = renderer.stop_recording().unwrap();
+    assert!(!renderer.is_recording() && renderer.stop_recording().is_none());
+    assert_eq!(recording.frame_count(), 5);
+
+    let cast = recording.to_asciicast();
+    let columns = 3 * renderer.pixel_element.chars().count();
+    assert_eq!(
+        cast.lines().next().unwrap(),
+        format!("{{\"version\": 2, \"width\": {}, \"height\": 2}}", columns)
+    );
+    assert_eq!(
+        timestamps(&cast),
+        vec!["0.000000", "0.040000", "0.080000", "0.120000", "0.220000"]
+    );
+    for line in cast.lines().skip(1) {
+        assert!(
+            line.contains(", \"o\", \"") && line.ends_with("\"]"),
+            "{}",
+            line
+        );
+    }
+    //recording the same session again gives the same file
+    let mut again = Renderer::headless(3, 2, Color::Black);
+    again.set_framerate(40);
+    again.start_recording();
+    for _ in 0..3 {
+        again.render_update();
+    }
+    again.set_framerate(100);
+    again.render_update();
+    again.render_update();
+    assert_eq!(again.stop_recording().unwrap().to_asciicast(), cast);
+}
+
+#[test]
+fn frame_output_is_escaped_as_json() {
+    let mut recording = Recording::new(4, 1);
+    recording.record_frame(b"\x1b[2J\"a\\b\"\r\n\tc\x7f", Duration::from_millis(50));
+    recording.record_frame("é".as_bytes(), Duration::from_millis(50));
+    assert_eq!(
+        recording.to_asciicast(),
+        concat!(
+            "{\"version\": 2, \"width\": 4, \"height\": 1}\n",
+            "[0.000000, \"o\", \"\\u001b[2J\\\"a\\\\b\\\"\\r\\n\\tc\\u007f\"]\n",
+            "[0.050000, \"o\", \"é\"]\n"
+        )
+    );
+
+    //text drawn by the renderer is escaped the same way
+    let mut renderer = Renderer::headless(4, 1, Color::Black);
+    let text = renderer.layerstack.add_text(0, 0);
+    renderer.layerstack.write_text(0, 0, "\"\\", Color::White, None, text);
+    renderer.start_recording();
+    renderer.render_update();
+    let cast = renderer.stop_recording().unwrap().to_asciicast();
+    //every cell is styled on its own, so the quote and the backslash sit between escape codes
+    assert!(cast.contains("m\\\"\\u001b["), "{}", cast);
+    assert!(cast.contains("m\\\\\\u001b["), "{}", cast);
+}
@@ -0,0 +1,368 @@
+//render.rs
+//golden frame tests, run with UPDATE_SNAPSHOTS=1 to regenerate tests/snapshots
+use crossterm::style::Color;
+use rs_cli_renderer::color::BlendMode;
+use rs_cli_renderer::draw;
+use rs_cli_renderer::font::{Font, TextAlign, TextStyle};
+use rs_cli_renderer::snapshot::assert_snapshot;
+use rs_cli_renderer::sprite::{compile_sprite, Metadata, Sprite};
+use rs_cli_renderer::stack::Renderer;
+use rs_cli_renderer::tilemap::{Tilemap, Tileset};
+use std::collections::HashMap;
+
+fn arrow() -> Sprite {
+    let metadata = Metadata {
+        color_map: HashMap::from([('▓', Color::Blue)]),
+        transparent: '░',
+        height: 3,
+        width: 3,
+        tag: None,
+    };
+    compile_sprite(
+        "
+        ░▓░
+        ▓▓▓
+        ░▓░
+        ",
+        metadata,
+    )
+    .unwrap()
+}
+
+fn block(color: Color) -> Sprite {
+    let metadata = Metadata {
+        color_map: HashMap::from([('#', color)]),
+        transparent: '.',
+        height: 2,
+        width: 2,
+        tag: None,
+    };
+    compile_sprite("##\n##", metadata).unwrap()
+}
+
+#[test]
+fn compile_sprite_places_pixels_from_origin() {
+    let sprite = arrow();
+    assert_eq!((sprite.width, sprite.height, sprite.center), (3, 3, (1, 1)));
+    let positions: Vec<(i16, i16, bool)> =
+        sprite.pixels.iter().map(|pixel| (pixel.x, pixel.y, pixel.isrendered)).collect();
+    assert_eq!(
+        positions,
+        vec![
+            (0, 0, false),
+            (1, 0, true),
+            (2, 0, false),
+            (0, 1, true),
+            (1, 1, true),
+            (2, 1, true),
+            (0, 2, false),
+            (1, 2, true),
+            (2, 2, false),
+        ]
+    );
+}
+
+#[test]
+fn compile_sprite_rejects_wrong_aspect_ratio() {
+    let metadata = Metadata {
+        color_map: HashMap::from([('#', Color::Red)]),
+        transparent: '.',
+        height: 2,
+        width: 3,
+        tag: None,
+    };
+    assert!(compile_sprite("##\n##", metadata).is_err());
+}
+
+#[test]
+fn layers_draw_in_stack_order() {
+    let mut renderer = Renderer::headless(6, 4, Color::Black);
+    let bottom = renderer.layerstack.add(0, 0);
+    let top = renderer.layerstack.add(1, 1);
+    renderer.layerstack.write_sprite(1, 1, &block(Color::Red), bottom);
+    renderer.layerstack.write_sprite(2, 1, &block(Color::Green), top);
+    renderer.render_update();
+    assert_snapshot("layers_draw_in_stack_order", renderer.last_frame());
+
+    renderer.layerstack.move_layer(top, 0);
+    renderer.layerstack.write_sprite(1, 1, &block(Color::Red), bottom);
+    renderer.layerstack.write_sprite(2, 1, &block(Color::Green), top);
+    renderer.render_update();
+    assert_snapshot(
+        "layers_draw_in_stack_order_after_move",
+        renderer.last_frame(),
+    );
+}
+
+#[test]
+fn headless_renderers_keep_width_and_height_apart() {
+    let mut renderer = Renderer::headless(5, 2, Color::Black);
+    let layer = renderer.layerstack.add(0, 0);
+    renderer.layerstack.direct_write(4, 1, Color::Red, layer);
+    renderer.render_update();
+    let frame = renderer.last_frame();
+    assert_eq!(
+        (frame.width(), frame.height(), frame.buffer.len()),
+        (5, 2, 10)
+    );
+    assert_eq!(frame.get(4, 1).unwrap().color, Color::Red);
+    assert!(frame.get(1, 4).is_none());
+}
+
+#[test]
+fn hidden_layers_are_skipped() {
+    let mut renderer = Renderer::headless(4, 4, Color::Black);
+    let hidden = renderer.layerstack.add(0, 0);
+    renderer.layerstack.set_visibility(hidden, false);
+    renderer.layerstack.write_sprite(0, 0, &arrow(), hidden);
+    renderer.render_update();
+    assert_snapshot("hidden_layers_are_skipped", renderer.last_frame());
+}
+
+#[test]
+fn sprites_are_clipped_at_the_edges() {
+    let mut renderer = Renderer::headless(4, 4, Color::Black);
+    let layer = renderer.layerstack.add(0, 0);
+    renderer.layerstack.write_sprite(-1, -1, &arrow(), layer);
+    renderer.layerstack.write_sprite(2, 2, &arrow(), layer);
+    renderer.render_update();
+    assert_snapshot("sprites_are_clipped_at_the_edges", renderer.last_frame());
+}
+
+#[test]
+fn blend_modes_combine_with_lower_layers() {
+    let mut renderer = Renderer::headless(
+        8,
+        2,
+        Color::Rgb {
+            r: 100,
+            g: 100,
+            b: 100,
+        },
+    );
+    let modes = [
+        BlendMode::Normal,
+        BlendMode::Additive,
+        BlendMode::Multiply,
+        BlendMode::Screen,
+    ];
+    let draw = |renderer: &mut Renderer| {
+        for index in 0..modes.len() {
+            renderer.layerstack.write_sprite(
+                index as i16 * 2,
+                0,
+                &block(Color::Rgb {
+                    r: 200,
+                    g: 50,
+                    b: 0,
+                }),
+                index as u16,
+            );
+        }
+        renderer.render_update();
+    };
+    for (index, mode) in modes.iter().enumerate() {
+        let layer = renderer.layerstack.add(index as u16, index as u16);
+        renderer.layerstack.set_blend_mode(layer, *mode);
+        renderer.layerstack.set_opacity(layer, 0.5);
+    }
+    draw(&mut renderer);
+    assert_snapshot(
+        "blend_modes_combine_with_lower_layers",
+        renderer.last_frame(),
+    );
+
+    //a full strength tint paints every pixel of the opaque normal layer in the tint color
+    renderer.layerstack.set_opacity(0, 1.0);
+    renderer.layerstack.set_tint(0, Color::White, 1.0);
+    draw(&mut renderer);
+    for (x, y) in [(0, 0), (1, 0), (0, 1), (1, 1)] {
+        let pixel = renderer.last_frame().get(x, y).unwrap();
+        assert_eq!(pixel.color, Color::White);
+    }
+
+    renderer.layerstack.clear_tint(0);
+    renderer.layerstack.set_opacity(0, 0.5);
+    draw(&mut renderer);
+    assert_snapshot(
+        "blend_modes_combine_with_lower_layers",
+        renderer.last_frame(),
+    );
+}
+
+#[test]
+fn text_is_aligned_and_wrapped() {
+    let mut renderer = Renderer::headless(24, 20, Color::Black);
+    let layer = renderer.layerstack.add(0, 0);
+    let font = Font::builtin();
+    //the builtin font is compiled once and shared
+    assert!(std::ptr::eq(font, Font::builtin()));
+    let wrapped = TextStyle {
+        color: Color::Yellow,
+        wrap_width: Some(20),
+        ..Default::default()
+    };
+    renderer.layerstack.draw_text(1, 1, "Hi there 42", font, &wrapped, layer);
+    let right = TextStyle {
+        align: TextAlign::Right,
+        ..Default::default()
+    };
+    renderer.layerstack.draw_text(23, 14, "OK!", font, &right, layer);
+    renderer.render_update();
+    assert_snapshot("text_is_aligned_and_wrapped", renderer.last_frame());
+}
+
+#[test]
+fn text_layers_are_covered_by_higher_layers() {
+    let mut renderer = Renderer::headless(4, 2, Color::Black);
+    let text = renderer.layerstack.add_text(0, 0);
+    let cover = renderer.layerstack.add(1, 1);
+    renderer.layerstack.write_text(0, 0, "AZ", Color::White, None, text);
+    renderer.layerstack.write_text(2, 1, "Q", Color::White, None, text);
+    renderer.layerstack.write_sprite(1, 0, &block(Color::Red), cover);
+    renderer.start_recording();
+    renderer.render_update();
+    let cast = renderer.stop_recording().unwrap().to_asciicast();
+    //A and Z share the first pixel, Q sits under the red block
+    assert!(cast.contains('A') && cast.contains('Z'));
+    assert!(!cast.contains('Q'));
+}
+
+#[test]
+fn text_shows_through_translucent_layers() {
+    let mut renderer = Renderer::headless(4, 2, Color::Black);
+    let text = renderer.layerstack.add_text(0, 0);
+    let fade = renderer.layerstack.add(1, 1);
+    let mut characters = |opacity: f32| -> String {
+        renderer.layerstack.write_text(0, 0, "HP", Color::White, None, text);
+        renderer.layerstack.draw_rect(0, 0, 4, 2, Color::Black, true, fade);
+        renderer.layerstack.set_opacity(fade, opacity);
+        renderer.render_update();
+        renderer.last_frame().text().iter().map(|cell| cell.character).collect()
+    };
+    assert_eq!(characters(0.0), "HP");
+    assert_eq!(characters(0.01), "HP");
+    assert_eq!(characters(0.5), "HP");
+    assert_eq!(characters(1.0), "");
+}
+
+#[test]
+fn text_and_the_debug_overlay_are_part_of_snapshots() {
+    let mut renderer = Renderer::headless(4, 3, Color::Black);
+    let text = renderer.layerstack.add_text(0, 0);
+    let cover = renderer.layerstack.add(1, 1);
+    renderer.layerstack.write_text(0, 1, "HP 3", Color::White, None, text);
+    renderer.layerstack.write_text(2, 2, "Q", Color::White, None, text);
+    renderer.layerstack.write_text(6, 2, "!", Color::Yellow, Some(Color::DarkBlue), text);
+    renderer.layerstack.write_sprite(1, 2, &block(Color::Red), cover);
+    renderer.overlay_message("fps", "9");
+    renderer.render_update();
+    assert_snapshot("text_and_the_debug_overlay", renderer.last_frame());
+}
+
+#[test]
+fn text_past_the_last_terminal_cell_is_dropped() {
+    let mut renderer = Renderer::headless(4, 2, Color::Black);
+    let text = renderer.layerstack.add_text(0, 0);
+    renderer.layerstack.write_text(u16::MAX - 1, 0, "abc", Color::White, None, text);
+    renderer.layerstack.write_text(0, u16::MAX, "a\nb\nc", Color::White, None, text);
+    renderer.render_update();
+    assert!(renderer.last_frame().text().is_empty());
+}
+
+#[test]
+fn primitives_are_rasterized() {
+    let mut renderer = Renderer::headless(24, 12, Color::Black);
+    let layer = renderer.layerstack.add(0, 0);
+    renderer.layerstack.draw_line(0, 11, 7, 0, Color::White, layer);
+    renderer.layerstack.draw_rect(8, 0, 7, 5, Color::Blue, false, layer);
+    renderer.layerstack.flood_fill(10, 2, Color::Cyan, layer);
+    renderer.layerstack.draw_circle(11, 8, 3, Color::Yellow, true, layer);
+    renderer.layerstack.draw_ellipse(19, 3, 4, 2, Color::Green, false, layer);
+    renderer.layerstack.draw_polygon(&[(16, 11), (23, 11), (20, 6)], Color::Red, true, layer);
+    renderer.render_update();
+    assert_snapshot("primitives_are_rasterized", renderer.last_frame());
+}
+
+#[test]
+fn shapes_are_clipped_instead_of_wrapping() {
+    //pixels past the largest position are dropped rather than wrapped to negative positions
+    let clipped = draw::rect(32_000, 0, 2_000, 1, Color::Red, true);
+    assert_eq!(clipped.len(), 768);
+    assert!(clipped.iter().all(|pixel| pixel.x >= 32_000));
+
+    let mut renderer = Renderer::headless(4, 3, Color::Black);
+    let layer = renderer.layerstack.add(0, 0);
+    //shapes far larger than the screen only produce the pixels that land on it
+    renderer.layerstack.draw_rect(
+        -30_000,
+        -30_000,
+        u16::MAX,
+        u16::MAX,
+        Color::Red,
+        true,
+        layer,
+    );
+    renderer.layerstack.draw_circle(1, 1, 60_000, Color::Green, true, layer);
+    renderer.layerstack.draw_polygon(
+        &[(-32_000, -32_000), (32_000, -32_000), (0, 32_000)],
+        Color::Blue,
+        true,
+        layer,
+    );
+    renderer.layerstack.draw_ellipse(-20_000, 0, 10_000, 5, Color::White, false, layer);
+    renderer.layerstack.draw_line(-32_768, 1, 32_767, 1, Color::Yellow, layer);
+    renderer.render_update();
+    let frame = renderer.last_frame();
+    for y in 0..3 {
+        let expected = if y == 1 { Color::Yellow } else { Color::Blue };
+        for x in 0..4 {
+            assert_eq!(frame.get(x, y).unwrap().color, expected);
+        }
+    }
+}
+
+const TILES: &str = "
+color # Red
+color ~ Blue
+color w Cyan
+
+sprite wall
+tag solid
+##
+##
+
+sprite water:0
+~w
+w~
+
+sprite water:1
+w~
+~w
+";
+
+#[test]
+fn tilemaps_draw_visible_tiles_and_animate() {
+    let tileset = Tileset::from_sprite_file(TILES).unwrap();
+    let map = Tilemap::parse(
+        "
+        legend # wall
+        legend ~ water
+        layer ground
+        ######
+        #~~..#
+        ######
+        ",
+        tileset,
+    )
+    .unwrap();
+    assert!(map.is_solid(0, 0) && !map.is_solid(1, 1) && map.is_solid(-1, 1));
+    let mut renderer = Renderer::headless(8, 6, Color::Black);
+    let layer = renderer.layerstack.add(0, 0);
+    renderer.layerstack.draw_tilemap(&map, 0, (1, 0), 0, layer);
+    renderer.render_update();
+    assert_snapshot("tilemaps_draw_visible_tiles", renderer.last_frame());
+    renderer.layerstack.draw_tilemap(&map, 0, (1, 0), 8, layer);
+    renderer.render_update();
+    assert_snapshot("tilemaps_animate_tiles", renderer.last_frame());
+}
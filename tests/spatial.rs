@@ -0,0 +1,55 @@
+//spatial.rs
+//spatial hash queries
+use rs_cli_renderer::collision::Rect;
+use rs_cli_renderer::spatial::SpatialHash;
+
+fn grid() -> SpatialHash<u32> {
+    let mut grid = SpatialHash::new(4);
+    grid.insert(1, Rect::new(0, 0, 3, 3));
+    //spans four cells
+    grid.insert(2, Rect::new(2, 2, 6, 6));
+    grid.insert(3, Rect::new(-10, 20, 2, 2));
+    grid.insert(4, Rect::new(30, 1, 1, 1));
+    grid
+}
+
+#[test]
+fn rect_and_point_queries() {
+    let mut grid = grid();
+    assert_eq!(grid.len(), 4);
+    assert_eq!(grid.query_rect(&Rect::new(1, 1, 2, 2)), vec![1, 2]);
+    assert_eq!(
+        grid.query_rect(&Rect::new(-20, 0, 60, 30)),
+        vec![1, 2, 3, 4]
+    );
+    assert_eq!(grid.query_rect(&Rect::new(8, 8, 4, 4)), Vec::<u32>::new());
+    assert_eq!(grid.query_point(2, 2), vec![1, 2]);
+    assert_eq!(grid.query_point(7, 7), vec![2]);
+    assert_eq!(grid.query_point(-9, 21), vec![3]);
+    assert_eq!(grid.query_point(3, 0), Vec::<u32>::new());
+
+    //moving an object keeps its result order
+    grid.insert(1, Rect::new(6, 6, 1, 1));
+    assert_eq!(grid.query_point(2, 2), vec![2]);
+    assert_eq!(grid.query_point(6, 6), vec![1, 2]);
+    assert_eq!(grid.remove(&2), Some(Rect::new(2, 2, 6, 6)));
+    assert_eq!(grid.query_point(6, 6), vec![1]);
+    assert_eq!(grid.remove(&2), None);
+    assert_eq!(grid.len(), 3);
+}
+
+#[test]
+fn nearest_and_pairs() {
+    let mut grid = grid();
+    assert_eq!(grid.nearest(1, 1), Some(1));
+    assert_eq!(grid.nearest(9, 9), Some(2));
+    assert_eq!(grid.nearest(-50, 50), Some(3));
+    assert_eq!(grid.nearest(100, 0), Some(4));
+    assert_eq!(grid.pairs(), vec![(1, 2)]);
+
+    grid.insert(5, Rect::new(29, 0, 3, 3));
+    assert_eq!(grid.pairs(), vec![(1, 2), (4, 5)]);
+    grid.clear();
+    assert_eq!(grid.nearest(0, 0), None);
+    assert!(grid.is_empty());
+}
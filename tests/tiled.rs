@@ -0,0 +1,174 @@
+//tiled.rs
+//Tiled import tests, run with `cargo test --features tiled`
+#![cfg(feature = "tiled")]
+use crossterm::style::Color;
+use rs_cli_renderer::snapshot::assert_snapshot;
+use rs_cli_renderer::stack::Renderer;
+use rs_cli_renderer::tiled::{load_tiled, parse_tiled_json, parse_tmx, ObjectShape};
+use std::path::Path;
+use std::time::Duration;
+
+fn map_path(name: &str) -> String {
+    format!("{}/tests/maps/{}", env!("CARGO_MANIFEST_DIR"), name)
+}
+
+#[test]
+fn tmx_maps_load_layers_tilesets_and_objects() {
+    let level = load_tiled(&map_path("level.tmx"), Duration::from_millis(40)).unwrap();
+    let map = &level.tilemap;
+    assert_eq!((map.width(), map.height(), map.layers().len()), (3, 2, 2));
+    assert!(map.is_solid(0, 0) && !map.is_solid(1, 0) && !map.is_solid(0, 1));
+    assert_eq!(map.get(1, 1, 0), map.tileset.index_of("tiles/1"));
+
+    let spawns = &level.object_layers[0];
+    assert_eq!(spawns.name, "spawns");
+    let player = &spawns.objects[0];
+    assert_eq!(
+        (player.class.as_str(), player.x, player.y),
+        ("spawn", 2.0, 1.0)
+    );
+    assert_eq!(player.shape, ObjectShape::Point);
+    assert_eq!(spawns.objects[1].properties["target"], "cave");
+
+    let mut renderer = Renderer::headless(6, 4, Color::Black);
+    for index in 0..map.layers().len() {
+        let layer = renderer.layerstack.add(index as u16, index as u16);
+        renderer.layerstack.draw_tilemap(map, index, (0, 0), 0, layer);
+    }
+    renderer.render_update();
+    assert_snapshot("tiled_tmx_map", renderer.last_frame());
+}
+
+#[test]
+fn json_maps_load_animations_and_tile_objects() {
+    let level = load_tiled(&map_path("level.json"), Duration::from_millis(40)).unwrap();
+    let map = &level.tilemap;
+    let animated = map.tileset.get(map.get(0, 1, 0).unwrap()).unwrap();
+    assert!(animated.flags.animated);
+    assert_eq!((animated.frames.len(), animated.frame_duration), (2, 5));
+
+    let bat = &level.object_layers[0].objects[0];
+    assert_eq!(bat.tile, map.get(0, 1, 0));
+    assert_eq!(bat.properties["speed"], "3");
+    assert_eq!(
+        bat.shape,
+        ObjectShape::Polygon(vec![(0.0, 0.0), (2.0, 0.0), (1.0, 2.0)])
+    );
+}
+
+#[test]
+fn unsupported_features_are_reported() {
+    let base = Path::new(".");
+    let isometric =
+        r#"<map orientation="isometric" width="1" height="1" tilewidth="2" tileheight="2"/>"#;
+    let error = parse_tmx(isometric, base, Duration::from_millis(40)).err().unwrap();
+    assert!(
+        error.contains("isometric maps are not supported"),
+        "{}",
+        error
+    );
+
+    let compressed = r#"<map orientation="orthogonal" width="1" height="1" tilewidth="2" tileheight="2">
+        <layer name="ground" width="1" height="1"><data encoding="base64" compression="zlib">eJxjYGBgAAAABAAB</data></layer>
+    </map>"#;
+    let error = parse_tmx(compressed, base, Duration::from_millis(40)).err().unwrap();
+    assert!(
+        error.contains("zlib compressed layer data is not supported"),
+        "{}",
+        error
+    );
+
+    //layer settings the engine has no place for
+    for (attributes, feature) in [
+        (r#"visible="0""#, "hidden layers"),
+        (r#"opacity="0.5""#, "layer opacity"),
+        (r#"offsetx="4""#, "layer offsets"),
+        (r#"parallaxy="0.5""#, "layer parallax"),
+    ] {
+        let tmx = format!(
+            r#"<map orientation="orthogonal" width="1" height="1" tilewidth="2" tileheight="2">
+            <layer name="ground" width="1" height="1" {}><data encoding="csv">0</data></layer>
+        </map>"#,
+            attributes
+        );
+        let error = parse_tmx(&tmx, base, Duration::from_millis(40)).err().unwrap();
+        assert!(
+            error.contains(&format!("{} (layer ground) are not supported", feature)),
+            "{}",
+            error
+        );
+    }
+    let hidden = r#"{"orientation":"orthogonal","width":1,"height":1,"tilewidth":2,"tileheight":2,
+        "layers":[{"type":"objectgroup","name":"spawns","visible":false,"objects":[]}]}"#;
+    let error = parse_tiled_json(hidden, base, Duration::from_millis(40)).err().unwrap();
+    assert!(
+        error.contains("hidden layers (layer spawns) are not supported"),
+        "{}",
+        error
+    );
+    //default values are accepted
+    let defaults = r#"<map orientation="orthogonal" width="1" height="1" tilewidth="2" tileheight="2">
+        <layer name="ground" width="1" height="1" visible="1" opacity="1" offsetx="0" parallaxx="1"><data encoding="csv">0</data></layer>
+    </map>"#;
+    assert!(parse_tmx(defaults, base, Duration::from_millis(40)).is_ok());
+
+    //a tick length per tile, so every frame has to last as long
+    let uneven = r#"<map orientation="orthogonal" width="1" height="1" tilewidth="2" tileheight="2">
+        <tileset firstgid="1" name="tiles" tilewidth="2" tileheight="2" tilecount="2" columns="2">
+            <image source="tiles.ppm"/>
+            <tile id="1"><animation><frame tileid="1" duration="200"/><frame tileid="0" duration="100"/></animation></tile>
+        </tileset>
+    </map>"#;
+    let maps = map_path("");
+    let error = parse_tmx(uneven, Path::new(&maps), Duration::from_millis(40)).err().unwrap();
+    assert!(
+        error.contains("tile tiles/1 has frames of different durations"),
+        "{}",
+        error
+    );
+}
+
+#[test]
+fn tilesets_larger_than_their_image_are_rejected() {
+    let maps = map_path("");
+    //tiles.ppm is 4x2, two 2x2 tiles side by side
+    for attributes in [
+        r#"tilecount="3" columns="2""#,
+        r#"tilecount="2" columns="1""#,
+        r#"tilecount="2" columns="2" margin="1""#,
+        r#"tilecount="65535" columns="65535" spacing="65535""#,
+    ] {
+        let tmx = format!(
+            r#"<map orientation="orthogonal" width="1" height="1" tilewidth="2" tileheight="2">
+            <tileset firstgid="1" name="tiles" tilewidth="2" tileheight="2" {}><image source="tiles.ppm"/></tileset>
+        </map>"#,
+            attributes
+        );
+        let error = parse_tmx(&tmx, Path::new(&maps), Duration::from_millis(40)).err().unwrap();
+        assert!(
+            error.contains("tileset tiles is larger than its image"),
+            "{} : {}",
+            attributes,
+            error
+        );
+    }
+}
+
+#[test]
+fn png_tilesets_load_with_the_tiled_feature_alone() {
+    use rs_cli_renderer::image::{load_image, save_sprite_png};
+
+    let dir = std::env::temp_dir().join(format!("rs_cli_renderer_tiled_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let sheet = load_image(&map_path("tiles.ppm"), None).unwrap();
+    save_sprite_png(&sheet, dir.join("tiles.png").to_str().unwrap()).unwrap();
+    let tmx = r#"<map orientation="orthogonal" width="2" height="1" tilewidth="2" tileheight="2">
+        <tileset firstgid="1" name="tiles" tilewidth="2" tileheight="2" tilecount="2" columns="2"><image source="tiles.png"/></tileset>
+        <layer name="ground" width="2" height="1"><data encoding="csv">2,1</data></layer>
+    </map>"#;
+    let level = parse_tmx(tmx, &dir, Duration::from_millis(40)).unwrap();
+    let map = &level.tilemap;
+    assert_eq!(map.get(0, 0, 0), map.tileset.index_of("tiles/1"));
+    assert_eq!(map.tileset.len(), 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
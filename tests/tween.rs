@@ -0,0 +1,116 @@
+//tween.rs
+//easing curves and tweens
+use crossterm::style::Color;
+use rs_cli_renderer::game::tween::{Easing, Lerp, Tween, Tweens};
+use rs_cli_renderer::stack::Renderer;
+use std::cell::Cell;
+use std::rc::Rc;
+
+#[test]
+fn easings_start_at_zero_and_end_at_one() {
+    let easings = [
+        Easing::Linear,
+        Easing::QuadIn,
+        Easing::QuadOut,
+        Easing::QuadInOut,
+        Easing::CubicIn,
+        Easing::CubicOut,
+        Easing::CubicInOut,
+        Easing::ElasticIn,
+        Easing::ElasticOut,
+        Easing::ElasticInOut,
+        Easing::BounceIn,
+        Easing::BounceOut,
+        Easing::BounceInOut,
+    ];
+    for easing in easings {
+        assert!(easing.apply(0.0).abs() < 1e-4, "{:?}", easing);
+        assert!((easing.apply(1.0) - 1.0).abs() < 1e-4, "{:?}", easing);
+    }
+    assert_eq!(Easing::QuadIn.apply(0.5), 0.25);
+    assert_eq!(Easing::CubicInOut.apply(0.5), 0.5);
+    assert!(Easing::ElasticOut.apply(0.2) > 1.0);
+    assert!((Easing::BounceOut.apply(0.5) - 0.765625).abs() < 1e-4);
+    assert_eq!(
+        Color::Black.lerp(
+            Color::Rgb {
+                r: 200,
+                g: 100,
+                b: 0
+            },
+            0.5
+        ),
+        Color::Rgb {
+            r: 100,
+            g: 50,
+            b: 0
+        }
+    );
+    assert_eq!((0i16, 10i16).lerp((10, 0), 0.25), (3, 8));
+}
+
+#[test]
+fn tweens_play_sequences_with_delays_and_repeats() {
+    let mut tween =
+        Tween::new(0.0, 10.0, 2, Easing::Linear).delay(1).wait(1).then(0.0, 1, Easing::Linear);
+    let values: Vec<f32> = (0..6).map(|_| tween.tick()).collect();
+    assert_eq!(values, vec![0.0, 5.0, 10.0, 10.0, 0.0, 0.0]);
+    assert!(tween.is_finished());
+
+    let completed = Rc::new(Cell::new(0));
+    let counter = Rc::clone(&completed);
+    let mut yoyo = Tween::new(0i16, 4, 2, Easing::Linear)
+        .yoyo()
+        .repeat(2)
+        .on_complete(move || counter.set(counter.get() + 1));
+    let values: Vec<i16> = (0..8).map(|_| yoyo.tick()).collect();
+    assert_eq!(values, vec![2, 4, 2, 0, 2, 4, 4, 4]);
+    assert_eq!(completed.get(), 1);
+
+    let mut forever = Tween::new(0.0, 1.0, 2, Easing::Linear).repeat_forever();
+    for _ in 0..100 {
+        forever.tick();
+    }
+    assert!(!forever.is_finished());
+    forever.restart();
+    assert_eq!(forever.value(), 0.0);
+}
+
+#[test]
+fn tweens_drive_layer_properties() {
+    let mut renderer = Renderer::headless(1, 1, Color::Black);
+    let layer = renderer.layerstack.add(0, 0);
+    let mut tweens = Tweens::new();
+    let fade = tweens.fade_layer(layer, Tween::new(0.0, 1.0, 2, Easing::Linear));
+    let seen = Rc::new(Cell::new(Color::Reset));
+    let target = Rc::clone(&seen);
+    tweens.add(
+        Tween::new(Color::Black, Color::White, 4, Easing::Linear),
+        move |color, _| target.set(color),
+    );
+    let colors: Vec<Option<Color>> = (0..2)
+        .map(|_| {
+            tweens.tick(&mut renderer.layerstack);
+            renderer.layerstack.direct_write(0, 0, Color::Rgb { r: 200, g: 0, b: 0 }, layer);
+            renderer.render_update();
+            renderer.last_frame().get(0, 0).map(|pixel| pixel.color)
+        })
+        .collect();
+    assert_eq!(
+        colors,
+        vec![
+            Some(Color::Rgb { r: 100, g: 0, b: 0 }),
+            Some(Color::Rgb { r: 200, g: 0, b: 0 })
+        ]
+    );
+    assert!(!tweens.is_running(fade));
+    assert_eq!(tweens.len(), 1);
+    assert_eq!(
+        seen.get(),
+        Color::Rgb {
+            r: 128,
+            g: 128,
+            b: 128
+        }
+    );
+}